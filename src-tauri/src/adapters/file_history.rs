@@ -52,10 +52,11 @@ impl HistoryRepository for FileHistoryAdapter {
         Ok(actions.into_iter().take(limit).collect())
     }
 
-    async fn record(&self, mut new_action: Action) -> Result<(), String> {
+    async fn record(&self, mut new_action: Action, query: Option<String>) -> Result<(), String> {
         let mut cache = self.cache.lock().map_err(|e| e.to_string())?;
+        let now = new_action.last_accessed;
 
-        if let Some(existing) = cache.iter_mut().find(|a| a.id == new_action.id) {
+        let entry = if let Some(existing) = cache.iter_mut().find(|a| a.id == new_action.id) {
             existing.last_accessed = new_action.last_accessed;
             existing.frequency += 1;
             // Update other fields just in case they changed (dynamic titles etc)
@@ -64,9 +65,15 @@ impl HistoryRepository for FileHistoryAdapter {
             if new_action.icon.is_some() {
                 existing.icon = new_action.icon;
             }
+            existing
         } else {
             new_action.frequency = 1;
+            new_action.associations.clear();
             cache.push(new_action);
+            cache.last_mut().unwrap()
+        };
+        if let Some(query) = query {
+            entry.learn(&query, now);
         }
 
         // Limit total history size to avoid infinte growth
@@ -88,3 +95,47 @@ impl HistoryRepository for FileHistoryAdapter {
         self.save()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn action(id: &str, at: u64) -> Action {
+        Action {
+            id: id.to_string(),
+            kind: "app".to_string(),
+            content: id.to_string(),
+            name: id.to_string(),
+            icon: None,
+            last_accessed: at,
+            frequency: 0,
+            associations: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_learns_queries_and_persists() {
+        let dir = tempdir().unwrap();
+        let history = FileHistoryAdapter::new(dir.path().to_path_buf());
+        history
+            .record(action("app:telegram", 1), Some("te".to_string()))
+            .await
+            .unwrap();
+        history
+            .record(action("app:telegram", 2), Some("te".to_string()))
+            .await
+            .unwrap();
+        history
+            .record(action("app:terminal", 3), None)
+            .await
+            .unwrap();
+
+        let reloaded = FileHistoryAdapter::new(dir.path().to_path_buf());
+        let recent = reloaded.get_recent(10).await.unwrap();
+        assert_eq!(recent[0].id, "app:terminal");
+        assert!(recent[0].associations.is_empty());
+        assert_eq!(recent[1].frequency, 2);
+        assert!(recent[1].affinity("te", 2) > 1.9);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::domain::script_run::RunRecord;
+use crate::ports::run_log_port::RunLog;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Once the log grows past this it is rotated, keeping one older file around.
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Script runs as JSON lines in the app data dir, `script_runs.jsonl` plus the
+/// rotated `script_runs.1.jsonl`.
+pub struct FileRunLog {
+    file_path: PathBuf,
+    rotated_path: PathBuf,
+    max_bytes: u64,
+    // Serializes appends and rotation
+    lock: Mutex<()>,
+}
+
+impl FileRunLog {
+    pub fn new(app_data_dir: PathBuf) -> Self {
+        Self::with_limit(app_data_dir, MAX_LOG_BYTES)
+    }
+
+    fn with_limit(app_data_dir: PathBuf, max_bytes: u64) -> Self {
+        Self {
+            file_path: app_data_dir.join("script_runs.jsonl"),
+            rotated_path: app_data_dir.join("script_runs.1.jsonl"),
+            max_bytes,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Every logged run, oldest first.
+    fn read_all(&self) -> Vec<RunRecord> {
+        [&self.rotated_path, &self.file_path]
+            .iter()
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .flat_map(|content| {
+                content
+                    .lines()
+                    .filter_map(|line| serde_json::from_str(line).ok())
+                    .collect::<Vec<RunRecord>>()
+            })
+            .collect()
+    }
+}
+
+impl RunLog for FileRunLog {
+    fn append(&self, record: &RunRecord) -> Result<(), String> {
+        let _guard = self.lock.lock().map_err(|e| e.to_string())?;
+        if let Some(parent) = self.file_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let size = fs::metadata(&self.file_path).map(|m| m.len()).unwrap_or(0);
+        if size > self.max_bytes {
+            fs::rename(&self.file_path, &self.rotated_path).map_err(|e| e.to_string())?;
+        }
+
+        let mut line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.file_path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| e.to_string())
+    }
+
+    fn list(&self, limit: usize) -> Result<Vec<RunRecord>, String> {
+        Ok(self
+            .read_all()
+            .into_iter()
+            .rev()
+            .take(limit)
+            .map(|mut record| {
+                record.output.clear();
+                record
+            })
+            .collect())
+    }
+
+    fn get(&self, run_id: &str) -> Result<Option<RunRecord>, String> {
+        Ok(self
+            .read_all()
+            .into_iter()
+            .rev()
+            .find(|record| record.run_id == run_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::script_run::RunStatus;
+    use tempfile::tempdir;
+
+    fn record(run_id: &str, output: &str) -> RunRecord {
+        RunRecord {
+            run_id: run_id.to_string(),
+            script_id: Some("backup".to_string()),
+            path: "/scripts/backup.sh".to_string(),
+            args: vec!["--all".to_string()],
+            started_at: 1,
+            ended_at: 2,
+            code: Some(0),
+            status: RunStatus::Success,
+            output: output.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_lists_newest_first_without_output() {
+        let dir = tempdir().unwrap();
+        let log = FileRunLog::new(dir.path().to_path_buf());
+        log.append(&record("run-1", "one\n")).unwrap();
+        log.append(&record("run-2", "two\n")).unwrap();
+
+        let runs = log.list(10).unwrap();
+        assert_eq!(runs[0].run_id, "run-2");
+        assert_eq!(runs[1].run_id, "run-1");
+        assert!(runs.iter().all(|run| run.output.is_empty()));
+
+        let run = log.get("run-1").unwrap().unwrap();
+        assert_eq!(run.output, "one\n");
+        assert_eq!(run.args, vec!["--all"]);
+        assert!(log.get("run-9").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_rotation_keeps_one_older_file() {
+        let dir = tempdir().unwrap();
+        let log = FileRunLog::with_limit(dir.path().to_path_buf(), 100);
+        let output = "x".repeat(150);
+        for i in 1..=4 {
+            log.append(&record(&format!("run-{}", i), &output)).unwrap();
+        }
+
+        // Each append past the limit rotates, so only the last two runs survive
+        let ids: Vec<String> = log
+            .list(10)
+            .unwrap()
+            .into_iter()
+            .map(|run| run.run_id)
+            .collect();
+        assert_eq!(ids, vec!["run-4", "run-3"]);
+    }
+}
@@ -1,14 +1,50 @@
-use crate::domain::apps::AppEntry;
+use crate::domain::apps::{AppAction, AppEntry};
+use crate::domain::desktop_entry::{DesktopFile, Locale};
+use crate::domain::desktop_exec::quote_exec_arg;
+use crate::domain::files::expand_tilde;
 use crate::ports::app_port::AppRepository;
 use crate::ports::icon_port::IconResolver;
-use freedesktop_desktop_entry::Iter;
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 pub struct FsAppRepository {
     icon_resolver: Arc<dyn IconResolver>,
     custom_paths: Option<Vec<PathBuf>>,
+    /// Names and keywords are translated for this locale.
+    locale: Option<Locale>,
+    /// `XDG_CURRENT_DESKTOP`, for `OnlyShowIn` and `NotShowIn`.
+    desktops: Vec<String>,
+}
+
+/// `XDG_CURRENT_DESKTOP`, split on `:`.
+pub fn current_desktops() -> Vec<String> {
+    std::env::var("XDG_CURRENT_DESKTOP")
+        .map(|value| {
+            value
+                .split(':')
+                .filter(|name| !name.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Whether `TryExec` names an installed program: an executable path, or a name
+/// found on `PATH`.
+fn program_exists(program: &str) -> bool {
+    let is_executable = |path: &Path| {
+        path.metadata()
+            .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    };
+    if program.contains('/') {
+        return is_executable(&expand_tilde(program));
+    }
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| is_executable(&dir.join(program))))
+        .unwrap_or(false)
 }
 
 impl FsAppRepository {
@@ -16,6 +52,8 @@ impl FsAppRepository {
         Self {
             icon_resolver,
             custom_paths: None,
+            locale: Locale::from_env(),
+            desktops: current_desktops(),
         }
     }
 
@@ -24,136 +62,179 @@ impl FsAppRepository {
         Self {
             icon_resolver,
             custom_paths: Some(paths),
+            locale: None,
+            desktops: Vec::new(),
         }
     }
 
-    fn parse_desktop_file(&self, path: &std::path::Path) -> Option<AppEntry> {
+    fn resolve_icon(&self, icon: &str) -> Option<String> {
+        self.icon_resolver
+            .resolve_icon(icon)
+            .filter(|icon| !icon.is_empty())
+    }
+
+    /// Reads an `Application` or `Link` entry. Entries that are hidden, meant for
+    /// other desktops or whose `TryExec` program is missing are left out; `NoDisplay`
+    /// ones are kept, flagged, since they may still open files.
+    fn parse_desktop_file(&self, id: String, path: &Path) -> Option<AppEntry> {
         let content = std::fs::read_to_string(path).ok()?;
-        let mut in_desktop_entry = false;
-        let mut name = None;
-        let mut exec = None;
-        let mut icon = None;
-        let mut no_display = false;
-
-        for line in content.lines() {
-            let line = line.trim();
-            if line.starts_with('[') && line.ends_with(']') {
-                in_desktop_entry = line == "[Desktop Entry]";
-                continue;
+        let file = DesktopFile::parse(&content);
+        let entry = file.entry()?;
+        let locale = self.locale.as_ref();
+
+        let (exec, url) = match entry.string("Type").as_deref() {
+            // Type is required, but older files often leave it out
+            Some("Application") | None => (entry.string("Exec")?, None),
+            Some("Link") => {
+                let url = entry.string("URL")?;
+                (format!("xdg-open {}", quote_exec_arg(&url)), Some(url))
             }
+            Some(_) => return None,
+        };
+        let icon = entry
+            .string("Icon")
+            .and_then(|icon| self.resolve_icon(&icon));
+        let actions = entry
+            .list("Actions")
+            .into_iter()
+            .filter_map(|id| {
+                let group = file.group(&format!("Desktop Action {}", id))?;
+                Some(AppAction {
+                    name: group.localized("Name", locale)?,
+                    // Actions without Exec are only reachable over D-Bus
+                    exec: group.string("Exec")?,
+                    icon: group
+                        .string("Icon")
+                        .and_then(|icon| self.resolve_icon(&icon))
+                        .or_else(|| icon.clone()),
+                    id,
+                })
+            })
+            .collect();
 
-            if in_desktop_entry {
-                if let Some(val) = line.strip_prefix("Name=") {
-                    if name.is_none() {
-                        name = Some(val.to_string());
-                    }
-                } else if let Some(val) = line.strip_prefix("Exec=") {
-                    if exec.is_none() {
-                        // Cleanup exec command (remove field codes)
-                        let clean_exec = val
-                            .replace("%f", "")
-                            .replace("%F", "")
-                            .replace("%u", "")
-                            .replace("%U", "")
-                            .replace("%i", "")
-                            .replace("%c", "")
-                            .replace("%k", "")
-                            .trim()
-                            .to_string();
-                        exec = Some(clean_exec);
-                    }
-                } else if let Some(val) = line.strip_prefix("Icon=") {
-                    if icon.is_none() {
-                        icon = Some(val.to_string());
-                    }
-                } else if let Some(val) = line.strip_prefix("NoDisplay=") {
-                    if val.to_lowercase() == "true" {
-                        no_display = true;
-                    }
-                }
+        let app = AppEntry {
+            id,
+            name: entry.localized("Name", locale)?,
+            exec,
+            icon,
+            desktop_file: Some(path.display().to_string()),
+            generic_name: entry.localized("GenericName", locale),
+            comment: entry.localized("Comment", locale),
+            keywords: entry.localized_list("Keywords", locale),
+            categories: entry.list("Categories"),
+            mime_types: entry.list("MimeType"),
+            try_exec: entry.string("TryExec"),
+            hidden: entry.boolean("Hidden"),
+            no_display: entry.boolean("NoDisplay"),
+            only_show_in: entry.list("OnlyShowIn"),
+            not_show_in: entry.list("NotShowIn"),
+            url,
+            actions,
+        };
+        if !app.shown_in(&self.desktops) {
+            return None;
+        }
+        if let Some(try_exec) = &app.try_exec {
+            if !program_exists(try_exec) {
+                return None;
             }
         }
+        Some(app)
+    }
+}
 
-        if !no_display {
-            if let (Some(name), Some(exec)) = (name, exec) {
-                // Check if icon resolves
-                // If icon is None, maybe we can try to guess from name or use default?
-                // lib.rs logic: icon.and_then(|i| resolve_icon(&i))
-                let icon_path = if let Some(i) = icon {
-                    self.icon_resolver.resolve_icon(&i)
-                } else {
-                    None
-                };
-
-                return Some(AppEntry {
-                    name,
-                    exec,
-                    icon: icon_path.filter(|i| !i.is_empty()),
-                });
+/// Desktop files under an `applications` dir with their desktop-file ids: the
+/// path below the dir with `/` turned into `-`, e.g. `kde4/konsole.desktop` is
+/// `kde4-konsole.desktop`. Sorted so ids resolve the same way every time.
+/// Symlinked directories aren't followed, so a link back up can't loop; linked
+/// desktop files are read as usual.
+fn desktop_files(dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut files = Vec::new();
+    let mut pending = vec![(dir.to_path_buf(), String::new())];
+    while let Some((dir, prefix)) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                pending.push((path, format!("{}{}-", prefix, name)));
+            } else if name.ends_with(".desktop") {
+                files.push((format!("{}{}", prefix, name), path));
             }
         }
-        None
     }
+    files.sort();
+    files
+}
+
+/// The `applications` dirs in the order the spec gives them precedence:
+/// `XDG_DATA_HOME`, then each of `XDG_DATA_DIRS`, then Flatpak's exports in case
+/// they aren't on `XDG_DATA_DIRS`.
+fn application_dirs() -> Vec<PathBuf> {
+    let flatpak = [
+        dirs::data_local_dir().map(|dir| dir.join("flatpak/exports/share")),
+        Some(PathBuf::from("/var/lib/flatpak/exports/share")),
+    ];
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for dir in xdg_data_dirs()
+        .into_iter()
+        .chain(flatpak.into_iter().flatten())
+    {
+        let dir = dir.join("applications");
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+/// `var` split on `:`, or `default` when unset.
+pub fn dirs_from_env(var: &str, default: &str) -> Vec<PathBuf> {
+    std::env::var(var)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| default.to_string())
+        .split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// `XDG_DATA_HOME` followed by `XDG_DATA_DIRS`, with the spec's defaults.
+pub fn xdg_data_dirs() -> Vec<PathBuf> {
+    dirs::data_local_dir()
+        .into_iter()
+        .chain(dirs_from_env(
+            "XDG_DATA_DIRS",
+            "/usr/local/share:/usr/share",
+        ))
+        .collect()
 }
 
 impl AppRepository for FsAppRepository {
+    /// Each desktop-file id is taken from the first dir that has it, so a copy in
+    /// `~/.local/share/applications` overrides (or, with `Hidden=true`, removes)
+    /// the system one.
     fn list_apps(&self) -> Result<Vec<AppEntry>, String> {
         let mut apps = Vec::new();
         let mut seen_ids = HashSet::new();
 
-        // Construct search paths
-        // Construct search paths
-        let mut search_paths = Vec::new();
-
-        if let Some(ref custom) = self.custom_paths {
-            search_paths.extend(custom.clone());
-        } else {
-            if let Ok(dirs) = std::env::var("XDG_DATA_DIRS") {
-                for dir in dirs.split(':') {
-                    search_paths.push(PathBuf::from(dir).join("applications"));
-                }
-            } else {
-                search_paths.push(PathBuf::from("/usr/share/applications"));
-                search_paths.push(PathBuf::from("/usr/local/share/applications"));
-            }
-            if let Some(home) = dirs::data_local_dir() {
-                search_paths.push(home.join("applications"));
-            }
-        }
-
-        // Iterate over paths using the crate's iterator
-        for path in Iter::new(search_paths.clone().into_iter()) {
-            if let Some(app) = self.parse_desktop_file(&path) {
-                let id = app.name.clone();
-                if !seen_ids.contains(&id) {
-                    apps.push(app);
-                    seen_ids.insert(id);
+        let search_paths = match &self.custom_paths {
+            Some(custom) => custom.clone(),
+            None => application_dirs(),
+        };
+        for dir in &search_paths {
+            for (id, path) in desktop_files(dir) {
+                if !seen_ids.insert(id.clone()) {
+                    continue;
                 }
+                apps.extend(self.parse_desktop_file(id, &path));
             }
         }
 
-        // 2. Scan Flatpak Exports explicitly if available (SKIP IF TESTING)
-        let mut flatpak_paths = Vec::new();
-        if self.custom_paths.is_none() {
-            flatpak_paths.push(PathBuf::from("/var/lib/flatpak/exports/share/applications"));
-            flatpak_paths.push(
-                dirs::data_local_dir()
-                    .map(|d| d.join("flatpak/exports/share/applications"))
-                    .unwrap_or_default(),
-            );
-        }
-
-        for path in Iter::new(flatpak_paths.into_iter()) {
-            if let Some(app) = self.parse_desktop_file(&path) {
-                let id = app.name.clone();
-                if !seen_ids.contains(&id) {
-                    apps.push(app);
-                    seen_ids.insert(id);
-                }
-            }
-        }
-
-        // 3. Scan AppImages
+        // AppImages in ~/Applications, unless a desktop file already runs them
         if let Some(home) = dirs::home_dir() {
             let applications_dir = home.join("Applications");
             if applications_dir.exists() && self.custom_paths.is_none() {
@@ -168,7 +249,9 @@ impl AppRepository for FsAppRepository {
                         if name.is_empty() {
                             continue;
                         }
-                        if seen_ids.contains(&name) {
+                        let exec = quote_exec_arg(&entry.to_string_lossy());
+                        let path = entry.to_string_lossy();
+                        if apps.iter().any(|app| app.exec.contains(path.as_ref())) {
                             continue;
                         }
 
@@ -177,11 +260,16 @@ impl AppRepository for FsAppRepository {
                         });
 
                         apps.push(AppEntry {
-                            name: name.clone(),
-                            exec: entry.to_string_lossy().to_string(),
+                            id: entry
+                                .file_name()
+                                .unwrap_or_default()
+                                .to_string_lossy()
+                                .to_string(),
+                            name,
+                            exec,
                             icon,
+                            ..Default::default()
                         });
-                        seen_ids.insert(name);
                     }
                 }
             }
@@ -228,12 +316,12 @@ mod tests {
         // Assertions
         assert_eq!(apps.len(), 1);
         assert_eq!(apps[0].name, "Test App");
-        assert_eq!(apps[0].exec, "test-exec");
+        assert_eq!(apps[0].exec, "test-exec %f");
         assert_eq!(apps[0].icon, Some("/tmp/icon.png".to_string()));
     }
 
     #[test]
-    fn test_list_apps_ignores_no_display() {
+    fn test_list_apps_flags_no_display() {
         let dir = tempdir().unwrap();
         let apps_dir = dir.path().join("applications");
         std::fs::create_dir(&apps_dir).unwrap();
@@ -251,6 +339,141 @@ mod tests {
 
         let apps = repo.list_apps().unwrap();
 
-        assert!(apps.is_empty());
+        // Kept for MIME handling, flagged so menus and search leave it out
+        assert_eq!(apps.len(), 1);
+        assert!(apps[0].no_display);
+    }
+
+    #[test]
+    fn test_list_apps_follows_spec_fields() {
+        let dir = tempdir().unwrap();
+        let apps_dir = dir.path().join("applications");
+        std::fs::create_dir(&apps_dir).unwrap();
+        let write = |name: &str, content: &str| {
+            std::fs::write(apps_dir.join(name), content).unwrap();
+        };
+        write(
+            "firefox.desktop",
+            "[Desktop Entry]\nType=Application\nName=Firefox\nName[de]=Firefox Webbrowser\n\
+             GenericName=Web Browser\nGenericName[de]=Webbrowser\nKeywords=Internet;WWW;\n\
+             Categories=Network;WebBrowser;\nExec=firefox %u\nTryExec=sh\n\
+             Actions=new-private-window;dbus-only;missing;\n\n\
+             [Desktop Action new-private-window]\nName=New Private Window\n\
+             Name[de]=Neues privates Fenster\nExec=firefox --private-window %u\n\n\
+             [Desktop Action dbus-only]\nName=Only over D-Bus\n",
+        );
+        write(
+            "docs.desktop",
+            "[Desktop Entry]\nType=Link\nName=Docs\nURL=https://example.com/docs\n",
+        );
+        write(
+            "kde-only.desktop",
+            "[Desktop Entry]\nType=Application\nName=Konsole\nExec=konsole\nOnlyShowIn=KDE;\n",
+        );
+        write(
+            "not-gnome.desktop",
+            "[Desktop Entry]\nType=Application\nName=Xterm\nExec=xterm\nNotShowIn=GNOME;\n",
+        );
+        write(
+            "missing.desktop",
+            "[Desktop Entry]\nType=Application\nName=Missing\nExec=missing\nTryExec=/nonexistent/missing\n",
+        );
+        write(
+            "deleted.desktop",
+            "[Desktop Entry]\nType=Application\nName=Deleted\nExec=deleted\nHidden=true\n",
+        );
+        write(
+            "folder.desktop",
+            "[Desktop Entry]\nType=Directory\nName=Folder\n",
+        );
+
+        let mut repo =
+            FsAppRepository::new_with_paths(Arc::new(MockIconResolver), vec![apps_dir.clone()]);
+        repo.locale = Locale::parse("de_DE.UTF-8");
+        repo.desktops = vec!["GNOME".to_string()];
+        let mut apps = repo.list_apps().unwrap();
+        apps.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let names: Vec<&str> = apps.iter().map(|app| app.name.as_str()).collect();
+        assert_eq!(names, vec!["Docs", "Firefox Webbrowser"]);
+        assert_eq!(apps[0].exec, "xdg-open https://example.com/docs");
+        assert_eq!(apps[0].url.as_deref(), Some("https://example.com/docs"));
+        let firefox = &apps[1];
+        assert_eq!(firefox.exec, "firefox %u");
+        assert_eq!(firefox.generic_name.as_deref(), Some("Webbrowser"));
+        assert_eq!(firefox.keywords, vec!["Internet", "WWW"]);
+        assert_eq!(firefox.categories, vec!["Network", "WebBrowser"]);
+        assert_eq!(
+            firefox.actions,
+            vec![AppAction {
+                id: "new-private-window".to_string(),
+                name: "Neues privates Fenster".to_string(),
+                exec: "firefox --private-window %u".to_string(),
+                icon: None,
+            }]
+        );
+
+        // Under KDE the Konsole entry shows up, the GNOME exclusion doesn't apply
+        repo.desktops = vec!["KDE".to_string()];
+        let apps = repo.list_apps().unwrap();
+        assert!(apps.iter().any(|app| app.name == "Konsole"));
+        assert!(apps.iter().any(|app| app.name == "Xterm"));
+    }
+
+    #[test]
+    fn test_list_apps_keys_apps_by_desktop_file_id() {
+        let dir = tempdir().unwrap();
+        let user = dir.path().join("user");
+        let system = dir.path().join("system");
+        std::fs::create_dir_all(system.join("kde4")).unwrap();
+        std::fs::create_dir_all(&user).unwrap();
+        let entry = |name: &str, exec: &str| {
+            format!(
+                "[Desktop Entry]\nType=Application\nName={}\nExec={}\n",
+                name, exec
+            )
+        };
+        std::fs::write(system.join("editor.desktop"), entry("Editor", "editor")).unwrap();
+        std::fs::write(system.join("removed.desktop"), entry("Removed", "removed")).unwrap();
+        std::fs::write(
+            system.join("other-editor.desktop"),
+            entry("Editor", "other"),
+        )
+        .unwrap();
+        std::fs::write(
+            system.join("kde4/konsole.desktop"),
+            entry("Konsole", "konsole"),
+        )
+        .unwrap();
+        // A link back up must not send the walk in circles
+        std::os::unix::fs::symlink(&system, system.join("kde4/loop")).unwrap();
+        // User copies shadow the system ones, even when they hide the app
+        std::fs::write(
+            user.join("editor.desktop"),
+            entry("Editor", "editor --user"),
+        )
+        .unwrap();
+        std::fs::write(
+            user.join("removed.desktop"),
+            "[Desktop Entry]\nType=Application\nName=Removed\nExec=removed\nHidden=true\n",
+        )
+        .unwrap();
+
+        let repo = FsAppRepository::new_with_paths(Arc::new(MockIconResolver), vec![user, system]);
+        let mut apps = repo.list_apps().unwrap();
+        apps.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let ids: Vec<(&str, &str)> = apps
+            .iter()
+            .map(|app| (app.id.as_str(), app.exec.as_str()))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                ("editor.desktop", "editor --user"),
+                ("kde4-konsole.desktop", "konsole"),
+                ("other-editor.desktop", "other"),
+            ]
+        );
     }
 }
@@ -0,0 +1,1261 @@
+use crate::domain::config::FileSearchConfig;
+use crate::domain::content_search::{looks_binary, ContentMatch, TextMatcher};
+use crate::domain::file_query::FileQuery;
+use crate::domain::files::{
+    expand_tilde, rank_matches, FileMatch, IndexState, IndexStatus, IndexedFile,
+};
+use crate::ports::file_index_port::FileIndex;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::WalkBuilder;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_MAX_DEPTH: usize = 10;
+const SAVE_DEBOUNCE: Duration = Duration::from_secs(5);
+/// Index entries scanned between cancellation checks and streamed batches.
+const SEARCH_CHUNK: usize = 4096;
+/// Content search skips files larger than this.
+const MAX_GREP_FILE_SIZE: u64 = 4 * 1024 * 1024;
+/// Bytes inspected for NUL bytes before a file is treated as text.
+const BINARY_SNIFF_LEN: usize = 8192;
+/// Matching lines reported per file, so one huge log can't crowd out everything else.
+const MAX_HITS_PER_FILE: usize = 20;
+
+type WatchEvent = notify::Result<notify::Event>;
+
+/// Takes a non-recursive watch per indexed directory; inotify in the app.
+trait DirWatcher: Send {
+    fn watch_dir(&mut self, dir: &Path) -> Result<(), String>;
+}
+
+impl DirWatcher for RecommendedWatcher {
+    fn watch_dir(&mut self, dir: &Path) -> Result<(), String> {
+        self.watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// A search root with every option resolved against the global defaults.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct RootSettings {
+    name: String,
+    path: PathBuf,
+    max_depth: usize,
+    include_hidden: bool,
+    respect_ignore_files: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+struct IndexSettings {
+    roots: Vec<RootSettings>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+impl IndexSettings {
+    fn from_config(config: &FileSearchConfig) -> Self {
+        let mut roots: Vec<RootSettings> = Vec::new();
+        for root in config.roots.iter().filter(|r| !r.path.trim().is_empty()) {
+            let path = expand_tilde(root.path.trim());
+            if roots.iter().any(|r| r.path == path) {
+                continue;
+            }
+            roots.push(RootSettings {
+                name: root.name.clone(),
+                path,
+                max_depth: root.max_depth.unwrap_or(DEFAULT_MAX_DEPTH),
+                include_hidden: root.include_hidden.unwrap_or(config.include_hidden),
+                respect_ignore_files: root
+                    .respect_ignore_files
+                    .unwrap_or(config.respect_ignore_files),
+            });
+        }
+        if roots.is_empty() {
+            roots.extend(dirs::home_dir().map(|path| RootSettings {
+                name: "Home".to_string(),
+                path,
+                max_depth: DEFAULT_MAX_DEPTH,
+                include_hidden: config.include_hidden,
+                respect_ignore_files: config.respect_ignore_files,
+            }));
+        }
+        Self {
+            roots,
+            exclude: config.exclude.clone(),
+        }
+    }
+
+    /// Other roots that live inside `root`; their files belong to them, not to `root`.
+    fn nested_in(&self, root: &RootSettings) -> Vec<PathBuf> {
+        self.roots
+            .iter()
+            .filter(|other| other.path != root.path && other.path.starts_with(&root.path))
+            .map(|other| other.path.clone())
+            .collect()
+    }
+}
+
+/// Settings plus the per-root exclude matchers, compiled once.
+struct IndexRules {
+    settings: IndexSettings,
+    excludes: Vec<Gitignore>,
+    nested: Vec<Vec<PathBuf>>,
+}
+
+impl IndexRules {
+    fn new(settings: IndexSettings) -> Self {
+        let excludes = settings
+            .roots
+            .iter()
+            .map(|root| {
+                let mut builder = GitignoreBuilder::new(&root.path);
+                for pattern in &settings.exclude {
+                    let _ = builder.add_line(None, pattern);
+                }
+                builder.build().unwrap_or_else(|_| Gitignore::empty())
+            })
+            .collect();
+        let nested = settings
+            .roots
+            .iter()
+            .map(|root| settings.nested_in(root))
+            .collect();
+        Self {
+            settings,
+            excludes,
+            nested,
+        }
+    }
+
+    /// The innermost root containing `path`.
+    fn root_of(&self, path: &Path) -> Option<(usize, &RootSettings)> {
+        self.settings
+            .roots
+            .iter()
+            .enumerate()
+            .filter(|(_, root)| path.starts_with(&root.path))
+            .max_by_key(|(_, root)| root.path.components().count())
+    }
+
+    /// Whether `path` belongs in the index according to the depth, hidden and exclude rules.
+    /// Ignore files are not consulted here; see `IndexInner::is_listed`.
+    fn accepts(&self, path: &Path, is_dir: bool) -> bool {
+        let (i, root) = match self.root_of(path) {
+            Some(found) => found,
+            None => return false,
+        };
+        let relative = path.strip_prefix(&root.path).unwrap_or(path);
+        let mut depth = 0;
+        for component in relative.components() {
+            depth += 1;
+            let hidden = component.as_os_str().to_string_lossy().starts_with('.');
+            if hidden && !root.include_hidden {
+                return false;
+            }
+        }
+        depth > 0
+            && depth <= root.max_depth
+            && !self.excludes[i]
+                .matched_path_or_any_parents(path, is_dir)
+                .is_ignore()
+    }
+
+    /// A ripgrep-style walker over `start`, which must lie inside root number `root_idx`.
+    /// Nested roots are left out; they are crawled with their own settings.
+    fn walker(&self, root_idx: usize, start: &Path, max_depth: usize) -> ignore::Walk {
+        let root = &self.settings.roots[root_idx];
+        let respect = root.respect_ignore_files;
+        let excludes = self.excludes[root_idx].clone();
+        let nested = self.nested[root_idx].clone();
+        WalkBuilder::new(start)
+            .max_depth(Some(max_depth))
+            .hidden(!root.include_hidden)
+            .parents(respect)
+            .ignore(respect)
+            .git_ignore(respect)
+            .git_global(respect)
+            .git_exclude(respect)
+            .filter_entry(move |entry| {
+                let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                !excludes.matched(entry.path(), is_dir).is_ignore()
+                    && !nested.iter().any(|n| n == entry.path())
+            })
+            .build()
+    }
+}
+
+#[derive(Deserialize)]
+struct IndexSnapshot {
+    settings: IndexSettings,
+    entries: Vec<IndexedFile>,
+}
+
+#[derive(Serialize)]
+struct IndexSnapshotRef<'a> {
+    settings: &'a IndexSettings,
+    entries: Vec<&'a IndexedFile>,
+}
+
+struct IndexInner {
+    store_path: PathBuf,
+    entries: RwLock<BTreeMap<String, IndexedFile>>,
+    settings: RwLock<IndexSettings>,
+    state: RwLock<IndexState>,
+    // Bumped on every restart so stale crawls and watchers stop on their own.
+    generation: AtomicU64,
+    dirty: AtomicBool,
+    watcher: Mutex<Option<Box<dyn DirWatcher>>>,
+    // Directories that couldn't be watched, and why the first one failed
+    unwatched: AtomicUsize,
+    watch_error: Mutex<Option<String>>,
+}
+
+/// File index persisted under the app data dir and kept fresh through inotify.
+pub struct FsFileIndex {
+    inner: Arc<IndexInner>,
+}
+
+impl FsFileIndex {
+    pub fn new(app_data_dir: PathBuf) -> Self {
+        let store_path = app_data_dir.join("file_index.json");
+        let snapshot = fs::File::open(&store_path).ok().and_then(|file| {
+            serde_json::from_reader::<_, IndexSnapshot>(BufReader::new(file)).ok()
+        });
+
+        let (settings, entries) = match snapshot {
+            Some(snapshot) => (
+                snapshot.settings,
+                snapshot
+                    .entries
+                    .into_iter()
+                    .map(|entry| (entry.path.clone(), entry))
+                    .collect(),
+            ),
+            None => (IndexSettings::default(), BTreeMap::new()),
+        };
+
+        Self {
+            inner: Arc::new(IndexInner {
+                store_path,
+                entries: RwLock::new(entries),
+                settings: RwLock::new(settings),
+                state: RwLock::new(IndexState::Idle),
+                generation: AtomicU64::new(0),
+                dirty: AtomicBool::new(false),
+                watcher: Mutex::new(None),
+                unwatched: AtomicUsize::new(0),
+                watch_error: Mutex::new(None),
+            }),
+        }
+    }
+
+    #[cfg(test)]
+    fn build_now(&self, config: &FileSearchConfig) {
+        *self.inner.settings.write().unwrap() = IndexSettings::from_config(config);
+        let generation = self.inner.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        self.inner.crawl(generation);
+    }
+}
+
+impl IndexInner {
+    fn is_current(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::SeqCst) == generation
+    }
+
+    fn rules(&self) -> IndexRules {
+        IndexRules::new(self.settings.read().unwrap().clone())
+    }
+
+    /// Full crawl of every root, one thread per root.
+    /// Returns false if a newer generation took over meanwhile.
+    fn crawl(&self, generation: u64) -> bool {
+        let rules = self.rules();
+        let mut fresh = BTreeMap::new();
+        let crawled: Vec<BTreeMap<String, IndexedFile>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = rules
+                .settings
+                .roots
+                .iter()
+                .map(|root| {
+                    let rules = &rules;
+                    scope.spawn(move || {
+                        let mut found = BTreeMap::new();
+                        self.crawl_into(rules, &root.path, &mut found);
+                        found
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap_or_default())
+                .collect()
+        });
+        for found in crawled {
+            fresh.extend(found);
+        }
+        if !self.is_current(generation) {
+            return false;
+        }
+
+        *self.entries.write().unwrap() = fresh;
+        *self.state.write().unwrap() = IndexState::Ready;
+        self.save();
+        true
+    }
+
+    fn rebuild(&self, generation: u64) {
+        // The crawl watches each directory as it enters it, so changes made while it
+        // runs are queued, not lost.
+        let (tx, rx) = mpsc::channel();
+        self.unwatched.store(0, Ordering::SeqCst);
+        *self.watch_error.lock().unwrap() = None;
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        });
+        *self.watcher.lock().unwrap() = match watcher {
+            Ok(watcher) => Some(Box::new(watcher)),
+            Err(e) => {
+                *self.watch_error.lock().unwrap() = Some(e.to_string());
+                None
+            }
+        };
+
+        if self.crawl(generation) {
+            self.process_events(generation, rx);
+        }
+    }
+
+    fn process_events(&self, generation: u64, rx: Receiver<WatchEvent>) {
+        let mut last_save = Instant::now();
+        loop {
+            match rx.recv_timeout(SAVE_DEBOUNCE) {
+                Ok(Ok(event)) => {
+                    if !self.is_current(generation) {
+                        return;
+                    }
+                    self.apply_event(&event);
+                }
+                Ok(Err(_)) => {}
+                Err(RecvTimeoutError::Timeout) => {
+                    if !self.is_current(generation) {
+                        return;
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+
+            if last_save.elapsed() >= SAVE_DEBOUNCE && self.dirty.swap(false, Ordering::SeqCst) {
+                self.save();
+                last_save = Instant::now();
+            }
+        }
+    }
+
+    fn apply_event(&self, event: &notify::Event) {
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        for path in &event.paths {
+            self.refresh_path(path);
+        }
+    }
+
+    /// Brings the index entry for `path` (and its subtree) in line with the filesystem.
+    fn refresh_path(&self, path: &Path) {
+        let rules = self.rules();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let respects_ignore = rules
+            .root_of(path)
+            .is_some_and(|(_, root)| root.respect_ignore_files);
+        if respects_ignore && (name == ".gitignore" || name == ".ignore") {
+            // Ignore rules changed: re-evaluate everything they govern
+            if let Some(parent) = path.parent().filter(|p| self.is_indexed_dir(&rules, p)) {
+                self.recrawl_dir(&rules, parent);
+                self.dirty.store(true, Ordering::SeqCst);
+            }
+            return;
+        }
+
+        let meta = fs::symlink_metadata(path);
+        let is_dir = meta.as_ref().map(|m| m.is_dir()).unwrap_or(false);
+        if !rules.accepts(path, is_dir) {
+            return;
+        }
+        let key = path.to_string_lossy().to_string();
+
+        match meta {
+            Ok(meta) => {
+                if !self.is_listed(&rules, path) {
+                    return;
+                } else if meta.is_dir() && !self.entries.read().unwrap().contains_key(&key) {
+                    // New or moved-in directory: pick up everything below it too.
+                    let mut found = BTreeMap::new();
+                    self.crawl_into(&rules, path, &mut found);
+                    self.entries.write().unwrap().extend(found);
+                } else {
+                    self.entries
+                        .write()
+                        .unwrap()
+                        .insert(key, indexed_file(path, &meta));
+                }
+            }
+            Err(_) => remove_subtree(&mut self.entries.write().unwrap(), &key, true),
+        }
+
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether a crawl would have reached `path`: its parent must be indexed (so it is not
+    /// inside an ignored directory) and, when honoring ignore files, the walker must list it.
+    fn is_listed(&self, rules: &IndexRules, path: &Path) -> bool {
+        let (parent, (root_idx, root)) = match (path.parent(), rules.root_of(path)) {
+            (Some(parent), Some(found)) => (parent, found),
+            _ => return false,
+        };
+        if !self.is_indexed_dir(rules, parent) {
+            return false;
+        }
+        if !root.respect_ignore_files {
+            return true;
+        }
+        rules
+            .walker(root_idx, parent, 1)
+            .filter_map(|e| e.ok())
+            .any(|entry| entry.depth() == 1 && entry.path() == path)
+    }
+
+    /// A root, or a directory that made it into the index.
+    fn is_indexed_dir(&self, rules: &IndexRules, dir: &Path) -> bool {
+        rules.settings.roots.iter().any(|root| root.path == dir)
+            || self
+                .entries
+                .read()
+                .unwrap()
+                .contains_key(dir.to_string_lossy().as_ref())
+    }
+
+    fn recrawl_dir(&self, rules: &IndexRules, dir: &Path) {
+        let mut found = BTreeMap::new();
+        self.crawl_into(rules, dir, &mut found);
+        let mut entries = self.entries.write().unwrap();
+        remove_subtree(&mut entries, &dir.to_string_lossy(), false);
+        entries.extend(found);
+    }
+
+    /// Walks `start` (a root or a directory below one), records every accepted entry
+    /// and watches each directory the walk descends into.
+    fn crawl_into(
+        &self,
+        rules: &IndexRules,
+        start: &Path,
+        out: &mut BTreeMap<String, IndexedFile>,
+    ) {
+        let (root_idx, root) = match rules.root_of(start) {
+            Some(found) => found,
+            None => return,
+        };
+        let offset = start
+            .strip_prefix(&root.path)
+            .map(|rel| rel.components().count())
+            .unwrap_or(0);
+        if offset > root.max_depth {
+            return;
+        }
+
+        for entry in rules
+            .walker(root_idx, start, root.max_depth - offset)
+            .filter_map(|e| e.ok())
+        {
+            // Directories at the depth limit are listed but not entered
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            if is_dir && offset + entry.depth() < root.max_depth {
+                self.watch_dir(entry.path());
+            }
+            if entry.depth() == 0 && offset == 0 {
+                continue; // The root itself is not a search result
+            }
+            if let Ok(meta) = entry.metadata() {
+                let file = indexed_file(entry.path(), &meta);
+                out.insert(file.path.clone(), file);
+            }
+        }
+    }
+
+    fn watch_dir(&self, dir: &Path) {
+        let mut watcher = self.watcher.lock().unwrap();
+        let Some(watcher) = watcher.as_mut() else {
+            return;
+        };
+        if let Err(e) = watcher.watch_dir(dir) {
+            // Typically `fs.inotify.max_user_watches` running out
+            if self.unwatched.fetch_add(1, Ordering::SeqCst) == 0 {
+                *self.watch_error.lock().unwrap() = Some(format!("{}: {}", dir.display(), e));
+            }
+        }
+    }
+
+    fn save(&self) {
+        let settings = self.settings.read().unwrap();
+        let entries = self.entries.read().unwrap();
+        let snapshot = IndexSnapshotRef {
+            settings: &settings,
+            entries: entries.values().collect(),
+        };
+
+        if let Some(parent) = self.store_path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        // Write to a temp file first so a crash never leaves a truncated index behind
+        let tmp_path = self.store_path.with_extension("json.tmp");
+        let written = fs::File::create(&tmp_path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| {
+                serde_json::to_writer(BufWriter::new(file), &snapshot).map_err(|e| e.to_string())
+            });
+        if written.is_ok() {
+            let _ = fs::rename(&tmp_path, &self.store_path);
+        }
+    }
+}
+
+impl FileIndex for FsFileIndex {
+    fn start(&self, config: &FileSearchConfig) {
+        let settings = IndexSettings::from_config(config);
+        {
+            let current = self.inner.settings.read().unwrap();
+            let state = *self.inner.state.read().unwrap();
+            if *current == settings && state != IndexState::Idle {
+                return;
+            }
+        }
+
+        // Drop whatever the new settings exclude so it stops showing up right away
+        let rules = IndexRules::new(settings.clone());
+        self.inner
+            .entries
+            .write()
+            .unwrap()
+            .retain(|path, file| rules.accepts(Path::new(path), file.is_dir));
+        *self.inner.settings.write().unwrap() = settings;
+        *self.inner.state.write().unwrap() = IndexState::Crawling;
+
+        let generation = self.inner.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let inner = self.inner.clone();
+        std::thread::spawn(move || inner.rebuild(generation));
+    }
+
+    fn search(&self, query: &FileQuery, limit: usize) -> Vec<FileMatch> {
+        let (batches, _) = mpsc::channel();
+        self.search_batched(query, limit, &AtomicBool::new(false), &batches)
+            .unwrap_or_default()
+    }
+
+    fn search_batched(
+        &self,
+        query: &FileQuery,
+        limit: usize,
+        cancel: &AtomicBool,
+        batches: &mpsc::Sender<Vec<FileMatch>>,
+    ) -> Option<Vec<FileMatch>> {
+        if query.is_empty() {
+            return Some(Vec::new());
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let lower_terms: Vec<String> = query.terms.iter().map(|t| t.to_lowercase()).collect();
+        let settings = self.inner.settings.read().unwrap().clone();
+        let entries = self.inner.entries.read().unwrap();
+
+        // Each root is scanned on its own thread in chunks; every chunk's hits are streamed
+        // out while the per-root winners are kept for the final merge
+        let mut matches: Vec<FileMatch> = std::thread::scope(|scope| {
+            let handles: Vec<_> = settings
+                .roots
+                .iter()
+                .map(|root| {
+                    let (entries, lower_terms, tx) = (&*entries, &lower_terms, batches.clone());
+                    let nested = settings.nested_in(root);
+                    scope.spawn(move || {
+                        let prefix = dir_prefix(&root.path);
+                        let mut files = entries
+                            .range(prefix.clone()..)
+                            .take_while(|(path, _)| path.starts_with(&prefix))
+                            .map(|(_, file)| file)
+                            .filter(|file| {
+                                !nested.iter().any(|n| Path::new(&file.path).starts_with(n))
+                            });
+                        let mut best = Vec::new();
+                        loop {
+                            if cancel.load(Ordering::Relaxed) {
+                                return Vec::new();
+                            }
+                            let chunk: Vec<&IndexedFile> =
+                                files.by_ref().take(SEARCH_CHUNK).collect();
+                            if chunk.is_empty() {
+                                return best;
+                            }
+                            // Cheap filters and the pre-check run first so the fuzzy scorer
+                            // only sees survivors
+                            let mut found: Vec<FileMatch> = chunk
+                                .into_iter()
+                                .filter(|file| query.passes_filters(file, now))
+                                .filter(|file| {
+                                    lower_terms.iter().all(|term| may_match(&file.path, term))
+                                })
+                                .filter_map(|file| query.score(file, now))
+                                .collect();
+                            if found.is_empty() {
+                                continue;
+                            }
+                            rank_matches(&mut found, limit);
+                            for file in &mut found {
+                                file.root = Some(root.name.clone());
+                            }
+                            let _ = tx.send(found.clone());
+                            best.extend(found);
+                            rank_matches(&mut best, limit);
+                        }
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap_or_default())
+                .collect()
+        });
+        drop(entries);
+
+        if cancel.load(Ordering::SeqCst) {
+            return None;
+        }
+        rank_matches(&mut matches, limit);
+        Some(matches)
+    }
+
+    fn search_content(
+        &self,
+        query: &FileQuery,
+        limit: usize,
+        cancel: &AtomicBool,
+    ) -> Option<Vec<ContentMatch>> {
+        let matcher = match TextMatcher::new(&query.text) {
+            Some(matcher) => matcher,
+            None => return Some(Vec::new()),
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let candidates: Vec<String> = self
+            .inner
+            .entries
+            .read()
+            .unwrap()
+            .values()
+            .filter(|file| !file.is_dir && file.size <= MAX_GREP_FILE_SIZE)
+            .filter(|file| query.passes_filters(file, now))
+            .map(|file| file.path.clone())
+            .collect();
+
+        // Reading files dominates, so split the candidates across a few workers. Each
+        // worker takes a contiguous run of paths in order and stops at `limit` hits, or
+        // once an earlier run has filled the limit on its own, so the result is always
+        // the first `limit` hits by path, however the threads are scheduled
+        let workers = std::thread::available_parallelism().map_or(4, |n| n.get().min(8));
+        let chunk_size = candidates.len().div_ceil(workers).max(1);
+        let chunks: Vec<&[String]> = candidates.chunks(chunk_size).collect();
+        let full: Vec<AtomicBool> = chunks.iter().map(|_| AtomicBool::new(false)).collect();
+        let mut matches: Vec<ContentMatch> = std::thread::scope(|scope| {
+            let handles: Vec<_> = chunks
+                .iter()
+                .enumerate()
+                .map(|(i, chunk)| {
+                    let (matcher, full) = (&matcher, &full);
+                    scope.spawn(move || {
+                        let mut hits = Vec::new();
+                        for path in chunk.iter() {
+                            if cancel.load(Ordering::Relaxed)
+                                || hits.len() >= limit
+                                || full[..i].iter().any(|f| f.load(Ordering::Relaxed))
+                            {
+                                break;
+                            }
+                            hits.extend(grep_file(matcher, path));
+                        }
+                        if hits.len() >= limit {
+                            full[i].store(true, Ordering::Relaxed);
+                        }
+                        hits
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap_or_default())
+                .collect()
+        });
+
+        if cancel.load(Ordering::SeqCst) {
+            return None;
+        }
+        // Runs were merged in order, so the hits are already sorted by path and line
+        matches.truncate(limit);
+        Some(matches)
+    }
+
+    fn status(&self) -> IndexStatus {
+        let settings = self.inner.settings.read().unwrap();
+        IndexStatus {
+            state: *self.inner.state.read().unwrap(),
+            indexed: self.inner.entries.read().unwrap().len(),
+            roots: settings
+                .roots
+                .iter()
+                .map(|r| r.path.to_string_lossy().to_string())
+                .collect(),
+            watch_error: self.inner.watch_error.lock().unwrap().as_ref().map(|e| {
+                match self.inner.unwatched.load(Ordering::SeqCst) {
+                    0 | 1 => format!("Not watching for changes: {}", e),
+                    n => format!("{} folders aren't watched for changes; first: {}", n, e),
+                }
+            }),
+        }
+    }
+}
+
+fn grep_file(matcher: &TextMatcher, path: &str) -> Vec<ContentMatch> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(_) => return Vec::new(),
+    };
+    if looks_binary(&bytes[..bytes.len().min(BINARY_SNIFF_LEN)]) {
+        return Vec::new();
+    }
+    match std::str::from_utf8(&bytes) {
+        Ok(content) => matcher.search(path, content, MAX_HITS_PER_FILE),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// `dir` as a key prefix that matches everything strictly below it.
+fn dir_prefix(dir: &Path) -> String {
+    let dir = dir.to_string_lossy();
+    if dir.ends_with('/') {
+        dir.to_string()
+    } else {
+        format!("{}/", dir)
+    }
+}
+
+/// Drops every entry below `key`, and `key` itself if `inclusive`.
+fn remove_subtree(entries: &mut BTreeMap<String, IndexedFile>, key: &str, inclusive: bool) {
+    if inclusive {
+        entries.remove(key);
+    }
+    let prefix = dir_prefix(Path::new(key));
+    let doomed: Vec<String> = entries
+        .range(prefix.clone()..)
+        .take_while(|(k, _)| k.starts_with(&prefix))
+        .map(|(k, _)| k.clone())
+        .collect();
+    for k in doomed {
+        entries.remove(&k);
+    }
+}
+
+fn indexed_file(path: &Path, meta: &fs::Metadata) -> IndexedFile {
+    IndexedFile {
+        path: path.to_string_lossy().to_string(),
+        is_dir: meta.is_dir(),
+        size: meta.len(),
+        modified: meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs()),
+    }
+}
+
+/// Allocation-free pre-check that rejects most entries before the fuzzy scorer runs.
+/// Only conclusive for ASCII; anything else is left to the scorer.
+fn may_match(haystack: &str, lower_query: &str) -> bool {
+    if !haystack.is_ascii() || !lower_query.is_ascii() {
+        return true;
+    }
+    let mut wanted = lower_query.bytes().peekable();
+    for b in haystack.bytes() {
+        match wanted.peek() {
+            Some(&w) if w == b.to_ascii_lowercase() => {
+                wanted.next();
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+    wanted.peek().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::config::SearchRoot;
+    use tempfile::tempdir;
+
+    fn config_for(root: &Path, include_hidden: bool) -> FileSearchConfig {
+        FileSearchConfig {
+            include_hidden,
+            roots: vec![SearchRoot::new(&root.to_string_lossy())],
+            ..Default::default()
+        }
+    }
+
+    fn make_tree(root: &Path) {
+        std::fs::create_dir_all(root.join(".hidden_dir")).unwrap();
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::File::create(root.join("visible.txt")).unwrap();
+        std::fs::File::create(root.join(".hidden.txt")).unwrap();
+        std::fs::File::create(root.join(".hidden_dir/file.txt")).unwrap();
+        std::fs::File::create(root.join("docs/report.txt")).unwrap();
+    }
+
+    fn q(query: &str) -> FileQuery {
+        FileQuery::parse(query).unwrap()
+    }
+
+    fn paths(files: &[FileMatch]) -> Vec<String> {
+        files.iter().map(|f| f.path.clone()).collect()
+    }
+
+    /// Whether a file with this exact name is indexed. Checking the name keeps
+    /// fuzzy hits on the random tempdir path from counting.
+    fn has_file(index: &FsFileIndex, name: &str) -> bool {
+        index
+            .search(&q(name), 50)
+            .iter()
+            .any(|f| f.path.rsplit('/').next() == Some(name))
+    }
+
+    #[test]
+    fn test_crawl_respects_hidden_setting() {
+        let data = tempdir().unwrap();
+        let tree = tempdir().unwrap();
+        make_tree(tree.path());
+
+        let index = FsFileIndex::new(data.path().to_path_buf());
+        index.build_now(&config_for(tree.path(), false));
+
+        let results = paths(&index.search(&q("txt"), 50));
+        assert!(results.iter().any(|r| r.ends_with("visible.txt")));
+        assert!(results.iter().any(|r| r.ends_with("docs/report.txt")));
+        assert!(!results.iter().any(|r| r.contains(".hidden")));
+
+        index.build_now(&config_for(tree.path(), true));
+        let results = paths(&index.search(&q("txt"), 50));
+        assert!(results.iter().any(|r| r.ends_with(".hidden.txt")));
+        assert!(results.iter().any(|r| r.ends_with(".hidden_dir/file.txt")));
+    }
+
+    #[test]
+    fn test_search_ranks_and_limits() {
+        let data = tempdir().unwrap();
+        let tree = tempdir().unwrap();
+        std::fs::create_dir_all(tree.path().join("report")).unwrap();
+        std::fs::File::create(tree.path().join("report/data.csv")).unwrap();
+        std::fs::File::create(tree.path().join("quarterly_report.pdf")).unwrap();
+        std::fs::File::create(tree.path().join("r_e_p_o_r_t.bin")).unwrap();
+
+        let index = FsFileIndex::new(data.path().to_path_buf());
+        index.build_now(&config_for(tree.path(), false));
+
+        let results = index.search(&q("REPORT"), 10);
+        let names: Vec<&str> = results
+            .iter()
+            .map(|m| m.path.rsplit('/').next().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "report",
+                "quarterly_report.pdf",
+                "r_e_p_o_r_t.bin",
+                "data.csv"
+            ]
+        );
+        assert!(results[0].is_dir);
+
+        // Positions point at the matched chars of the full path
+        let prefix = tree.path().to_string_lossy().chars().count() + 1;
+        assert_eq!(
+            results[0].positions,
+            (prefix..prefix + 6).collect::<Vec<_>>()
+        );
+
+        assert_eq!(index.search(&q("report"), 1).len(), 1);
+        assert!(index.search(&q(""), 10).is_empty());
+        assert!(index.search(&q("zzz"), 10).is_empty());
+
+        // Filters narrow the scan
+        let dirs = paths(&index.search(&q("type:dir"), 10));
+        assert_eq!(dirs.len(), 1);
+        assert!(dirs[0].ends_with("/report"));
+        let pdfs = paths(&index.search(&q("rep ext:pdf"), 10));
+        assert_eq!(pdfs.len(), 1);
+        assert!(pdfs[0].ends_with("quarterly_report.pdf"));
+        let inside = paths(&index.search(&q(&format!("in:{}/report", tree.path().display())), 10));
+        assert_eq!(inside.len(), 1);
+        assert!(inside[0].ends_with("data.csv"));
+    }
+
+    #[test]
+    fn test_refresh_path_tracks_changes() {
+        let data = tempdir().unwrap();
+        let tree = tempdir().unwrap();
+        make_tree(tree.path());
+
+        let index = FsFileIndex::new(data.path().to_path_buf());
+        index.build_now(&config_for(tree.path(), false));
+
+        // New directory with content gets indexed recursively
+        let new_dir = tree.path().join("projects");
+        std::fs::create_dir_all(new_dir.join("app")).unwrap();
+        std::fs::File::create(new_dir.join("app/main.rs")).unwrap();
+        index.inner.refresh_path(&new_dir);
+        assert_eq!(index.search(&q("main.rs"), 10).len(), 1);
+
+        // Removing a directory drops its whole subtree
+        std::fs::remove_dir_all(&new_dir).unwrap();
+        index.inner.refresh_path(&new_dir);
+        assert!(index.search(&q("projects"), 10).is_empty());
+
+        // Hidden paths stay out of the index
+        let hidden = tree.path().join(".cache.txt");
+        std::fs::File::create(&hidden).unwrap();
+        index.inner.refresh_path(&hidden);
+        assert!(index.search(&q("cache"), 10).is_empty());
+    }
+
+    fn make_repo(root: &Path) {
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join("build")).unwrap();
+        std::fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join(".gitignore"), "build/\n*.log\n").unwrap();
+        std::fs::File::create(root.join("build/output.bin")).unwrap();
+        std::fs::File::create(root.join("node_modules/pkg/index.js")).unwrap();
+        std::fs::File::create(root.join("src/main.rs")).unwrap();
+        std::fs::File::create(root.join("src/debug.log")).unwrap();
+    }
+
+    #[test]
+    fn test_crawl_honors_ignore_files_and_excludes() {
+        let data = tempdir().unwrap();
+        let tree = tempdir().unwrap();
+        make_repo(tree.path());
+
+        let index = FsFileIndex::new(data.path().to_path_buf());
+        let mut config = config_for(tree.path(), false);
+        index.build_now(&config);
+
+        assert!(has_file(&index, "main.rs"));
+        assert!(!has_file(&index, "output.bin"));
+        assert!(!has_file(&index, "debug.log"));
+        assert!(!has_file(&index, "index.js"));
+
+        // Ignore files can be turned off; the exclude list still applies
+        config.respect_ignore_files = false;
+        index.build_now(&config);
+        assert!(has_file(&index, "output.bin"));
+        assert!(has_file(&index, "debug.log"));
+        assert!(!has_file(&index, "index.js"));
+
+        config.exclude = vec!["*.rs".to_string()];
+        index.build_now(&config);
+        assert!(!has_file(&index, "main.rs"));
+        assert!(has_file(&index, "index.js"));
+    }
+
+    #[test]
+    fn test_refresh_path_honors_ignore_files() {
+        let data = tempdir().unwrap();
+        let tree = tempdir().unwrap();
+        make_repo(tree.path());
+
+        let index = FsFileIndex::new(data.path().to_path_buf());
+        index.build_now(&config_for(tree.path(), false));
+
+        let log = tree.path().join("src/trace.log");
+        std::fs::File::create(&log).unwrap();
+        index.inner.refresh_path(&log);
+        assert!(!has_file(&index, "trace.log"));
+
+        let ignored = tree.path().join("build/late.txt");
+        std::fs::File::create(&ignored).unwrap();
+        index.inner.refresh_path(&ignored);
+        assert!(!has_file(&index, "late.txt"));
+
+        // Editing .gitignore re-evaluates what it covers
+        std::fs::write(tree.path().join(".gitignore"), "*.log\n").unwrap();
+        index.inner.refresh_path(&tree.path().join(".gitignore"));
+        assert!(has_file(&index, "late.txt"));
+        assert!(has_file(&index, "output.bin"));
+        assert!(!has_file(&index, "trace.log"));
+    }
+
+    #[test]
+    fn test_multiple_roots_with_own_settings() {
+        let data = tempdir().unwrap();
+        let docs = tempdir().unwrap();
+        let code = tempdir().unwrap();
+        std::fs::create_dir_all(docs.path().join("a/b/c")).unwrap();
+        std::fs::File::create(docs.path().join("plan.md")).unwrap();
+        std::fs::File::create(docs.path().join("a/b/c/deep_plan.md")).unwrap();
+        std::fs::create_dir_all(code.path().join("vendor/.config")).unwrap();
+        std::fs::File::create(code.path().join("plan.rs")).unwrap();
+        std::fs::File::create(code.path().join(".plan.toml")).unwrap();
+        std::fs::File::create(code.path().join("vendor/.config/plan.json")).unwrap();
+
+        let config = FileSearchConfig {
+            roots: vec![
+                SearchRoot {
+                    max_depth: Some(2),
+                    ..SearchRoot::new(&docs.path().to_string_lossy())
+                },
+                SearchRoot {
+                    name: "Code".to_string(),
+                    include_hidden: Some(true),
+                    ..SearchRoot::new(&code.path().to_string_lossy())
+                },
+            ],
+            ..Default::default()
+        };
+        let index = FsFileIndex::new(data.path().to_path_buf());
+        index.build_now(&config);
+
+        let results = index.search(&q("plan"), 50);
+        let found = paths(&results);
+        assert!(found.iter().any(|p| p.ends_with("plan.md")));
+        assert!(!found.iter().any(|p| p.ends_with("deep_plan.md")));
+        assert!(found.iter().any(|p| p.ends_with(".plan.toml")));
+        assert!(found
+            .iter()
+            .any(|p| p.ends_with("vendor/.config/plan.json")));
+
+        let code_hit = results
+            .iter()
+            .find(|m| m.path.ends_with("plan.rs"))
+            .unwrap();
+        assert_eq!(code_hit.root.as_deref(), Some("Code"));
+
+        // Results from both roots are ranked together and share one limit
+        assert_eq!(index.search(&q("plan"), 2).len(), 2);
+    }
+
+    #[test]
+    fn test_nested_root_uses_its_own_settings() {
+        let data = tempdir().unwrap();
+        let tree = tempdir().unwrap();
+        make_tree(tree.path());
+        std::fs::File::create(tree.path().join("docs/.draft.txt")).unwrap();
+
+        let outer = tree.path().to_string_lossy().to_string();
+        let inner = tree.path().join("docs").to_string_lossy().to_string();
+        let config = FileSearchConfig {
+            roots: vec![
+                SearchRoot::new(&outer),
+                SearchRoot {
+                    include_hidden: Some(true),
+                    ..SearchRoot::new(&inner)
+                },
+            ],
+            ..Default::default()
+        };
+        let index = FsFileIndex::new(data.path().to_path_buf());
+        index.build_now(&config);
+
+        let results = index.search(&q("txt"), 50);
+        assert!(!results.iter().any(|m| m.path.ends_with(".hidden.txt")));
+        let draft = results
+            .iter()
+            .find(|m| m.path.ends_with(".draft.txt"))
+            .unwrap();
+        assert_eq!(draft.root.as_deref(), Some("docs"));
+        // Nothing shows up twice
+        let mut unique = paths(&results);
+        unique.dedup();
+        assert_eq!(unique.len(), results.len());
+    }
+
+    #[test]
+    fn test_search_batched_streams_and_cancels() {
+        let data = tempdir().unwrap();
+        let tree = tempdir().unwrap();
+        for i in 0..(SEARCH_CHUNK + 10) {
+            std::fs::File::create(tree.path().join(format!("note_{}.txt", i))).unwrap();
+        }
+        let index = FsFileIndex::new(data.path().to_path_buf());
+        index.build_now(&config_for(tree.path(), false));
+
+        let (tx, rx) = mpsc::channel();
+        let results = index
+            .search_batched(&q("note"), 20, &AtomicBool::new(false), &tx)
+            .unwrap();
+        let batches: Vec<Vec<FileMatch>> = rx.try_iter().collect();
+        assert_eq!(results.len(), 20);
+        assert_eq!(batches.len(), 2);
+        assert!(batches.iter().all(|b| b.len() <= 20));
+
+        let cancelled = index.search_batched(&q("note"), 20, &AtomicBool::new(true), &tx);
+        assert!(cancelled.is_none());
+        assert_eq!(rx.try_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_search_content_greps_text_files() {
+        let data = tempdir().unwrap();
+        let tree = tempdir().unwrap();
+        std::fs::create_dir_all(tree.path().join("src")).unwrap();
+        std::fs::write(
+            tree.path().join("src/main.rs"),
+            "fn main() {\n    // TODO: parse args\n}\n",
+        )
+        .unwrap();
+        std::fs::write(tree.path().join("notes.md"), "todo list\n").unwrap();
+        std::fs::write(tree.path().join("blob.bin"), b"TODO\0\x01\x02").unwrap();
+
+        let index = FsFileIndex::new(data.path().to_path_buf());
+        index.build_now(&config_for(tree.path(), false));
+        let never = AtomicBool::new(false);
+
+        let found = index.search_content(&q("todo"), 50, &never).unwrap();
+        assert_eq!(found.len(), 2);
+        assert!(found[0].path.ends_with("notes.md"));
+        assert!(found[1].path.ends_with("src/main.rs"));
+        assert_eq!((found[1].line, found[1].column), (2, 8));
+        assert_eq!(found[1].snippet, "// TODO: parse args");
+
+        // Filters narrow the candidate files; uppercase makes the match exact
+        let found = index.search_content(&q("todo ext:rs"), 50, &never).unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(index
+            .search_content(&q("TODO ext:md"), 50, &never)
+            .unwrap()
+            .is_empty());
+
+        assert!(index
+            .search_content(&q("todo"), 50, &AtomicBool::new(true))
+            .is_none());
+    }
+
+    #[test]
+    fn test_search_content_keeps_first_hits_by_path() {
+        let data = tempdir().unwrap();
+        let tree = tempdir().unwrap();
+        for i in 0..40 {
+            std::fs::write(tree.path().join(format!("log_{:02}.txt", i)), "ERROR\n").unwrap();
+        }
+
+        let index = FsFileIndex::new(data.path().to_path_buf());
+        index.build_now(&config_for(tree.path(), false));
+        let never = AtomicBool::new(false);
+
+        // More files match than the limit; the same ones win on every run
+        for _ in 0..5 {
+            let found = index.search_content(&q("ERROR"), 5, &never).unwrap();
+            let names: Vec<&str> = found
+                .iter()
+                .map(|hit| hit.path.rsplit('/').next().unwrap())
+                .collect();
+            assert_eq!(
+                names,
+                vec![
+                    "log_00.txt",
+                    "log_01.txt",
+                    "log_02.txt",
+                    "log_03.txt",
+                    "log_04.txt"
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_index_is_persisted() {
+        let data = tempdir().unwrap();
+        let tree = tempdir().unwrap();
+        make_tree(tree.path());
+
+        let index = FsFileIndex::new(data.path().to_path_buf());
+        index.build_now(&config_for(tree.path(), false));
+        assert!(data.path().join("file_index.json").exists());
+
+        let reloaded = FsFileIndex::new(data.path().to_path_buf());
+        assert_eq!(reloaded.status().indexed, index.status().indexed);
+        assert_eq!(reloaded.search(&q("report"), 10).len(), 1);
+    }
+
+    /// Records watched directories; watching `fail` errors as a full inotify table would.
+    struct RecordingWatcher {
+        watched: Arc<Mutex<Vec<PathBuf>>>,
+        fail: Option<PathBuf>,
+    }
+
+    impl DirWatcher for RecordingWatcher {
+        fn watch_dir(&mut self, dir: &Path) -> Result<(), String> {
+            if self.fail.as_deref() == Some(dir) {
+                return Err("No space left on device".to_string());
+            }
+            self.watched.lock().unwrap().push(dir.to_path_buf());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_watches_only_crawled_dirs() {
+        let data = tempdir().unwrap();
+        let tree = tempdir().unwrap();
+        make_repo(tree.path());
+        make_tree(tree.path());
+        std::fs::create_dir_all(tree.path().join("src/a/b")).unwrap();
+
+        let index = FsFileIndex::new(data.path().to_path_buf());
+        let watched = Arc::new(Mutex::new(Vec::new()));
+        *index.inner.watcher.lock().unwrap() = Some(Box::new(RecordingWatcher {
+            watched: watched.clone(),
+            fail: Some(tree.path().join("docs")),
+        }));
+        let mut config = config_for(tree.path(), false);
+        config.roots[0].max_depth = Some(2);
+        index.build_now(&config);
+
+        // Ignored, excluded and hidden dirs and those at the depth limit are left out
+        let mut dirs: Vec<PathBuf> = watched.lock().unwrap().clone();
+        dirs.sort();
+        assert_eq!(
+            dirs,
+            vec![tree.path().to_path_buf(), tree.path().join("src")]
+        );
+        let error = index.status().watch_error.unwrap();
+        assert!(error.contains("docs"), "{}", error);
+
+        // Directories created later are watched once their event comes in
+        let fresh = tree.path().join("fresh");
+        std::fs::create_dir(&fresh).unwrap();
+        std::fs::File::create(fresh.join("notes.md")).unwrap();
+        index.inner.apply_event(
+            &notify::Event::new(EventKind::Create(notify::event::CreateKind::Folder))
+                .add_path(fresh.clone()),
+        );
+        assert!(has_file(&index, "notes.md"));
+        assert!(watched.lock().unwrap().contains(&fresh));
+    }
+}
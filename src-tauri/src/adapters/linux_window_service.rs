@@ -23,6 +23,7 @@ impl CommandExecutor for StdCommandExecutor {
 trait WindowBackend {
     fn list_windows(&self, executor: &dyn CommandExecutor) -> Result<Vec<WindowEntry>, String>;
     fn focus_window(&self, executor: &dyn CommandExecutor, id: &str) -> Result<(), String>;
+    fn paste(&self, executor: &dyn CommandExecutor) -> Result<(), String>;
 }
 
 struct HyprlandBackend;
@@ -72,6 +73,23 @@ impl WindowBackend for HyprlandBackend {
             Err("Failed to focus window via hyprctl".to_string())
         }
     }
+
+    fn paste(&self, executor: &dyn CommandExecutor) -> Result<(), String> {
+        let output = executor.execute(
+            "hyprctl",
+            vec![
+                "dispatch".to_string(),
+                "sendshortcut".to_string(),
+                "CTRL, V, activewindow".to_string(),
+            ],
+        )?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err("Failed to paste via hyprctl".to_string())
+        }
+    }
 }
 
 impl WindowBackend for WlrctlBackend {
@@ -114,6 +132,26 @@ impl WindowBackend for WlrctlBackend {
             Err("Failed to focus window via wlrctl".to_string())
         }
     }
+
+    // wlrctl can't type, wtype can
+    fn paste(&self, executor: &dyn CommandExecutor) -> Result<(), String> {
+        let output = executor.execute(
+            "wtype",
+            vec![
+                "-M".to_string(),
+                "ctrl".to_string(),
+                "v".to_string(),
+                "-m".to_string(),
+                "ctrl".to_string(),
+            ],
+        )?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err("Failed to paste via wtype".to_string())
+        }
+    }
 }
 
 impl WindowBackend for WmctrlBackend {
@@ -158,6 +196,23 @@ impl WindowBackend for WmctrlBackend {
             Err("Failed to focus window via wmctrl".to_string())
         }
     }
+
+    fn paste(&self, executor: &dyn CommandExecutor) -> Result<(), String> {
+        let output = executor.execute(
+            "xdotool",
+            vec![
+                "key".to_string(),
+                "--clearmodifiers".to_string(),
+                "ctrl+v".to_string(),
+            ],
+        )?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err("Failed to paste via xdotool".to_string())
+        }
+    }
 }
 
 pub struct LinuxWindowService {
@@ -188,6 +243,10 @@ impl WindowService for LinuxWindowService {
     fn focus_window(&self, id: &str) -> Result<(), String> {
         self.get_backend().focus_window(self.executor.as_ref(), id)
     }
+
+    fn paste(&self) -> Result<(), String> {
+        self.get_backend().paste(self.executor.as_ref())
+    }
 }
 
 #[cfg(test)]
@@ -275,4 +334,22 @@ mod tests {
         assert_eq!(windows[0].title, "PyCharm Projects");
         assert_eq!(windows[0].class, "PyCharm");
     }
+
+    #[test]
+    fn test_hyprland_backend_paste() {
+        let mut mock = MockCommandExecutor::new();
+        mock.expect_execute()
+            .with(
+                mockall::predicate::eq("hyprctl"),
+                mockall::predicate::eq(vec![
+                    "dispatch".to_string(),
+                    "sendshortcut".to_string(),
+                    "CTRL, V, activewindow".to_string(),
+                ]),
+            )
+            .times(1)
+            .returning(|_, _| Ok(mock_success_output("ok")));
+
+        assert!(HyprlandBackend.paste(&mock).is_ok());
+    }
 }
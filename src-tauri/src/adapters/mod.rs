@@ -1,7 +1,15 @@
 pub mod cached_icon_resolver;
 pub mod file_history;
+pub mod file_run_log;
 pub mod fs_app_repository;
 pub mod fs_config_service;
+pub mod fs_file_index;
 pub mod google_translation_service;
 pub mod http_ai_service;
 pub mod linux_window_service;
+pub mod process_script_runner;
+pub mod script_discovery;
+pub mod script_filter_provider;
+pub mod search_providers;
+pub mod stdio_plugin_host;
+pub mod xdg_mime_resolver;
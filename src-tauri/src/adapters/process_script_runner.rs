@@ -0,0 +1,829 @@
+//! Runs scripts as child processes, each in its own process group so that killing a
+//! run also stops whatever the script started. Scripts either get pipes, with stdin
+//! kept open for what the user types, or a pseudo-terminal, in which case they lead
+//! a new session with it as their controlling terminal. Unattended runs get pipes
+//! with nothing on stdin.
+
+use crate::domain::frecency::now_ms;
+use crate::domain::script_run::{
+    resolve_command, PtySize, RunStatus, RunningScript, ScriptEvent, ScriptRequest,
+};
+use crate::ports::script_runner_port::ScriptRunner;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::{ChildStderr, ChildStdin, ChildStdout, Command};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::task::JoinHandle;
+
+/// A killed run that ignores SIGTERM this long gets SIGKILL.
+const KILL_GRACE: Duration = Duration::from_secs(2);
+/// Output still arriving after the script exited (from a detached child holding the
+/// pipe open) is not waited for longer than this.
+const OUTPUT_DRAIN: Duration = Duration::from_millis(500);
+/// How often the terminal reader checks whether its run is over while no output comes.
+const TERMINAL_POLL_MS: i32 = 100;
+
+struct Run {
+    info: RunningScript,
+    killed: bool,
+    /// Master side of the pseudo-terminal for PTY runs.
+    terminal: Option<Arc<File>>,
+    /// Feeds the stdin pipe of piped runs; dropping it closes the pipe.
+    input: Option<UnboundedSender<String>>,
+}
+
+enum Streams {
+    Piped(Option<ChildStdin>, ChildStdout, ChildStderr),
+    Terminal(Arc<File>),
+}
+
+type Runs = Arc<Mutex<HashMap<String, Run>>>;
+
+pub struct ProcessScriptRunner {
+    runs: Runs,
+    /// Run ids are `run-<launch time>-<n>`, unique across restarts for the run log.
+    launched_at: u64,
+    next_id: AtomicU64,
+}
+
+impl ProcessScriptRunner {
+    pub fn new() -> Self {
+        Self {
+            runs: Arc::new(Mutex::new(HashMap::new())),
+            launched_at: now_ms(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    fn next_run_id(&self) -> String {
+        let n = self.next_id.fetch_add(1, Ordering::Relaxed);
+        format!("run-{}-{}", self.launched_at, n)
+    }
+
+    fn with_run<T>(&self, run_id: &str, f: impl FnOnce(&mut Run) -> T) -> Result<T, String> {
+        let mut runs = self.runs.lock().unwrap();
+        let run = runs
+            .get_mut(run_id)
+            .ok_or_else(|| format!("No running script with id {}", run_id))?;
+        Ok(f(run))
+    }
+}
+
+impl Default for ProcessScriptRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn first_line(path: &str) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let mut line = String::new();
+    std::io::BufReader::new(file).read_line(&mut line).ok()?;
+    Some(line)
+}
+
+/// Program and arguments running the script at `path`, with the interpreter taken
+/// from `interpreter` or the script's shebang.
+pub fn script_command_line(path: &str, interpreter: Option<&str>) -> Result<Vec<String>, String> {
+    resolve_command(path, interpreter, first_line(path).as_deref())
+}
+
+/// Sends `signal` to every process in the group led by `pid`.
+pub fn signal_group(pid: u32, signal: i32) -> Result<(), String> {
+    // SAFETY: killpg has no memory-safety preconditions
+    if unsafe { libc::killpg(pid as libc::pid_t, signal) } == 0 {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to signal script: {}",
+            std::io::Error::last_os_error()
+        ))
+    }
+}
+
+fn winsize(size: PtySize) -> libc::winsize {
+    libc::winsize {
+        ws_row: size.rows,
+        ws_col: size.cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    }
+}
+
+fn last_os_error(context: &str) -> String {
+    format!("{}: {}", context, std::io::Error::last_os_error())
+}
+
+/// Opens a pseudo-terminal pair, returning (master, slave).
+fn open_pty(size: PtySize) -> Result<(File, File), String> {
+    let mut master: libc::c_int = -1;
+    let mut slave: libc::c_int = -1;
+    let winsize = winsize(size);
+    // SAFETY: openpty only writes the two descriptors; name and termios are optional
+    let result = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            &winsize,
+        )
+    };
+    if result != 0 {
+        return Err(last_os_error("Failed to open a terminal"));
+    }
+    // Only the script gets the terminal, not every other process we spawn
+    for fd in [master, slave] {
+        // SAFETY: fd was just opened by openpty
+        unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) };
+    }
+    // SAFETY: both descriptors are open and nothing else owns them
+    Ok(unsafe { (File::from_raw_fd(master), File::from_raw_fd(slave)) })
+}
+
+/// Length of the prefix of `bytes` that can be decoded now; a character cut in half
+/// by the read boundary is left for the next read.
+fn complete_utf8_len(bytes: &[u8]) -> usize {
+    match std::str::from_utf8(bytes) {
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        _ => bytes.len(),
+    }
+}
+
+/// Waits up to `timeout_ms` for the terminal to have output, or to be closed.
+fn terminal_readable(terminal: &File, timeout_ms: i32) -> bool {
+    let mut fd = libc::pollfd {
+        fd: terminal.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: poll reads and writes the one pollfd it is given
+    unsafe { libc::poll(&mut fd, 1, timeout_ms) > 0 }
+}
+
+/// Forwards terminal output as is; the terminal view interprets the escape sequences.
+/// Stops once `finished` is set, even if a background child still holds the terminal.
+fn forward_raw(
+    terminal: Arc<File>,
+    run_id: String,
+    events: UnboundedSender<ScriptEvent>,
+    finished: Arc<AtomicBool>,
+) -> JoinHandle<()> {
+    tokio::task::spawn_blocking(move || {
+        let mut buf = [0u8; 4096];
+        let mut pending = Vec::new();
+        while !finished.load(Ordering::Relaxed) {
+            if !terminal_readable(&terminal, TERMINAL_POLL_MS) {
+                continue;
+            }
+            // Fails with EIO once the script and its children closed the terminal
+            let n = match (&*terminal).read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            pending.extend_from_slice(&buf[..n]);
+            let len = complete_utf8_len(&pending);
+            let text = String::from_utf8_lossy(&pending[..len]).into_owned();
+            pending.drain(..len);
+            let _ = events.send(ScriptEvent::Output {
+                run_id: run_id.clone(),
+                text,
+            });
+        }
+    })
+}
+
+fn spawn_piped(cmd: &mut Command, input: bool) -> Result<(tokio::process::Child, Streams), String> {
+    let stdin = if input { Stdio::piped() } else { Stdio::null() };
+    cmd.stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0);
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to spawn script: {}", e))?;
+    let stdin = match input {
+        true => Some(child.stdin.take().ok_or("Failed to open stdin")?),
+        false => None,
+    };
+    let stdout = child.stdout.take().ok_or("Failed to open stdout")?;
+    let stderr = child.stderr.take().ok_or("Failed to open stderr")?;
+    Ok((child, Streams::Piped(stdin, stdout, stderr)))
+}
+
+fn spawn_pty(cmd: &mut Command, size: PtySize) -> Result<(tokio::process::Child, Streams), String> {
+    let (master, slave) = open_pty(size)?;
+    let stdio = |file: &File| {
+        file.try_clone()
+            .map(Stdio::from)
+            .map_err(|e| format!("Failed to open a terminal: {}", e))
+    };
+    cmd.stdin(stdio(&slave)?)
+        .stdout(stdio(&slave)?)
+        .stderr(Stdio::from(slave))
+        .env("TERM", "xterm-256color");
+    // SAFETY: only async-signal-safe calls between fork and exec
+    unsafe {
+        cmd.pre_exec(|| {
+            // A new session is also a new process group, so kill() still reaches
+            // everything the script starts
+            if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY, 0) == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to spawn script: {}", e))?;
+    Ok((child, Streams::Terminal(Arc::new(master))))
+}
+
+/// Writes what the user types to the script's stdin. The pipe closes, and the script
+/// reads EOF, once the returned sender is dropped.
+fn forward_input(mut stdin: ChildStdin) -> UnboundedSender<String> {
+    let (tx, mut rx) = unbounded_channel::<String>();
+    tokio::spawn(async move {
+        while let Some(text) = rx.recv().await {
+            if stdin.write_all(text.as_bytes()).await.is_err() {
+                break;
+            }
+            let _ = stdin.flush().await;
+        }
+    });
+    tx
+}
+
+fn forward_lines<R>(
+    reader: R,
+    prefix: &'static str,
+    run_id: String,
+    events: UnboundedSender<ScriptEvent>,
+) -> JoinHandle<()>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let text = format!("{}{}\n", prefix, line);
+            let _ = events.send(ScriptEvent::Output {
+                run_id: run_id.clone(),
+                text,
+            });
+        }
+    })
+}
+
+impl ScriptRunner for ProcessScriptRunner {
+    fn start(
+        &self,
+        request: ScriptRequest,
+        events: UnboundedSender<ScriptEvent>,
+    ) -> Result<String, String> {
+        let path = request.path.trim();
+        let mut command = script_command_line(path, request.interpreter.as_deref())?;
+        command.extend(request.args.iter().cloned());
+
+        let mut cmd = Command::new(&command[0]);
+        cmd.args(&command[1..]).envs(&request.env);
+        if let Some(cwd) = &request.cwd {
+            cmd.current_dir(cwd);
+        }
+        let (mut child, streams) = match request.pty {
+            Some(size) if !request.unattended => spawn_pty(&mut cmd, size)?,
+            _ => spawn_piped(&mut cmd, !request.unattended)?,
+        };
+        // Our copies of the terminal's slave side go with it; reads from the master
+        // end once the script's copies are closed too
+        drop(cmd);
+        let pid = child.id().ok_or("Script exited before it started")?;
+
+        // Start goes out before the readers can forward any output
+        let run_id = self.next_run_id();
+        let _ = events.send(ScriptEvent::Start {
+            run_id: run_id.clone(),
+            command,
+            cwd: request.cwd.clone(),
+        });
+        let finished = Arc::new(AtomicBool::new(false));
+        let (terminal, input, mut readers) = match streams {
+            Streams::Piped(stdin, stdout, stderr) => (
+                None,
+                stdin.map(forward_input),
+                vec![
+                    forward_lines(stdout, "> ", run_id.clone(), events.clone()),
+                    forward_lines(stderr, "ERR> ", run_id.clone(), events.clone()),
+                ],
+            ),
+            Streams::Terminal(master) => (
+                Some(master.clone()),
+                None,
+                vec![forward_raw(
+                    master,
+                    run_id.clone(),
+                    events.clone(),
+                    finished.clone(),
+                )],
+            ),
+        };
+
+        self.runs.lock().unwrap().insert(
+            run_id.clone(),
+            Run {
+                info: RunningScript {
+                    run_id: run_id.clone(),
+                    path: request.path.clone(),
+                    pid,
+                    started_at: now_ms(),
+                },
+                killed: false,
+                terminal,
+                input,
+            },
+        );
+
+        let runs = self.runs.clone();
+        let id = run_id.clone();
+        tokio::spawn(async move {
+            let mut timed_out = false;
+            let status = match request.timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, child.wait()).await {
+                    Ok(status) => status,
+                    Err(_) => {
+                        timed_out = true;
+                        let _ = signal_group(pid, libc::SIGKILL);
+                        child.wait().await
+                    }
+                },
+                None => child.wait().await,
+            };
+            let drain = futures_util::future::join_all(readers.iter_mut());
+            let _ = tokio::time::timeout(OUTPUT_DRAIN, drain).await;
+            // Whatever still holds the output open outlived the run; stop reading it
+            // so the terminal or pipes close once the run is removed
+            finished.store(true, Ordering::Relaxed);
+            for reader in &readers {
+                reader.abort();
+            }
+
+            let killed = runs
+                .lock()
+                .unwrap()
+                .remove(&id)
+                .is_some_and(|run| run.killed);
+            let code = status.ok().and_then(|status| status.code());
+            let status = if timed_out {
+                RunStatus::TimedOut
+            } else if killed {
+                RunStatus::Killed
+            } else {
+                RunStatus::from_exit(code)
+            };
+            let _ = events.send(ScriptEvent::Done {
+                run_id: id,
+                code,
+                status,
+            });
+        });
+
+        Ok(run_id)
+    }
+
+    fn kill(&self, run_id: &str) -> Result<(), String> {
+        let pid = self.with_run(run_id, |run| {
+            run.killed = true;
+            run.info.pid
+        })?;
+        signal_group(pid, libc::SIGTERM)?;
+
+        let runs = self.runs.clone();
+        let run_id = run_id.to_string();
+        tokio::spawn(async move {
+            tokio::time::sleep(KILL_GRACE).await;
+            if runs.lock().unwrap().contains_key(&run_id) {
+                let _ = signal_group(pid, libc::SIGKILL);
+            }
+        });
+        Ok(())
+    }
+
+    fn write_stdin(&self, run_id: &str, text: &str) -> Result<(), String> {
+        let (terminal, input) =
+            self.with_run(run_id, |run| (run.terminal.clone(), run.input.clone()))?;
+        if let Some(terminal) = terminal {
+            return (&*terminal)
+                .write_all(text.as_bytes())
+                .map_err(|e| format!("Failed to write to script: {}", e));
+        }
+        input
+            .ok_or_else(|| format!("Input of script {} is closed", run_id))?
+            .send(text.to_string())
+            .map_err(|_| format!("Script {} stopped reading its input", run_id))
+    }
+
+    fn close_stdin(&self, run_id: &str) -> Result<(), String> {
+        let terminal = self.with_run(run_id, |run| {
+            run.input = None;
+            run.terminal.clone()
+        })?;
+        // A terminal has no pipe to close; Ctrl+D is how its line discipline signals EOF
+        match terminal {
+            Some(terminal) => (&*terminal)
+                .write_all(b"\x04")
+                .map_err(|e| format!("Failed to write to script: {}", e)),
+            None => Ok(()),
+        }
+    }
+
+    fn resize(&self, run_id: &str, size: PtySize) -> Result<(), String> {
+        let terminal = self
+            .with_run(run_id, |run| run.terminal.clone())?
+            .ok_or_else(|| format!("Script {} doesn't run in a terminal", run_id))?;
+        let winsize = winsize(size);
+        // SAFETY: TIOCSWINSZ reads a winsize from the pointer
+        if unsafe { libc::ioctl(terminal.as_raw_fd(), libc::TIOCSWINSZ, &winsize) } == -1 {
+            return Err(last_os_error("Failed to resize terminal"));
+        }
+        Ok(())
+    }
+
+    fn running(&self) -> Vec<RunningScript> {
+        let mut running: Vec<RunningScript> = self
+            .runs
+            .lock()
+            .unwrap()
+            .values()
+            .map(|run| run.info.clone())
+            .collect();
+        running.sort_by_key(|run| run.started_at);
+        running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tokio::sync::mpsc::UnboundedReceiver;
+
+    fn write_script(dir: &Path, body: &str) -> String {
+        let path = dir.join("script.sh");
+        std::fs::write(&path, body).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn output(events: &[ScriptEvent]) -> String {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                ScriptEvent::Output { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    async fn collect(mut events: UnboundedReceiver<ScriptEvent>) -> Vec<ScriptEvent> {
+        let mut collected = Vec::new();
+        while let Some(event) = events.recv().await {
+            let done = matches!(event, ScriptEvent::Done { .. });
+            collected.push(event);
+            if done {
+                break;
+            }
+        }
+        collected
+    }
+
+    #[tokio::test]
+    async fn test_run_reports_output_and_exit_with_run_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_script(dir.path(), "echo \"hello $1\"\nexit 3\n");
+        let runner = ProcessScriptRunner::new();
+        let (tx, rx) = unbounded_channel();
+
+        let run_id = runner
+            .start(
+                ScriptRequest {
+                    path: path.clone(),
+                    args: vec!["world".to_string()],
+                    ..Default::default()
+                },
+                tx,
+            )
+            .unwrap();
+        let events = collect(rx).await;
+
+        assert!(events.iter().all(|event| event.run_id() == run_id));
+        assert_eq!(
+            events[0],
+            ScriptEvent::Start {
+                run_id: run_id.clone(),
+                command: vec!["sh".to_string(), path, "world".to_string()],
+                cwd: None,
+            }
+        );
+        assert!(events.contains(&ScriptEvent::Output {
+            run_id: run_id.clone(),
+            text: "> hello world\n".to_string()
+        }));
+        assert_eq!(
+            events.last().unwrap(),
+            &ScriptEvent::Done {
+                run_id,
+                code: Some(3),
+                status: RunStatus::Failed
+            }
+        );
+        assert!(runner.running().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_kill_stops_the_process_group() {
+        let dir = tempfile::tempdir().unwrap();
+        // The background sleep would keep stdout open if only the shell were killed
+        let path = write_script(dir.path(), "sleep 30 &\nsleep 30\n");
+        let runner = ProcessScriptRunner::new();
+        let (tx, rx) = unbounded_channel();
+
+        let run_id = runner
+            .start(
+                ScriptRequest {
+                    path,
+                    ..Default::default()
+                },
+                tx,
+            )
+            .unwrap();
+        assert_eq!(runner.running()[0].run_id, run_id);
+
+        runner.kill(&run_id).unwrap();
+        let events = tokio::time::timeout(Duration::from_secs(5), collect(rx))
+            .await
+            .expect("killed run should finish");
+
+        assert!(matches!(
+            events.last().unwrap(),
+            ScriptEvent::Done {
+                status: RunStatus::Killed,
+                ..
+            }
+        ));
+        assert!(runner.running().is_empty());
+        assert!(runner.kill(&run_id).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills_the_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_script(dir.path(), "sleep 30\n");
+        let runner = ProcessScriptRunner::new();
+        let (tx, rx) = unbounded_channel();
+
+        runner
+            .start(
+                ScriptRequest {
+                    path,
+                    timeout: Some(Duration::from_millis(200)),
+                    ..Default::default()
+                },
+                tx,
+            )
+            .unwrap();
+        let events = tokio::time::timeout(Duration::from_secs(5), collect(rx))
+            .await
+            .expect("timed out run should finish");
+
+        assert!(matches!(
+            events.last().unwrap(),
+            ScriptEvent::Done {
+                status: RunStatus::TimedOut,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_pty_reader_stops_when_a_background_child_keeps_the_terminal() {
+        let dir = tempfile::tempdir().unwrap();
+        // Ignoring SIGHUP, the sleep keeps the terminal open after the script exits
+        let path = write_script(dir.path(), "trap '' HUP\nsleep 5 &\necho started\n");
+        let runner = ProcessScriptRunner::new();
+        let (tx, mut rx) = unbounded_channel();
+
+        runner
+            .start(
+                ScriptRequest {
+                    path,
+                    pty: Some(PtySize::default()),
+                    ..Default::default()
+                },
+                tx,
+            )
+            .unwrap();
+        let mut events = Vec::new();
+        while !matches!(events.last(), Some(ScriptEvent::Done { .. })) {
+            events.push(rx.recv().await.unwrap());
+        }
+
+        // The channel closes once the terminal reader let go of its sender
+        let closed = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .expect("terminal reader should stop after the run is done");
+        assert!(closed.is_none());
+        assert!(output(&events).contains("started"));
+    }
+
+    #[tokio::test]
+    async fn test_pty_run_is_a_terminal_that_takes_input_and_resizes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_script(
+            dir.path(),
+            "[ -t 1 ] && printf '\\033[32mtty\\033[0m\\n'\nstty size\nread answer\nstty size\necho \"got $answer\"\n",
+        );
+        let runner = ProcessScriptRunner::new();
+        let (tx, mut rx) = unbounded_channel();
+
+        let run_id = runner
+            .start(
+                ScriptRequest {
+                    path,
+                    pty: Some(PtySize {
+                        rows: 30,
+                        cols: 100,
+                    }),
+                    ..Default::default()
+                },
+                tx,
+            )
+            .unwrap();
+
+        // Wait for the first size before answering the prompt
+        let mut events = Vec::new();
+        while !output(&events).contains("30 100") {
+            events.push(rx.recv().await.unwrap());
+        }
+        runner
+            .resize(
+                &run_id,
+                PtySize {
+                    rows: 40,
+                    cols: 120,
+                },
+            )
+            .unwrap();
+        runner.write_stdin(&run_id, "yes\n").unwrap();
+        let rest = tokio::time::timeout(Duration::from_secs(5), collect(rx))
+            .await
+            .expect("pty run should finish");
+        events.extend(rest);
+
+        let text = output(&events);
+        assert!(text.contains("\u{1b}[32mtty\u{1b}[0m"), "{:?}", text);
+        assert!(text.contains("40 120"), "{:?}", text);
+        assert!(text.contains("got yes"), "{:?}", text);
+        assert!(matches!(
+            events.last().unwrap(),
+            ScriptEvent::Done {
+                status: RunStatus::Success,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_split_utf8_character_waits_for_next_read() {
+        let bytes = "añ".as_bytes();
+        assert_eq!(complete_utf8_len(bytes), 3);
+        assert_eq!(complete_utf8_len(&bytes[..2]), 1);
+    }
+
+    #[tokio::test]
+    async fn test_piped_run_reads_stdin_until_closed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_script(dir.path(), "read name\necho \"hi $name\"\nwc -l\n");
+        let runner = ProcessScriptRunner::new();
+        let (tx, rx) = unbounded_channel();
+
+        let run_id = runner
+            .start(
+                ScriptRequest {
+                    path,
+                    ..Default::default()
+                },
+                tx,
+            )
+            .unwrap();
+        runner.write_stdin(&run_id, "bob\n").unwrap();
+        runner.write_stdin(&run_id, "one\ntwo\n").unwrap();
+        runner.close_stdin(&run_id).unwrap();
+        let events = tokio::time::timeout(Duration::from_secs(5), collect(rx))
+            .await
+            .expect("script should finish at EOF");
+
+        let text = output(&events);
+        assert!(text.contains("> hi bob\n"), "{:?}", text);
+        assert!(text.contains("> 2\n"), "{:?}", text);
+        assert!(runner.write_stdin(&run_id, "late\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unattended_run_reads_eof_instead_of_waiting() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_script(
+            dir.path(),
+            "[ -t 0 ] && echo tty\nif read answer; then echo \"got $answer\"; else echo eof; fi\n",
+        );
+        let runner = ProcessScriptRunner::new();
+        let (tx, rx) = unbounded_channel();
+
+        let run_id = runner
+            .start(
+                ScriptRequest {
+                    path,
+                    pty: Some(PtySize::default()),
+                    unattended: true,
+                    ..Default::default()
+                },
+                tx,
+            )
+            .unwrap();
+        assert!(runner.write_stdin(&run_id, "yes\n").is_err());
+        let events = tokio::time::timeout(Duration::from_secs(5), collect(rx))
+            .await
+            .expect("unattended run should not wait for input");
+
+        assert_eq!(output(&events), "> eof\n");
+    }
+
+    #[tokio::test]
+    async fn test_shebang_env_and_cwd() {
+        let dir = tempfile::tempdir().unwrap();
+        // Not executable and no .sh extension: only the shebang says how to run it
+        let path = dir.path().join("greet");
+        std::fs::write(&path, "#!/bin/sh\necho \"$GREETING from $(pwd)\"\n").unwrap();
+        let cwd = dir.path().canonicalize().unwrap().display().to_string();
+        let runner = ProcessScriptRunner::new();
+        let (tx, rx) = unbounded_channel();
+
+        runner
+            .start(
+                ScriptRequest {
+                    path: path.display().to_string(),
+                    cwd: Some(cwd.clone()),
+                    env: HashMap::from([("GREETING".to_string(), "hello".to_string())]),
+                    ..Default::default()
+                },
+                tx,
+            )
+            .unwrap();
+        let events = collect(rx).await;
+
+        assert!(matches!(
+            &events[0],
+            ScriptEvent::Start { command, .. } if command[0] == "/bin/sh"
+        ));
+        let text = output(&events);
+        assert!(
+            text.contains(&format!("> hello from {}\n", cwd)),
+            "{:?}",
+            text
+        );
+    }
+    #[tokio::test]
+    async fn test_env_split_shebang() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("strict");
+        std::fs::write(
+            &path,
+            "#!/usr/bin/env -S sh -eu\ncase $- in *u*) echo nounset ;; esac\n",
+        )
+        .unwrap();
+        let runner = ProcessScriptRunner::new();
+        let (tx, rx) = unbounded_channel();
+
+        runner
+            .start(
+                ScriptRequest {
+                    path: path.display().to_string(),
+                    ..Default::default()
+                },
+                tx,
+            )
+            .unwrap();
+        let events = collect(rx).await;
+
+        assert_eq!(output(&events), "> nounset\n");
+        assert!(matches!(
+            events.last(),
+            Some(ScriptEvent::Done { code: Some(0), .. })
+        ));
+    }
+}
@@ -0,0 +1,219 @@
+//! Finds scripts carrying Raycast metadata headers in the configured scripts folder.
+
+use crate::domain::config::{AppConfig, ScriptConfig};
+use crate::domain::files::expand_tilde;
+use crate::domain::script_metadata::{parse_metadata, ScriptMetadata};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Only the start of a file is read; headers sit at the top.
+const HEADER_BYTES: u64 = 8 * 1024;
+
+/// Parsed headers by path, reused while the file's mtime stays the same. Discovery
+/// runs on every keystroke and scheduler tick, so headers are only re-read when a
+/// script changes.
+type HeaderCache = HashMap<PathBuf, (SystemTime, Option<ScriptMetadata>)>;
+
+fn header_cache() -> &'static Mutex<HeaderCache> {
+    static CACHE: OnceLock<Mutex<HeaderCache>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+fn read_header(path: &Path) -> Option<String> {
+    let mut head = Vec::new();
+    std::fs::File::open(path)
+        .ok()?
+        .take(HEADER_BYTES)
+        .read_to_end(&mut head)
+        .ok()?;
+    Some(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Relative icon paths are relative to the script; emoji and URLs pass through.
+fn resolve_icon(icon: String, dir: &Path) -> String {
+    let looks_like_path = icon.contains('.') && !icon.contains("://");
+    if looks_like_path && !Path::new(&icon).is_absolute() {
+        dir.join(&icon).display().to_string()
+    } else {
+        icon
+    }
+}
+
+/// Scripts directly inside `dir` that declare at least `@raycast.title`, sorted by path.
+pub fn discover_scripts(dir: &Path) -> Vec<ScriptConfig> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(PathBuf, SystemTime)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|path| {
+            let meta = path.metadata().ok().filter(|meta| meta.is_file())?;
+            Some((path, meta.modified().ok()?))
+        })
+        .collect();
+    files.sort();
+
+    let mut cache = header_cache().lock().unwrap();
+    // Forget scripts that were removed from this dir
+    cache
+        .retain(|path, _| path.parent() != Some(dir) || files.iter().any(|(file, _)| file == path));
+    let headers: Vec<(PathBuf, Option<ScriptMetadata>)> = files
+        .into_iter()
+        .map(|(path, modified)| {
+            let meta = match cache.get(&path) {
+                Some((cached, meta)) if *cached == modified => meta.clone(),
+                _ => {
+                    let meta = read_header(&path).and_then(|header| parse_metadata(&header));
+                    cache.insert(path.clone(), (modified, meta.clone()));
+                    meta
+                }
+            };
+            (path, meta)
+        })
+        .collect();
+    drop(cache);
+
+    headers
+        .into_iter()
+        .filter_map(|(path, meta)| {
+            let meta = meta?;
+            let path_str = path.display().to_string();
+            let alias = path.file_stem()?.to_string_lossy().into_owned();
+            Some(ScriptConfig {
+                id: format!("discovered:{}", path_str),
+                alias,
+                path: path_str,
+                args: None,
+                filter: false,
+                title: Some(meta.title),
+                icon: meta.icon.map(|icon| resolve_icon(icon, dir)),
+                arguments: meta.arguments,
+                mode: meta.mode,
+                cwd: meta.cwd,
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Configured scripts followed by discovered ones. A script registered in the
+/// config wins over the same file found in the scripts folder.
+pub fn all_scripts(config: &AppConfig) -> Vec<ScriptConfig> {
+    let mut scripts = config.scripts.clone();
+    if let Some(dir) = config
+        .scripts_dir
+        .as_deref()
+        .filter(|d| !d.trim().is_empty())
+    {
+        for script in discover_scripts(&expand_tilde(dir.trim())) {
+            if !scripts.iter().any(|s| s.path.trim() == script.path) {
+                scripts.push(script);
+            }
+        }
+    }
+    scripts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::config::ScriptMode;
+    use tempfile::tempdir;
+
+    fn write(dir: &Path, name: &str, content: &str) -> String {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path.display().to_string()
+    }
+
+    #[test]
+    fn test_discovers_scripts_with_headers() {
+        let dir = tempdir().unwrap();
+        write(
+            dir.path(),
+            "open-ticket.sh",
+            "#!/bin/bash\n# @raycast.title Open Ticket\n# @raycast.icon images/jira.png\n# @raycast.mode silent\n",
+        );
+        write(dir.path(), "plain.sh", "#!/bin/sh\necho hi\n");
+        write(
+            dir.path(),
+            "weather.py",
+            "# @raycast.title Weather\n# @raycast.icon ⛅\n",
+        );
+        std::fs::create_dir(dir.path().join("nested.sh")).unwrap();
+
+        let scripts = discover_scripts(dir.path());
+        assert_eq!(scripts.len(), 2);
+        assert_eq!(scripts[0].alias, "open-ticket");
+        assert_eq!(scripts[0].title.as_deref(), Some("Open Ticket"));
+        assert_eq!(scripts[0].mode, Some(ScriptMode::Silent));
+        assert_eq!(
+            scripts[0].icon,
+            Some(dir.path().join("images/jira.png").display().to_string())
+        );
+        assert_eq!(scripts[1].icon.as_deref(), Some("⛅"));
+
+        assert!(discover_scripts(&dir.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn test_headers_are_reread_only_when_scripts_change() {
+        let dir = tempdir().unwrap();
+        let path = write(dir.path(), "deploy.sh", "# @raycast.title Deploy\n");
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+        assert_eq!(
+            discover_scripts(dir.path())[0].title.as_deref(),
+            Some("Deploy")
+        );
+
+        // Same mtime: the cached header is used
+        std::fs::write(&path, "# @raycast.title Ship\n").unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(modified).unwrap();
+        assert_eq!(
+            discover_scripts(dir.path())[0].title.as_deref(),
+            Some("Deploy")
+        );
+
+        file.set_modified(modified + std::time::Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(
+            discover_scripts(dir.path())[0].title.as_deref(),
+            Some("Ship")
+        );
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(discover_scripts(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_configured_scripts_take_precedence() {
+        let dir = tempdir().unwrap();
+        let path = write(dir.path(), "deploy.sh", "# @raycast.title Deploy\n");
+        write(dir.path(), "backup.sh", "# @raycast.title Backup\n");
+
+        let config = AppConfig {
+            scripts: vec![ScriptConfig {
+                id: "1".to_string(),
+                alias: "ship".to_string(),
+                path,
+                ..Default::default()
+            }],
+            scripts_dir: Some(dir.path().display().to_string()),
+            ..Default::default()
+        };
+        let scripts = all_scripts(&config);
+        let aliases: Vec<&str> = scripts.iter().map(|s| s.alias.as_str()).collect();
+        assert_eq!(aliases, vec!["ship", "backup"]);
+
+        let without_dir = AppConfig {
+            scripts_dir: None,
+            ..config
+        };
+        assert_eq!(all_scripts(&without_dir).len(), 1);
+    }
+}
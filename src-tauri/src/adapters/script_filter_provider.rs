@@ -0,0 +1,204 @@
+//! Runs script filters (see `domain::script_filter`) for the unified search.
+
+use crate::adapters::process_script_runner::{script_command_line, signal_group};
+use crate::adapters::script_discovery::all_scripts;
+use crate::domain::config::ScriptConfig;
+use crate::domain::files::expand_tilde;
+use crate::domain::script_filter::{match_filter, parse_items, ItemAction, ScriptFilterItem};
+use crate::domain::search::{action, ResultKind, SearchResult};
+use crate::ports::config_port::ConfigService;
+use crate::ports::search_provider_port::{SearchContext, SearchProvider};
+use std::io::Read;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Script filters that haven't printed their items by then are killed.
+const FILTER_TIMEOUT: Duration = Duration::from_millis(1500);
+/// Items keep the script's order and sit above everything else: typing the
+/// filter's alias is an explicit request for them.
+const ITEM_BASE_SCORE: i32 = 10_000;
+
+/// Runs `script` with `query` as its last argument and parses what it prints.
+pub fn run_script_filter(
+    script: &ScriptConfig,
+    query: &str,
+    timeout: Duration,
+) -> Result<Vec<ScriptFilterItem>, String> {
+    let command = script_command_line(script.path.trim(), script.interpreter.as_deref())?;
+    let mut cmd = Command::new(&command[0]);
+    cmd.args(&command[1..]).envs(&script.env);
+    if let Some(cwd) = &script.cwd {
+        cmd.current_dir(expand_tilde(cwd));
+    }
+    if let Some(args) = &script.args {
+        cmd.args(shell_words::split(args).map_err(|e| e.to_string())?);
+    }
+    let mut child = cmd
+        .arg(query)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .map_err(|e| format!("Failed to spawn script: {}", e))?;
+
+    let mut stdout = child.stdout.take().ok_or("Failed to open stdout")?;
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut output = String::new();
+        let _ = tx.send(stdout.read_to_string(&mut output).map(|_| output));
+    });
+
+    let output = rx.recv_timeout(timeout);
+    if output.is_err() {
+        // Children of the script may hold stdout too; the reader only sees EOF once
+        // the whole group is gone
+        let _ = signal_group(child.id(), libc::SIGKILL);
+    }
+    let _ = child.wait();
+    match output {
+        Ok(Ok(output)) => parse_items(&output),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("Script filter {} timed out", script.alias)),
+    }
+}
+
+fn to_result(script: &ScriptConfig, index: usize, item: ScriptFilterItem) -> SearchResult {
+    let (target, item_action) = match item.action {
+        ItemAction::Open { target } => (target, action("open_url", "Open")),
+        ItemAction::Copy { text } => (text, action("copy", "Copy")),
+        ItemAction::Run { command } => (command, action("run_command", "Run")),
+    };
+    let mut result = SearchResult::new(
+        ResultKind::ScriptItem,
+        format!("script:{}:{}", script.alias, item.title),
+        &item.title,
+        &item.subtitle,
+        &target,
+    );
+    result.icon = item.icon;
+    result.score = ITEM_BASE_SCORE - index as i32;
+    result.actions = vec![item_action];
+    result
+}
+
+pub struct ScriptFilterSearchProvider {
+    pub config: Arc<dyn ConfigService>,
+}
+
+impl SearchProvider for ScriptFilterSearchProvider {
+    fn budget(&self) -> Duration {
+        FILTER_TIMEOUT + Duration::from_millis(100)
+    }
+
+    fn search(&self, context: &SearchContext) -> Result<Vec<SearchResult>, String> {
+        let scripts = all_scripts(&self.config.load_config());
+        let Some((script, query)) = match_filter(&scripts, &context.query) else {
+            return Ok(Vec::new());
+        };
+        let items = run_script_filter(script, query, FILTER_TIMEOUT)?;
+        Ok(items
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| to_result(script, i, item))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::config::AppConfig;
+    use crate::ports::config_port::MockConfigService;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn filter_script(dir: &Path, body: &str) -> ScriptConfig {
+        let path = dir.join("filter.sh");
+        std::fs::write(&path, body).unwrap();
+        ScriptConfig {
+            id: "1".to_string(),
+            alias: "gh".to_string(),
+            path: path.display().to_string(),
+            args: Some("--repo stratos".to_string()),
+            filter: true,
+            ..Default::default()
+        }
+    }
+
+    fn provider(script: ScriptConfig) -> ScriptFilterSearchProvider {
+        let mut config = MockConfigService::new();
+        config.expect_load_config().returning(move || AppConfig {
+            scripts: vec![script.clone()],
+            ..Default::default()
+        });
+        ScriptFilterSearchProvider {
+            config: Arc::new(config),
+        }
+    }
+
+    fn context(query: &str) -> SearchContext {
+        SearchContext {
+            query: query.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_filter_items_become_results() {
+        let dir = tempdir().unwrap();
+        let script = filter_script(
+            dir.path(),
+            r#"printf '[{"title":"%s %s","action":{"type":"open","target":"https://example.com"}},' "$2" "$3"
+printf '{"title":"Copy","subtitle":"token","action":{"type":"copy","text":"abc"}}]'
+"#,
+        );
+        let provider = provider(script);
+
+        let results = provider.search(&context("gh login bug")).unwrap();
+        assert_eq!(results.len(), 2);
+        // Configured args come first, the query is the last argument
+        assert_eq!(results[0].title, "stratos login bug");
+        assert_eq!(results[0].target, "https://example.com");
+        assert_eq!(results[0].actions[0].id, "open_url");
+        assert_eq!(results[1].target, "abc");
+        assert_eq!(results[1].actions[0].id, "copy");
+        assert!(results[0].score > results[1].score);
+
+        assert!(provider.search(&context("ghost")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_broken_and_slow_filters_fail() {
+        let dir = tempdir().unwrap();
+        let broken = filter_script(dir.path(), "echo not json\n");
+        assert!(run_script_filter(&broken, "x", FILTER_TIMEOUT).is_err());
+
+        let pid_file = dir.path().join("sleep.pid");
+        let slow = filter_script(
+            dir.path(),
+            &format!("sleep 5 &\necho $! > {}\nwait\n", pid_file.display()),
+        );
+        let started = std::time::Instant::now();
+        let err = run_script_filter(&slow, "x", Duration::from_millis(100)).unwrap_err();
+        assert!(err.contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // The script's own children go down with it
+        let pid: u32 = std::fs::read_to_string(&pid_file)
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        // Killed and waiting to be reaped by init counts as gone
+        let gone = (0..50).any(|_| {
+            std::thread::sleep(Duration::from_millis(20));
+            std::fs::read_to_string(format!("/proc/{}/stat", pid))
+                .map_or(true, |stat| stat.contains(") Z "))
+        });
+        assert!(gone, "sleep {} outlived the timed out filter", pid);
+    }
+}
@@ -0,0 +1,649 @@
+//! Unified search providers, one per result source, built on the existing ports.
+
+use crate::adapters::script_discovery::all_scripts;
+use crate::domain::file_query::FileQuery;
+use crate::domain::fuzzy::fuzzy_match;
+use crate::domain::plugin::PluginHit;
+use crate::domain::search::{action, ResultKind, SearchResult};
+use crate::ports::app_port::AppRepository;
+use crate::ports::config_port::ConfigService;
+use crate::ports::file_index_port::FileIndex;
+use crate::ports::icon_port::IconResolver;
+use crate::ports::plugin_port::PluginService;
+use crate::ports::search_provider_port::{SearchContext, SearchProvider};
+use crate::ports::window_port::WindowService;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Files only get this many slots so they can't drown out apps and windows.
+const MAX_FILE_RESULTS: usize = 10;
+/// Recent actions offered for an empty query.
+const MAX_RECENT_RESULTS: usize = 8;
+
+/// Scores a candidate against the query, trying its fields in order.
+/// Only a match on the first field (the title) reports highlight positions.
+fn score_fields(query: &str, fields: &[&str]) -> Option<(i32, Vec<usize>)> {
+    fields.iter().enumerate().find_map(|(i, field)| {
+        fuzzy_match(query, field).map(|found| {
+            if i == 0 {
+                (found.score, found.positions)
+            } else {
+                (found.score / 2, Vec::new())
+            }
+        })
+    })
+}
+
+/// Fuzzy-matches `result` on `fields` and adds the history boost, or drops it.
+fn scored(
+    context: &SearchContext,
+    mut result: SearchResult,
+    fields: &[&str],
+) -> Option<SearchResult> {
+    let (score, positions) = score_fields(&context.query, fields)?;
+    result.score = score + context.frecency.boost(&result.id);
+    result.positions = positions;
+    Some(result)
+}
+
+pub struct AppSearchProvider {
+    pub apps: Arc<dyn AppRepository>,
+}
+
+impl SearchProvider for AppSearchProvider {
+    fn budget(&self) -> Duration {
+        Duration::from_millis(150)
+    }
+
+    fn search(&self, context: &SearchContext) -> Result<Vec<SearchResult>, String> {
+        if context.query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut results = Vec::new();
+        for app in self
+            .apps
+            .list_apps()?
+            .into_iter()
+            .filter(|app| !app.no_display)
+        {
+            let mut result = SearchResult::new(
+                ResultKind::App,
+                format!("app:{}", app.id),
+                &app.name,
+                &app.exec,
+                &app.exec,
+            );
+            result.icon = app.icon.clone();
+            // "browser" finds Firefox through its generic name or keywords
+            let mut fields = vec![app.name.as_str()];
+            fields.extend(app.generic_name.as_deref());
+            fields.extend(app.keywords.iter().map(String::as_str));
+            fields.push(&app.exec);
+            results.extend(scored(context, result, &fields));
+
+            // Actions only show up once the query reaches past the app's name,
+            // as in "firefox private", so "fire" lists Firefox alone
+            let prefix = app.name.chars().count() + 2;
+            for action in &app.actions {
+                let title = format!("{}: {}", app.name, action.name);
+                let mut result = SearchResult::new(
+                    ResultKind::App,
+                    format!("app:{}:{}", app.id, action.id),
+                    &title,
+                    &action.exec,
+                    &action.exec,
+                );
+                result.icon = action.icon.clone();
+                results.extend(
+                    scored(context, result, &[&title])
+                        .filter(|result| result.positions.iter().any(|&p| p >= prefix)),
+                );
+            }
+        }
+        Ok(results)
+    }
+}
+
+pub struct WindowSearchProvider {
+    pub windows: Arc<dyn WindowService>,
+    pub icons: Arc<dyn IconResolver>,
+}
+
+impl SearchProvider for WindowSearchProvider {
+    fn budget(&self) -> Duration {
+        Duration::from_millis(150)
+    }
+
+    fn search(&self, context: &SearchContext) -> Result<Vec<SearchResult>, String> {
+        if context.query.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .windows
+            .list_windows()?
+            .into_iter()
+            .filter_map(|window| {
+                let result = SearchResult::new(
+                    ResultKind::Window,
+                    format!("window:{}", window.class),
+                    &window.title,
+                    &window.class,
+                    &window.address,
+                );
+                // Icons are resolved only for windows that matched
+                let mut result = scored(context, result, &[&window.title, &window.class])?;
+                result.icon = window
+                    .icon
+                    .or_else(|| self.icons.resolve_icon(&window.class.to_lowercase()));
+                Some(result)
+            })
+            .collect())
+    }
+}
+
+pub struct ScriptSearchProvider {
+    pub config: Arc<dyn ConfigService>,
+}
+
+impl SearchProvider for ScriptSearchProvider {
+    fn budget(&self) -> Duration {
+        Duration::from_millis(100)
+    }
+
+    fn search(&self, context: &SearchContext) -> Result<Vec<SearchResult>, String> {
+        if context.query.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(all_scripts(&self.config.load_config())
+            .into_iter()
+            .filter_map(|script| {
+                let title = script.title.as_deref().unwrap_or(&script.alias);
+                let mut result = SearchResult::new(
+                    ResultKind::Script,
+                    format!("script:{}", script.alias),
+                    title,
+                    &script.path,
+                    &script.path,
+                );
+                result.icon = script.icon.clone();
+                scored(context, result, &[title, &script.alias])
+            })
+            .collect())
+    }
+}
+
+pub struct FileSearchProvider {
+    pub index: Arc<dyn FileIndex>,
+}
+
+impl SearchProvider for FileSearchProvider {
+    fn budget(&self) -> Duration {
+        Duration::from_millis(300)
+    }
+
+    fn search(&self, context: &SearchContext) -> Result<Vec<SearchResult>, String> {
+        if context.query.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Filter syntax errors are reported by `search_files`; here they just mean no files
+        let Ok(query) = FileQuery::parse(&context.query) else {
+            return Ok(Vec::new());
+        };
+        let mut files = self.index.search(&query, MAX_FILE_RESULTS * 2);
+        context.frecency.rerank_files(&mut files, MAX_FILE_RESULTS);
+        Ok(files
+            .into_iter()
+            .map(|file| {
+                let name_start = file.path.rfind('/').map_or(0, |i| i + 1);
+                let offset = file.path[..name_start].chars().count();
+                let kind = if file.is_dir {
+                    ResultKind::Folder
+                } else {
+                    ResultKind::File
+                };
+                SearchResult {
+                    score: file.score,
+                    positions: file
+                        .positions
+                        .iter()
+                        .filter(|&&p| p >= offset)
+                        .map(|p| p - offset)
+                        .collect(),
+                    ..SearchResult::new(
+                        kind,
+                        format!("file:{}", file.path),
+                        &file.path[name_start..],
+                        &file.path,
+                        &file.path,
+                    )
+                }
+            })
+            .collect())
+    }
+}
+
+/// Recorded history: the most recent entries for an empty query, otherwise the
+/// entries whose name matches, so things used before show up even when their
+/// own source doesn't list them (a file outside the index, say).
+pub struct RecentSearchProvider;
+
+impl SearchProvider for RecentSearchProvider {
+    fn budget(&self) -> Duration {
+        Duration::from_millis(50)
+    }
+
+    fn search(&self, context: &SearchContext) -> Result<Vec<SearchResult>, String> {
+        let results = context.recent.iter().filter_map(|action| {
+            let kind = match action.kind.as_str() {
+                "app" => ResultKind::App,
+                "script" => ResultKind::Script,
+                "file" if std::path::Path::new(&action.content).is_dir() => ResultKind::Folder,
+                "file" => ResultKind::File,
+                // Windows come and go, and AI tools aren't search results
+                _ => return None,
+            };
+            let mut result = SearchResult::new(
+                kind,
+                action.id.clone(),
+                &action.name,
+                &action.content,
+                &action.content,
+            );
+            result.icon = action.icon.clone();
+            if context.query.is_empty() {
+                result.score = context.frecency.boost(&action.id);
+                Some(result)
+            } else {
+                scored(context, result, &[&action.name])
+            }
+        });
+        if context.query.is_empty() {
+            Ok(results.take(MAX_RECENT_RESULTS).collect())
+        } else {
+            Ok(results.collect())
+        }
+    }
+}
+
+/// Results from external plugins. Plugins judge relevance themselves, so nothing
+/// they return is filtered out; unscored results are ranked by their title.
+pub struct PluginSearchProvider {
+    pub plugins: Arc<dyn PluginService>,
+}
+
+/// Extra time on top of the plugin timeout for bookkeeping, so a plugin answering
+/// right at its deadline isn't dropped by the aggregator.
+const PLUGIN_BUDGET_MARGIN: Duration = Duration::from_millis(50);
+
+impl PluginSearchProvider {
+    fn to_result(context: &SearchContext, hit: PluginHit) -> SearchResult {
+        let PluginHit { plugin, result } = hit;
+        let mut converted = SearchResult::new(
+            ResultKind::Plugin,
+            format!("plugin:{}:{}", plugin, result.id),
+            &result.title,
+            &result.subtitle,
+            &result.id,
+        );
+        let (fuzzy, positions) = score_fields(&context.query, &[&result.title]).unwrap_or_default();
+        converted.score = result.score.unwrap_or(fuzzy) + context.frecency.boost(&converted.id);
+        converted.positions = positions;
+        converted.icon = result.icon;
+        if !result.actions.is_empty() {
+            converted.actions = result
+                .actions
+                .iter()
+                .map(|a| action(&a.id, &a.title))
+                .collect();
+        }
+        converted.plugin = Some(plugin);
+        converted
+    }
+}
+
+impl SearchProvider for PluginSearchProvider {
+    fn budget(&self) -> Duration {
+        self.plugins.timeout() + PLUGIN_BUDGET_MARGIN
+    }
+
+    fn search(&self, context: &SearchContext) -> Result<Vec<SearchResult>, String> {
+        if context.query.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .plugins
+            .query(&context.query)
+            .into_iter()
+            .map(|hit| Self::to_result(context, hit))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::action::Action;
+    use crate::domain::apps::{AppAction, AppEntry};
+    use crate::domain::config::{AppConfig, ScriptConfig};
+    use crate::domain::files::FileMatch;
+    use crate::domain::frecency::Frecency;
+    use crate::domain::plugin::{PluginAction, PluginResult};
+    use crate::domain::windows::WindowEntry;
+    use crate::ports::app_port::MockAppRepository;
+    use crate::ports::config_port::MockConfigService;
+    use crate::ports::file_index_port::MockFileIndex;
+    use crate::ports::icon_port::MockIconResolver;
+    use crate::ports::plugin_port::MockPluginService;
+    use crate::ports::window_port::MockWindowService;
+
+    fn context(query: &str) -> SearchContext {
+        SearchContext {
+            query: query.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn used(id: &str, kind: &str, name: &str, content: &str) -> Action {
+        Action {
+            id: id.to_string(),
+            kind: kind.to_string(),
+            content: content.to_string(),
+            name: name.to_string(),
+            icon: None,
+            last_accessed: 1_000,
+            frequency: 3,
+            associations: Vec::new(),
+        }
+    }
+
+    fn apps() -> AppSearchProvider {
+        let mut apps = MockAppRepository::new();
+        apps.expect_list_apps().returning(|| {
+            Ok(vec![
+                AppEntry {
+                    id: "firefox.desktop".to_string(),
+                    name: "Firefox".to_string(),
+                    exec: "firefox %u".to_string(),
+                    icon: Some("firefox.png".to_string()),
+                    generic_name: Some("Web Browser".to_string()),
+                    keywords: vec!["Internet".to_string(), "WWW".to_string()],
+                    actions: vec![AppAction {
+                        id: "new-private-window".to_string(),
+                        name: "New Private Window".to_string(),
+                        exec: "firefox --private-window".to_string(),
+                        icon: Some("private.png".to_string()),
+                    }],
+                    ..Default::default()
+                },
+                AppEntry {
+                    id: "firefox-handler.desktop".to_string(),
+                    name: "Firefox Handler".to_string(),
+                    exec: "firefox --handle %u".to_string(),
+                    no_display: true,
+                    ..Default::default()
+                },
+                AppEntry {
+                    id: "org.gnome.Nautilus.desktop".to_string(),
+                    name: "Files".to_string(),
+                    exec: "nautilus".to_string(),
+                    icon: None,
+                    ..Default::default()
+                },
+            ])
+        });
+        AppSearchProvider {
+            apps: Arc::new(apps),
+        }
+    }
+
+    #[test]
+    fn test_app_provider_matches_name_and_exec() {
+        let provider = apps();
+        // The NoDisplay handler stays out of the results
+        let results = provider.search(&context("fire")).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "app:firefox.desktop");
+        assert_eq!(results[0].icon.as_deref(), Some("firefox.png"));
+        assert_eq!(results[0].actions[0].id, "launch");
+
+        // Exec matches count, without highlighting the title
+        let results = provider.search(&context("nautilus")).unwrap();
+        assert_eq!(results[0].title, "Files");
+        assert!(results[0].positions.is_empty());
+
+        // Generic names and keywords count too
+        let results = provider.search(&context("browser")).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Firefox");
+        assert_eq!(
+            provider.search(&context("www")).unwrap()[0].title,
+            "Firefox"
+        );
+
+        assert!(provider.search(&context("")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_app_provider_matches_desktop_actions() {
+        let provider = apps();
+        let results = provider.search(&context("firefox private")).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Firefox: New Private Window");
+        assert_eq!(results[0].id, "app:firefox.desktop:new-private-window");
+        assert_eq!(results[0].target, "firefox --private-window");
+        assert_eq!(results[0].icon.as_deref(), Some("private.png"));
+        assert_eq!(results[0].actions[0].id, "launch");
+
+        assert_eq!(provider.search(&context("private")).unwrap().len(), 1);
+        // The app's name alone doesn't list its actions
+        let titles: Vec<String> = provider
+            .search(&context("firefox"))
+            .unwrap()
+            .into_iter()
+            .map(|result| result.title)
+            .collect();
+        assert_eq!(titles, vec!["Firefox"]);
+    }
+
+    #[test]
+    fn test_history_boosts_app_scores() {
+        let provider = apps();
+        let plain = provider.search(&context("f")).unwrap();
+        let boosted = provider
+            .search(&SearchContext {
+                frecency: Frecency::from_actions(
+                    &[used(
+                        "app:org.gnome.Nautilus.desktop",
+                        "app",
+                        "Files",
+                        "nautilus",
+                    )],
+                    "",
+                    2_000,
+                ),
+                ..context("f")
+            })
+            .unwrap();
+        let score = |results: &[SearchResult], title: &str| {
+            results.iter().find(|r| r.title == title).unwrap().score
+        };
+        assert!(score(&boosted, "Files") > score(&plain, "Files"));
+        assert_eq!(score(&boosted, "Firefox"), score(&plain, "Firefox"));
+    }
+
+    #[test]
+    fn test_window_provider_resolves_icons_for_matches() {
+        let mut windows = MockWindowService::new();
+        windows.expect_list_windows().returning(|| {
+            Ok(vec![
+                WindowEntry {
+                    title: "Inbox".to_string(),
+                    class: "Thunderbird".to_string(),
+                    address: "0x1".to_string(),
+                    icon: None,
+                },
+                WindowEntry {
+                    title: "Terminal".to_string(),
+                    class: "foot".to_string(),
+                    address: "0x2".to_string(),
+                    icon: None,
+                },
+            ])
+        });
+        let mut icons = MockIconResolver::new();
+        icons
+            .expect_resolve_icon()
+            .withf(|name| name == "thunderbird")
+            .times(1)
+            .returning(|_| Some("thunderbird.png".to_string()));
+        let provider = WindowSearchProvider {
+            windows: Arc::new(windows),
+            icons: Arc::new(icons),
+        };
+
+        let results = provider.search(&context("thunder")).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].target, "0x1");
+        assert_eq!(results[0].id, "window:Thunderbird");
+        assert_eq!(results[0].icon.as_deref(), Some("thunderbird.png"));
+    }
+
+    #[test]
+    fn test_script_provider() {
+        let mut config = MockConfigService::new();
+        config.expect_load_config().returning(|| AppConfig {
+            scripts: vec![ScriptConfig {
+                id: "1".to_string(),
+                alias: "fire-drill".to_string(),
+                path: "/scripts/drill.sh".to_string(),
+                args: None,
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        let provider = ScriptSearchProvider {
+            config: Arc::new(config),
+        };
+
+        let results = provider.search(&context("drill")).unwrap();
+        assert_eq!(results[0].kind, ResultKind::Script);
+        assert_eq!(results[0].target, "/scripts/drill.sh");
+        assert_eq!(results[0].actions[0].id, "run");
+    }
+
+    #[test]
+    fn test_file_provider_titles_by_file_name() {
+        let mut index = MockFileIndex::new();
+        index
+            .expect_search()
+            .withf(|_, limit| *limit == MAX_FILE_RESULTS * 2)
+            .returning(|_, _| {
+                vec![
+                    FileMatch {
+                        path: "/home/me/fire.txt".to_string(),
+                        is_dir: false,
+                        score: 40,
+                        positions: vec![9, 10, 11, 12],
+                        root: None,
+                    },
+                    FileMatch {
+                        path: "/home/me/fireworks".to_string(),
+                        is_dir: true,
+                        score: 30,
+                        positions: vec![9, 10, 11, 12],
+                        root: None,
+                    },
+                ]
+            });
+        let provider = FileSearchProvider {
+            index: Arc::new(index),
+        };
+
+        let results = provider.search(&context("fire")).unwrap();
+        assert_eq!(results[0].title, "fire.txt");
+        assert_eq!(results[0].positions, vec![0, 1, 2, 3]);
+        assert_eq!(results[0].kind, ResultKind::File);
+        assert_eq!(results[1].kind, ResultKind::Folder);
+
+        // Broken filters mean no file results, not an error
+        assert!(provider
+            .search(&context("fire size:huge"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_recent_provider() {
+        let recent = vec![
+            used("app:firefox", "app", "Firefox", "firefox"),
+            used("window:foot", "window", "Terminal", "foot"),
+            used("file:/tmp/notes.md", "file", "notes.md", "/tmp/notes.md"),
+        ];
+        let all = RecentSearchProvider
+            .search(&SearchContext {
+                recent: recent.clone(),
+                ..context("")
+            })
+            .unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[1].kind, ResultKind::File);
+
+        let matched = RecentSearchProvider
+            .search(&SearchContext {
+                recent,
+                ..context("notes")
+            })
+            .unwrap();
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].target, "/tmp/notes.md");
+    }
+
+    #[test]
+    fn test_plugin_provider_keeps_plugin_results_and_actions() {
+        let mut plugins = MockPluginService::new();
+        plugins
+            .expect_query()
+            .withf(|query| query == "inc")
+            .returning(|_| {
+                let result = |id: &str, title: &str, score| PluginResult {
+                    id: id.to_string(),
+                    title: title.to_string(),
+                    subtitle: "Open ticket".to_string(),
+                    icon: None,
+                    score,
+                    actions: Vec::new(),
+                };
+                vec![
+                    PluginHit {
+                        plugin: "tickets".to_string(),
+                        result: PluginResult {
+                            actions: vec![PluginAction {
+                                id: "assign".to_string(),
+                                title: "Assign to Me".to_string(),
+                            }],
+                            ..result("INC-1", "Login broken", Some(500))
+                        },
+                    },
+                    PluginHit {
+                        plugin: "tickets".to_string(),
+                        result: result("INC-2", "Include path wrong", None),
+                    },
+                ]
+            });
+        let provider = PluginSearchProvider {
+            plugins: Arc::new(plugins),
+        };
+
+        let results = provider.search(&context("inc")).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].id, "plugin:tickets:INC-1");
+        assert_eq!(results[0].score, 500);
+        assert_eq!(results[0].plugin.as_deref(), Some("tickets"));
+        assert_eq!(results[0].actions[0].id, "assign");
+
+        // Unscored results fall back to fuzzy matching the title
+        assert_eq!(results[1].positions, vec![0, 1, 2]);
+        assert!(results[1].score > 0);
+        assert_eq!(results[1].actions[0].id, "open");
+    }
+}
@@ -0,0 +1,422 @@
+//! Runs search plugins as child processes speaking JSON lines over stdio.
+//!
+//! Each plugin gets a writer thread feeding its stdin and a reader thread handing
+//! replies to whoever waits for that query id, so a plugin that is slow to read or
+//! to answer never holds up the next query.
+
+use crate::domain::config::PluginConfig;
+use crate::domain::plugin::{HostMessage, PluginHit, PluginMessage};
+use crate::ports::plugin_port::PluginService;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(500);
+/// A plugin that crashes more often than this within `RESTART_WINDOW` stays down
+/// until the config is saved again.
+const MAX_RESTARTS: usize = 3;
+const RESTART_WINDOW: Duration = Duration::from_secs(60);
+
+/// The query a plugin's replies currently go to. Asking a new query replaces it, which
+/// also ends the wait for the previous one.
+type Replies = Arc<Mutex<Option<(u64, Sender<PluginMessage>)>>>;
+
+struct PluginProcess {
+    child: Child,
+    /// Lines for the writer thread; dropping it closes the plugin's stdin.
+    input: Sender<String>,
+}
+
+impl PluginProcess {
+    fn spawn(config: &PluginConfig, replies: Replies) -> Result<Self, String> {
+        let args = match &config.args {
+            Some(args) => shell_words::split(args).map_err(|e| e.to_string())?,
+            None => Vec::new(),
+        };
+        let mut child = Command::new(&config.command)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| format!("Failed to start plugin {}: {}", config.id, e))?;
+        let mut stdin = child.stdin.take().ok_or("Failed to open plugin stdin")?;
+        let stdout = child.stdout.take().ok_or("Failed to open plugin stdout")?;
+
+        // Ends once the plugin is stopped or stops reading
+        let (input, lines) = mpsc::channel::<String>();
+        std::thread::spawn(move || {
+            for line in lines {
+                if stdin
+                    .write_all(line.as_bytes())
+                    .and_then(|_| stdin.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        // Ends at EOF, i.e. when the plugin exits
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                let Some(message) = PluginMessage::parse(&line) else {
+                    continue;
+                };
+                let PluginMessage::Results { id, .. } = &message;
+                // Late answers to earlier queries are dropped
+                if let Some((current, tx)) = &*replies.lock().unwrap() {
+                    if current == id {
+                        let _ = tx.send(message);
+                    }
+                }
+            }
+        });
+
+        Ok(Self { child, input })
+    }
+
+    fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    fn send(&mut self, message: &HostMessage) -> Result<(), String> {
+        self.input
+            .send(message.to_line())
+            .map_err(|_| "Plugin stopped reading its input".to_string())
+    }
+
+    fn stop(mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+struct PluginSlot {
+    config: PluginConfig,
+    process: Option<PluginProcess>,
+    restarts: Vec<Instant>,
+    /// Shared with the reader thread of every process started for this slot.
+    replies: Replies,
+}
+
+impl PluginSlot {
+    fn new(config: PluginConfig) -> Self {
+        Self {
+            config,
+            process: None,
+            restarts: Vec::new(),
+            replies: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        self.config
+            .timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_TIMEOUT)
+    }
+
+    /// The running process, (re)started if needed and allowed.
+    fn process(&mut self) -> Result<&mut PluginProcess, String> {
+        let alive = self.process.as_mut().is_some_and(PluginProcess::is_alive);
+        if !alive {
+            if let Some(dead) = self.process.take() {
+                dead.stop();
+                self.restarts.retain(|at| at.elapsed() < RESTART_WINDOW);
+                if self.restarts.len() >= MAX_RESTARTS {
+                    return Err(format!("Plugin {} keeps crashing", self.config.id));
+                }
+                self.restarts.push(Instant::now());
+            } else if self.restarts.len() >= MAX_RESTARTS {
+                return Err(format!("Plugin {} keeps crashing", self.config.id));
+            }
+            self.process = Some(PluginProcess::spawn(&self.config, self.replies.clone())?);
+        }
+        Ok(self.process.as_mut().unwrap())
+    }
+
+    /// Sends `message`, restarting the plugin once if the pipe turns out to be broken.
+    fn send(&mut self, message: &HostMessage) -> Result<(), String> {
+        if self.process()?.send(message).is_ok() {
+            return Ok(());
+        }
+        if let Some(mut process) = self.process.take() {
+            // Make the next `process()` call see it as crashed
+            let _ = process.child.kill();
+            let _ = process.child.wait();
+            self.process = Some(process);
+        }
+        self.process()?.send(message)
+    }
+
+    /// Sends query `id`, routing its replies to `tx` instead of the previous query's.
+    fn ask(
+        &mut self,
+        id: u64,
+        message: &HostMessage,
+        tx: Sender<PluginMessage>,
+    ) -> Result<(), String> {
+        *self.replies.lock().unwrap() = Some((id, tx));
+        self.send(message)
+    }
+}
+
+pub struct StdioPluginHost {
+    slots: Mutex<Vec<Arc<Mutex<PluginSlot>>>>,
+    next_query: AtomicU64,
+}
+
+impl StdioPluginHost {
+    pub fn new() -> Self {
+        Self {
+            slots: Mutex::new(Vec::new()),
+            next_query: AtomicU64::new(1),
+        }
+    }
+
+    fn enabled_slots(&self) -> Vec<Arc<Mutex<PluginSlot>>> {
+        self.slots.lock().unwrap().clone()
+    }
+}
+
+impl Default for StdioPluginHost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for StdioPluginHost {
+    fn drop(&mut self) {
+        self.configure(&[]);
+    }
+}
+
+impl PluginService for StdioPluginHost {
+    fn configure(&self, plugins: &[PluginConfig]) {
+        let mut slots = self.slots.lock().unwrap();
+        let mut previous: Vec<_> = slots.drain(..).collect();
+
+        let runnable = plugins
+            .iter()
+            .filter(|p| p.enabled && !p.command.trim().is_empty());
+        for config in runnable {
+            // Unchanged plugins keep running, anything else starts fresh
+            let kept = previous
+                .iter()
+                .position(|slot| slot.lock().unwrap().config == *config)
+                .map(|i| previous.remove(i));
+            let slot = kept.unwrap_or_else(|| {
+                let mut slot = PluginSlot::new(config.clone());
+                let _ = slot.process();
+                Arc::new(Mutex::new(slot))
+            });
+            slots.push(slot);
+        }
+
+        for slot in previous {
+            if let Some(process) = slot.lock().unwrap().process.take() {
+                process.stop();
+            }
+        }
+    }
+
+    fn query(&self, query: &str) -> Vec<PluginHit> {
+        let id = self.next_query.fetch_add(1, Ordering::SeqCst);
+        let message = HostMessage::Query {
+            id,
+            query: query.to_string(),
+        };
+        let started = Instant::now();
+
+        // Send to everyone first so plugins work in parallel, then collect without
+        // holding any slot
+        let mut asked = Vec::new();
+        for slot in self.enabled_slots() {
+            let mut slot = slot.lock().unwrap();
+            let (tx, replies) = mpsc::channel();
+            if slot.ask(id, &message, tx).is_ok() {
+                asked.push((slot.config.id.clone(), started + slot.timeout(), replies));
+            }
+        }
+
+        let mut hits = Vec::new();
+        for (plugin, deadline, replies) in asked {
+            loop {
+                let wait = deadline.saturating_duration_since(Instant::now());
+                match replies.recv_timeout(wait) {
+                    Ok(PluginMessage::Results { results, done, .. }) => {
+                        hits.extend(results.into_iter().map(|result| PluginHit {
+                            plugin: plugin.clone(),
+                            result,
+                        }));
+                        if done {
+                            break;
+                        }
+                    }
+                    // Out of time, or a newer query took over
+                    Err(_) => break,
+                }
+            }
+        }
+        hits
+    }
+
+    fn timeout(&self) -> Duration {
+        self.enabled_slots()
+            .iter()
+            .map(|slot| slot.lock().unwrap().timeout())
+            .max()
+            .unwrap_or(DEFAULT_TIMEOUT)
+    }
+
+    fn run_action(&self, plugin: &str, result_id: &str, action: &str) -> Result<(), String> {
+        let slot = self
+            .enabled_slots()
+            .into_iter()
+            .find(|slot| slot.lock().unwrap().config.id == plugin)
+            .ok_or_else(|| format!("Plugin {} is not running", plugin))?;
+        let mut slot = slot.lock().unwrap();
+        slot.send(&HostMessage::Action {
+            result_id: result_id.to_string(),
+            action: action.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    /// A plugin answering every query with one result, logging actions to `log`.
+    /// With `crash_after_query`, it exits right after answering.
+    fn write_plugin(dir: &Path, crash_after_query: bool) -> PluginConfig {
+        let script = dir.join("plugin.sh");
+        let exit = if crash_after_query { "exit 1" } else { ":" };
+        std::fs::write(
+            &script,
+            format!(
+                r#"while IFS= read -r line; do
+  case "$line" in
+    *'"type":"query"'*)
+      id=$(printf '%s' "$line" | sed 's/.*"id":\([0-9]*\).*/\1/')
+      printf '{{"type":"results","id":%s,"results":[{{"id":"r1","title":"First"}}]}}\n' "$id"
+      printf '{{"type":"results","id":%s,"results":[{{"id":"r2","title":"Second"}}],"done":true}}\n' "$id"
+      {exit};;
+    *'"type":"action"'*)
+      printf '%s\n' "$line" >> "{log}";;
+  esac
+done
+"#,
+                exit = exit,
+                log = dir.join("actions.log").display()
+            ),
+        )
+        .unwrap();
+        PluginConfig {
+            id: "tickets".to_string(),
+            command: "sh".to_string(),
+            args: Some(script.display().to_string()),
+            enabled: true,
+            timeout_ms: Some(2_000),
+        }
+    }
+
+    #[test]
+    fn test_query_collects_batches_and_routes_actions() {
+        let dir = tempdir().unwrap();
+        let host = StdioPluginHost::new();
+        host.configure(&[write_plugin(dir.path(), false)]);
+
+        let hits = host.query("inc");
+        let titles: Vec<&str> = hits.iter().map(|h| h.result.title.as_str()).collect();
+        assert_eq!(titles, vec!["First", "Second"]);
+        assert_eq!(hits[0].plugin, "tickets");
+
+        host.run_action("tickets", "r2", "open").unwrap();
+        // The action is handled asynchronously by the plugin; a query round-trip syncs up
+        host.query("sync");
+        let log = std::fs::read_to_string(dir.path().join("actions.log")).unwrap();
+        assert!(log.contains(r#""result_id":"r2""#));
+
+        assert!(host.run_action("roster", "x", "open").is_err());
+    }
+
+    #[test]
+    fn test_crashed_plugin_is_restarted_a_few_times() {
+        let dir = tempdir().unwrap();
+        let host = StdioPluginHost::new();
+        host.configure(&[write_plugin(dir.path(), true)]);
+
+        for _ in 0..=MAX_RESTARTS {
+            assert_eq!(host.query("inc").len(), 2);
+            // Give the process time to exit before the next query
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        assert!(host.query("inc").is_empty());
+    }
+
+    #[test]
+    fn test_slow_answer_does_not_hold_up_the_next_query() {
+        let dir = tempdir().unwrap();
+        let script = dir.path().join("slow.sh");
+        // Answers in the background, taking a second for "slow"
+        std::fs::write(
+            &script,
+            r#"while IFS= read -r line; do
+  id=$(printf '%s' "$line" | sed 's/.*"id":\([0-9]*\).*/\1/')
+  case "$line" in *'"query":"slow"'*) delay=1;; *) delay=0;; esac
+  (sleep $delay; printf '{"type":"results","id":%s,"results":[{"id":"r","title":"Hit"}],"done":true}\n' "$id") &
+done
+"#,
+        )
+        .unwrap();
+        let host = Arc::new(StdioPluginHost::new());
+        host.configure(&[PluginConfig {
+            id: "slow".to_string(),
+            command: "sh".to_string(),
+            args: Some(script.display().to_string()),
+            enabled: true,
+            timeout_ms: Some(3_000),
+        }]);
+
+        let slow = {
+            let host = host.clone();
+            std::thread::spawn(move || host.query("slow"))
+        };
+        std::thread::sleep(Duration::from_millis(200));
+        let started = Instant::now();
+        assert_eq!(host.query("fast").len(), 1);
+        assert!(started.elapsed() < Duration::from_millis(700));
+
+        // The newer query took over, so the slow one stops waiting
+        assert!(slow.join().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_disabled_and_silent_plugins() {
+        let dir = tempdir().unwrap();
+        let host = StdioPluginHost::new();
+        let mut disabled = write_plugin(dir.path(), false);
+        disabled.enabled = false;
+        let silent = PluginConfig {
+            id: "silent".to_string(),
+            command: "sleep".to_string(),
+            args: Some("5".to_string()),
+            enabled: true,
+            timeout_ms: Some(100),
+        };
+        host.configure(&[disabled, silent]);
+
+        let started = Instant::now();
+        assert!(host.query("inc").is_empty());
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(host.timeout(), Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,213 @@
+use crate::adapters::fs_app_repository::{current_desktops, dirs_from_env, xdg_data_dirs};
+use crate::domain::content_search::looks_binary;
+use crate::domain::mime::{open_with, MimeApps, MimeDatabase, OpenWith};
+use crate::ports::app_port::AppRepository;
+use crate::ports::mime_port::MimeResolver;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+/// Bytes read to tell text from binary files no glob matches.
+const SNIFF_BYTES: usize = 1024;
+
+/// Reads the shared-mime-info database from the XDG data dirs and the
+/// `mimeapps.list` files from the XDG config and data dirs.
+pub struct XdgMimeResolver {
+    apps: Arc<dyn AppRepository>,
+    /// `XDG_DATA_HOME` first, then `XDG_DATA_DIRS`.
+    data_dirs: Vec<PathBuf>,
+    /// `XDG_CONFIG_HOME` first, then `XDG_CONFIG_DIRS`.
+    config_dirs: Vec<PathBuf>,
+    desktops: Vec<String>,
+    // Loaded on first use; the database only changes on package installs
+    database: OnceLock<MimeDatabase>,
+}
+
+impl XdgMimeResolver {
+    pub fn new(apps: Arc<dyn AppRepository>) -> Self {
+        let data_dirs = xdg_data_dirs();
+        let mut config_dirs: Vec<PathBuf> = dirs::config_dir().into_iter().collect();
+        config_dirs.extend(dirs_from_env("XDG_CONFIG_DIRS", "/etc/xdg"));
+        Self::with_dirs(apps, data_dirs, config_dirs, current_desktops())
+    }
+
+    pub fn with_dirs(
+        apps: Arc<dyn AppRepository>,
+        data_dirs: Vec<PathBuf>,
+        config_dirs: Vec<PathBuf>,
+        desktops: Vec<String>,
+    ) -> Self {
+        Self {
+            apps,
+            data_dirs,
+            config_dirs,
+            desktops,
+            database: OnceLock::new(),
+        }
+    }
+
+    fn database(&self) -> &MimeDatabase {
+        self.database.get_or_init(|| {
+            let mut database = MimeDatabase::default();
+            for dir in &self.data_dirs {
+                let read = |name: &str| {
+                    std::fs::read_to_string(dir.join("mime").join(name)).unwrap_or_default()
+                };
+                database.add(&read("globs2"), &read("aliases"), &read("subclasses"));
+            }
+            database
+        })
+    }
+
+    /// Every `mimeapps.list` in precedence order: desktop-specific before generic,
+    /// config dirs before data dirs.
+    fn mimeapps_lists(&self) -> Vec<MimeApps> {
+        let names: Vec<String> = self
+            .desktops
+            .iter()
+            .map(|desktop| format!("{}-mimeapps.list", desktop.to_lowercase()))
+            .chain(std::iter::once("mimeapps.list".to_string()))
+            .collect();
+        let config = self.config_dirs.iter().cloned();
+        let data = self.data_dirs.iter().map(|dir| dir.join("applications"));
+        config
+            .chain(data)
+            .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+            .filter_map(|path| std::fs::read_to_string(path).ok())
+            .map(|content| MimeApps::parse(&content))
+            .collect()
+    }
+}
+
+/// Text or binary, for files no glob matches.
+fn sniff(path: &Path) -> Option<String> {
+    let mut buffer = vec![0; SNIFF_BYTES];
+    let n = std::fs::File::open(path)
+        .and_then(|mut file| file.read(&mut buffer))
+        .ok()?;
+    Some(if looks_binary(&buffer[..n]) {
+        "application/octet-stream".to_string()
+    } else {
+        "text/plain".to_string()
+    })
+}
+
+impl MimeResolver for XdgMimeResolver {
+    fn mime_type(&self, path: &str) -> Option<String> {
+        let path = Path::new(path);
+        if path.is_dir() {
+            return Some("inode/directory".to_string());
+        }
+        let name = path.file_name()?.to_string_lossy();
+        self.database()
+            .type_for_name(&name)
+            .map(|mime| self.database().canonical(&mime))
+            .or_else(|| sniff(path))
+    }
+
+    fn open_with(&self, path: &str) -> Result<OpenWith, String> {
+        let Some(mime) = self.mime_type(path) else {
+            return Ok(OpenWith::default());
+        };
+        let types = self.database().with_parents(&mime);
+        let apps = self.apps.list_apps()?;
+        Ok(open_with(&types, &self.mimeapps_lists(), &apps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::apps::AppEntry;
+    use crate::ports::app_port::MockAppRepository;
+    use tempfile::tempdir;
+
+    fn app(name: &str, file: &str, mime_types: &[&str]) -> AppEntry {
+        AppEntry {
+            id: file.to_string(),
+            name: name.to_string(),
+            exec: name.to_lowercase(),
+            mime_types: mime_types.iter().map(|m| m.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resolves_types_and_apps_from_xdg_dirs() {
+        let root = tempdir().unwrap();
+        let data = root.path().join("share");
+        let config = root.path().join("config");
+        std::fs::create_dir_all(data.join("mime")).unwrap();
+        std::fs::create_dir_all(data.join("applications")).unwrap();
+        std::fs::create_dir_all(&config).unwrap();
+        std::fs::write(
+            data.join("mime/globs2"),
+            "50:image/png:*.png\n50:image/x-png:*.xpng\n50:text/html:*.html\n",
+        )
+        .unwrap();
+        std::fs::write(data.join("mime/aliases"), "image/x-png image/png\n").unwrap();
+        std::fs::write(
+            data.join("applications/mimeapps.list"),
+            "[Default Applications]\nimage/png=eog.desktop;\ntext/html=handler.desktop;\n",
+        )
+        .unwrap();
+        // The desktop-specific list beats the generic one
+        std::fs::write(
+            config.join("gnome-mimeapps.list"),
+            "[Default Applications]\nimage/png=gimp.desktop;\n",
+        )
+        .unwrap();
+        let text = root.path().join("notes");
+        std::fs::write(&text, "plain words").unwrap();
+        let binary = root.path().join("blob");
+        std::fs::write(&binary, [0u8, 1, 2]).unwrap();
+
+        let mut apps = MockAppRepository::new();
+        apps.expect_list_apps().returning(|| {
+            Ok(vec![
+                app("Eog", "eog.desktop", &["image/png"]),
+                app("Gimp", "gimp.desktop", &["image/png"]),
+                app("Gedit", "gedit.desktop", &["text/plain"]),
+                // Handler-only entries are hidden from menus but open files all the same
+                AppEntry {
+                    no_display: true,
+                    ..app("Handler", "handler.desktop", &["text/html"])
+                },
+            ])
+        });
+        let resolver = XdgMimeResolver::with_dirs(
+            Arc::new(apps),
+            vec![data],
+            vec![config],
+            vec!["GNOME".to_string()],
+        );
+
+        assert_eq!(
+            resolver.mime_type("/pics/a.xpng").as_deref(),
+            Some("image/png")
+        );
+        assert_eq!(
+            resolver.mime_type(&text.display().to_string()).as_deref(),
+            Some("text/plain")
+        );
+        assert_eq!(
+            resolver.mime_type(&binary.display().to_string()).as_deref(),
+            Some("application/octet-stream")
+        );
+        assert_eq!(
+            resolver
+                .mime_type(&root.path().display().to_string())
+                .as_deref(),
+            Some("inode/directory")
+        );
+
+        let resolved = resolver.open_with("/pics/a.png").unwrap();
+        assert_eq!(resolved.default.unwrap().name, "Gimp");
+        assert_eq!(resolved.others.len(), 1);
+        assert_eq!(resolved.others[0].name, "Eog");
+        let resolved = resolver.open_with(&text.display().to_string()).unwrap();
+        assert_eq!(resolved.default.unwrap().name, "Gedit");
+        let resolved = resolver.open_with("/pages/index.html").unwrap();
+        assert_eq!(resolved.default.unwrap().name, "Handler");
+    }
+}
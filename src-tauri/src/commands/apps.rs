@@ -1,12 +1,16 @@
-use crate::domain::apps::AppEntry;
+use crate::domain::apps::{AppAction, AppEntry};
+use crate::domain::desktop_exec::{accepts_targets, expand_exec, ExecContext};
+use crate::domain::mime::OpenWith;
 use crate::ports::app_port::AppRepository;
 use crate::state::AppState;
 use std::os::unix::process::CommandExt;
 use tauri::State;
 
-// Handler logic for testing
+// Handler logic for testing; `NoDisplay` entries only serve as file handlers
 pub fn list_apps_logic(repo: &dyn AppRepository) -> Result<Vec<AppEntry>, String> {
-    repo.list_apps()
+    let mut apps = repo.list_apps()?;
+    apps.retain(|app| !app.no_display);
+    Ok(apps)
 }
 
 #[tauri::command]
@@ -14,31 +18,119 @@ pub async fn list_apps(state: State<'_, AppState>) -> Result<Vec<AppEntry>, Stri
     list_apps_logic(&*state.app_repository)
 }
 
-// Helper to clean exec command - exposed for testing
+// Helper to split an exec line into program and arguments - exposed for testing
 pub fn parse_exec_command(exec_cmd: &str) -> Option<(String, Vec<String>)> {
-    let cleaned = exec_cmd
-        .replace("%f", "")
-        .replace("%F", "")
-        .replace("%u", "")
-        .replace("%U", "")
-        .replace("%i", "")
-        .replace("%c", "")
-        .replace("%k", "");
-
-    let parts = shell_words::split(&cleaned).ok()?;
-    if parts.is_empty() {
+    let mut argv = expand_exec(exec_cmd, &ExecContext::default())
+        .ok()?
+        .into_iter()
+        .next()?;
+    if argv.is_empty() {
         return None;
     }
+    let cmd = argv.remove(0);
+    Some((cmd, argv))
+}
 
-    let cmd = parts[0].clone();
-    let args = parts[1..].to_vec();
-    Some((cmd, args))
+/// What an app or desktop action is launched from.
+struct Launchable<'a> {
+    exec: &'a str,
+    icon: Option<&'a str>,
+    name: &'a str,
+    desktop_file: Option<&'a str>,
 }
 
-#[tauri::command]
-pub async fn launch_app(exec_cmd: String) -> Result<(), String> {
-    let (cmd, args) = parse_exec_command(&exec_cmd).ok_or_else(|| "Empty command".to_string())?;
+/// Finds `app_id` (a desktop-file id, or `<id>:<action>` for a desktop action)
+/// among the listed apps, falling back to the app or action with the exec line
+/// `exec_cmd`, which older history and shortcuts are keyed by.
+fn find_launchable<'a>(
+    apps: &'a [AppEntry],
+    app_id: &str,
+    exec_cmd: Option<&str>,
+) -> Option<Launchable<'a>> {
+    let of_app = |app: &'a AppEntry| Launchable {
+        exec: &app.exec,
+        icon: app.icon.as_deref(),
+        name: &app.name,
+        desktop_file: app.desktop_file.as_deref(),
+    };
+    let of_action = |app: &'a AppEntry, action: &'a AppAction| Launchable {
+        exec: &action.exec,
+        icon: action.icon.as_deref(),
+        name: &action.name,
+        desktop_file: app.desktop_file.as_deref(),
+    };
+    let by_id = || {
+        if let Some(app) = apps.iter().find(|app| app.id == app_id) {
+            return Some(of_app(app));
+        }
+        let (app_id, action_id) = app_id.rsplit_once(':')?;
+        let app = apps.iter().find(|app| app.id == app_id)?;
+        let action = app.actions.iter().find(|action| action.id == action_id)?;
+        Some(of_action(app, action))
+    };
+    by_id().or_else(|| {
+        let exec_cmd = exec_cmd?;
+        apps.iter().find_map(|app| {
+            if app.exec == exec_cmd {
+                return Some(of_app(app));
+            }
+            app.actions
+                .iter()
+                .find(|action| action.exec == exec_cmd)
+                .map(|action| of_action(app, action))
+        })
+    })
+}
+
+/// A command that isn't a listed app: shell words, as script filter items and
+/// skills give them, with no field codes.
+fn plain_command(exec_cmd: &str, targets: &[String]) -> Result<Vec<Vec<String>>, String> {
+    if !targets.is_empty() {
+        return Err("This app can't open files".to_string());
+    }
+    let argv = shell_words::split(exec_cmd).map_err(|e| e.to_string())?;
+    if argv.is_empty() {
+        return Err("Empty command".to_string());
+    }
+    Ok(vec![argv])
+}
 
+/// The command lines that launch the app `app_id` with `targets`, its Exec field
+/// codes filled in from the app or desktop action being launched. Without an app
+/// id, or when the app isn't listed, `exec_cmd` runs as a plain command.
+pub fn launch_commands_logic(
+    repo: &dyn AppRepository,
+    app_id: Option<&str>,
+    exec_cmd: Option<&str>,
+    targets: &[String],
+) -> Result<Vec<Vec<String>>, String> {
+    // Plain commands don't need the desktop files scanned
+    let Some(app_id) = app_id else {
+        return plain_command(exec_cmd.ok_or("No app to launch")?, targets);
+    };
+    let apps = repo.list_apps().unwrap_or_default();
+    let Some(launchable) = find_launchable(&apps, app_id, exec_cmd) else {
+        return match exec_cmd {
+            Some(exec_cmd) => plain_command(exec_cmd, targets),
+            None => Err(format!("App '{}' is not installed", app_id)),
+        };
+    };
+    if !targets.is_empty() && !accepts_targets(launchable.exec) {
+        return Err("This app can't open files".to_string());
+    }
+    let context = ExecContext {
+        targets,
+        icon: launchable.icon,
+        name: launchable.name,
+        desktop_file: launchable.desktop_file,
+    };
+    expand_exec(launchable.exec, &context)
+}
+
+fn spawn_detached(argv: &[String]) -> Result<(), String> {
+    let (cmd, args) = argv
+        .split_first()
+        .ok_or_else(|| "Empty command".to_string())?;
     std::process::Command::new(cmd)
         .args(args)
         .stdin(std::process::Stdio::null())
@@ -47,10 +139,54 @@ pub async fn launch_app(exec_cmd: String) -> Result<(), String> {
         .process_group(0) // Sets the process group ID to the child's PID effectively creating a new session
         .spawn()
         .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
+/// Launches an app by its desktop-file id, or by exec line for commands that
+/// aren't listed apps (script filter items, skills).
+#[tauri::command]
+pub async fn launch_app(
+    state: State<'_, AppState>,
+    app_id: Option<String>,
+    exec_cmd: Option<String>,
+) -> Result<(), String> {
+    let commands = launch_commands_logic(
+        &*state.app_repository,
+        app_id.as_deref(),
+        exec_cmd.as_deref(),
+        &[],
+    )?;
+    for argv in commands {
+        spawn_detached(&argv)?;
+    }
     Ok(())
 }
 
+/// Opens files or URLs in a specific app ("Open with…"), given its desktop-file id.
+#[tauri::command]
+pub async fn launch_app_with(
+    state: State<'_, AppState>,
+    app_id: String,
+    paths: Vec<String>,
+) -> Result<(), String> {
+    for argv in launch_commands_logic(&*state.app_repository, Some(&app_id), None, &paths)? {
+        spawn_detached(&argv)?;
+    }
+    Ok(())
+}
+
+/// The default app and the other apps that can open `path`, for "Open with…".
+#[tauri::command]
+pub async fn get_open_with_apps(
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<OpenWith, String> {
+    let resolver = state.mime_resolver.clone();
+    tokio::task::spawn_blocking(move || resolver.open_with(&path))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,11 +196,20 @@ mod tests {
     fn test_list_apps() {
         let mut mock = MockAppRepository::new();
         mock.expect_list_apps().times(1).returning(|| {
-            Ok(vec![AppEntry {
-                name: "Test App".to_string(),
-                exec: "test".to_string(),
-                icon: None,
-            }])
+            Ok(vec![
+                AppEntry {
+                    name: "Test App".to_string(),
+                    exec: "test".to_string(),
+                    icon: None,
+                    ..Default::default()
+                },
+                AppEntry {
+                    name: "Handler".to_string(),
+                    exec: "handler %u".to_string(),
+                    no_display: true,
+                    ..Default::default()
+                },
+            ])
         });
 
         let result = list_apps_logic(&mock);
@@ -109,4 +254,107 @@ mod tests {
         // Empty/Invalid
         assert_eq!(parse_exec_command(""), None);
     }
+
+    fn viewer() -> MockAppRepository {
+        let mut mock = MockAppRepository::new();
+        mock.expect_list_apps().returning(|| {
+            Ok(vec![AppEntry {
+                id: "org.example.Viewer.desktop".to_string(),
+                name: "Image Viewer".to_string(),
+                exec: "viewer --class=%c %i %F".to_string(),
+                icon: Some("viewer".to_string()),
+                desktop_file: Some("/apps/viewer.desktop".to_string()),
+                actions: vec![AppAction {
+                    id: "slideshow".to_string(),
+                    name: "Slideshow".to_string(),
+                    exec: "viewer --slideshow %k %f".to_string(),
+                    icon: None,
+                }],
+                ..Default::default()
+            }])
+        });
+        mock
+    }
+
+    #[test]
+    fn test_launch_commands_fill_in_app_details() {
+        let mock = viewer();
+        let paths = vec!["/pics/a.png".to_string(), "/pics/b.png".to_string()];
+
+        assert_eq!(
+            launch_commands_logic(&mock, Some("org.example.Viewer.desktop"), None, &paths).unwrap(),
+            vec![vec![
+                "viewer",
+                "--class=Image Viewer",
+                "--icon",
+                "viewer",
+                "/pics/a.png",
+                "/pics/b.png"
+            ]]
+        );
+        assert_eq!(
+            launch_commands_logic(
+                &mock,
+                Some("org.example.Viewer.desktop:slideshow"),
+                None,
+                &paths
+            )
+            .unwrap(),
+            vec![
+                vec![
+                    "viewer",
+                    "--slideshow",
+                    "/apps/viewer.desktop",
+                    "/pics/a.png"
+                ],
+                vec![
+                    "viewer",
+                    "--slideshow",
+                    "/apps/viewer.desktop",
+                    "/pics/b.png"
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_launch_commands_fall_back_to_exec_lines() {
+        let mock = viewer();
+
+        // History recorded before apps had ids is keyed by exec line
+        assert_eq!(
+            launch_commands_logic(
+                &mock,
+                Some("viewer --class=%c %i %F"),
+                Some("viewer --class=%c %i %F"),
+                &[]
+            )
+            .unwrap(),
+            vec![vec!["viewer", "--class=Image Viewer", "--icon", "viewer"]]
+        );
+        assert!(launch_commands_logic(&mock, Some("gone.desktop"), None, &[]).is_err());
+    }
+
+    #[test]
+    fn test_plain_commands_are_shell_words() {
+        // Commands from script filters and skills aren't Exec lines
+        let mut mock = MockAppRepository::new();
+        mock.expect_list_apps().never();
+        let plain = |cmd| launch_commands_logic(&mock, None, Some(cmd), &[]).unwrap();
+
+        assert_eq!(plain("date +%s"), vec![vec!["date", "+%s"]]);
+        assert_eq!(
+            plain("notify-send 'a b' \"c d\""),
+            vec![vec!["notify-send", "a b", "c d"]]
+        );
+        assert!(launch_commands_logic(&mock, None, Some("date"), &["/a".to_string()]).is_err());
+        assert!(launch_commands_logic(&mock, None, Some("  "), &[]).is_err());
+
+        // Unlisted apps fall back the same way
+        let mock = viewer();
+        assert_eq!(
+            launch_commands_logic(&mock, Some("gone.desktop"), Some("printf '%s'"), &[]).unwrap(),
+            vec![vec!["printf", "%s"]]
+        );
+    }
 }
@@ -19,7 +19,14 @@ pub async fn get_config(state: State<'_, AppState>) -> Result<AppConfig, String>
 
 #[tauri::command]
 pub async fn save_config(state: State<'_, AppState>, config: AppConfig) -> Result<(), String> {
-    save_config_logic(&*state.config_service, &config)
+    save_config_logic(&*state.config_service, &config)?;
+    // Re-crawl only if the file search roots or rules changed
+    state.file_index.start(&config.file_search);
+    // Only plugins whose settings changed are restarted
+    let plugins = state.plugin_service.clone();
+    let declared = config.plugins.clone();
+    tokio::task::spawn_blocking(move || plugins.configure(&declared));
+    Ok(())
 }
 
 #[cfg(test)]
@@ -1,3 +1,4 @@
+use crate::commands::search::refresh_history;
 use crate::domain::action::Action;
 use crate::state::AppState;
 use tauri::State;
@@ -11,11 +12,19 @@ pub async fn get_recent_actions(
 }
 
 #[tauri::command]
-pub async fn record_action(state: State<'_, AppState>, action: Action) -> Result<(), String> {
-    state.history_repository.record(action).await
+pub async fn record_action(
+    state: State<'_, AppState>,
+    action: Action,
+    query: Option<String>,
+) -> Result<(), String> {
+    state.history_repository.record(action, query).await?;
+    refresh_history(&state).await;
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn clear_history(state: State<'_, AppState>) -> Result<(), String> {
-    state.history_repository.clear().await
+    state.history_repository.clear().await?;
+    refresh_history(&state).await;
+    Ok(())
 }
@@ -2,7 +2,9 @@ pub mod ai;
 pub mod apps;
 pub mod config;
 pub mod history;
+pub mod plugins;
 pub mod scripts;
+pub mod search;
 pub mod system;
 pub mod translation;
 pub mod windows;
@@ -0,0 +1,49 @@
+use crate::ports::plugin_port::PluginService;
+use crate::state::AppState;
+use tauri::State;
+
+// Handler logic separated from Tauri state injection for easier testing
+pub fn run_plugin_action_logic(
+    plugins: &dyn PluginService,
+    plugin: &str,
+    result_id: &str,
+    action: Option<&str>,
+) -> Result<(), String> {
+    plugins.run_action(plugin, result_id, action.unwrap_or("open"))
+}
+
+/// Routes the action picked on a plugin result back to that plugin.
+#[tauri::command]
+pub async fn run_plugin_action(
+    state: State<'_, AppState>,
+    plugin: String,
+    result_id: String,
+    action: Option<String>,
+) -> Result<(), String> {
+    let plugins = state.plugin_service.clone();
+    // Writing to a wedged plugin can block, keep it off the async runtime
+    tokio::task::spawn_blocking(move || {
+        run_plugin_action_logic(&*plugins, &plugin, &result_id, action.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ports::plugin_port::MockPluginService;
+
+    #[test]
+    fn test_run_plugin_action_defaults_to_open() {
+        let mut mock = MockPluginService::new();
+        mock.expect_run_action()
+            .withf(|plugin, result_id, action| {
+                plugin == "tickets" && result_id == "INC-1" && action == "open"
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        assert!(run_plugin_action_logic(&mock, "tickets", "INC-1", None).is_ok());
+    }
+}
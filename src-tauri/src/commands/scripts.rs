@@ -1,80 +1,529 @@
-use crate::domain::config::ScriptConfig;
+use crate::adapters::script_discovery::all_scripts;
+use crate::commands::system::{copy_to_clipboard, notify_desktop, open_entity};
+use crate::domain::config::{ScriptConfig, ScriptMode, ScriptOutputMode, ScriptSchedule};
+use crate::domain::files::expand_tilde;
+use crate::domain::frecency::now_ms;
+use crate::domain::schedule::Schedule;
+use crate::domain::script_run::{
+    LastLine, PtySize, RunCapture, RunRecord, RunStatus, RunningScript, ScriptEvent, ScriptRequest,
+};
+use crate::ports::config_port::ConfigService;
+use crate::ports::run_log_port::RunLog;
+use crate::ports::script_runner_port::ScriptRunner;
+use crate::ports::window_port::WindowService;
 use crate::state::AppState;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tauri::{Emitter, State};
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::Command;
+use tokio::sync::mpsc::unbounded_channel;
 
+/// Scripts registered in the config plus those found in `scripts_dir` through their
+/// Raycast metadata headers.
 #[tauri::command]
 pub async fn list_scripts(state: State<'_, AppState>) -> Result<Vec<ScriptConfig>, String> {
     let config = state.config_service.load_config();
-    Ok(config.scripts)
+    Ok(all_scripts(&config))
 }
 
+/// The configured or discovered script at `path`, or defaults for a bare path.
+fn find_script(service: &dyn ConfigService, path: &str) -> ScriptConfig {
+    let path = path.trim();
+    all_scripts(&service.load_config())
+        .into_iter()
+        .find(|script| script.path.trim() == path)
+        .unwrap_or_default()
+}
+
+/// Builds the request for the script at `path`, picking up the settings (timeout,
+/// PTY mode, environment, working directory, interpreter) of `script`, the matching
+/// configured or discovered script as `find_script` gives it. `size` is the terminal
+/// view's size for PTY runs. Scripts run in their own directory unless they name
+/// another one. Silent scripts and those with an output action run without the
+/// terminal view, so they are unattended.
+pub fn script_request_logic(
+    script: &ScriptConfig,
+    path: &str,
+    args: Option<String>,
+    arguments: Vec<String>,
+    size: Option<PtySize>,
+) -> Result<ScriptRequest, String> {
+    let mut all_args = match args {
+        Some(args) => shell_words::split(&args).map_err(|e| e.to_string())?,
+        None => Vec::new(),
+    };
+    // Values typed into the script's argument prompts
+    all_args.extend(arguments);
+
+    let path = path.trim();
+    let cwd = match &script.cwd {
+        Some(cwd) => Some(expand_tilde(cwd)),
+        None => Path::new(path)
+            .parent()
+            .filter(|dir| dir.is_absolute())
+            .map(Path::to_path_buf),
+    };
+
+    Ok(ScriptRequest {
+        script_id: (!script.id.is_empty()).then(|| script.id.clone()),
+        path: path.to_string(),
+        args: all_args,
+        interpreter: script.interpreter.clone(),
+        cwd: cwd.map(|cwd| cwd.display().to_string()),
+        env: script.env.clone(),
+        timeout: script.timeout_ms.map(Duration::from_millis),
+        pty: script.pty.then(|| size.unwrap_or_default()),
+        unattended: script.mode == Some(ScriptMode::Silent)
+            || script.output_mode != ScriptOutputMode::Terminal,
+    })
+}
+
+/// Starts `request` and saves it to the run log once it is done. `on_event` sees
+/// every event of the run as it happens.
+pub fn start_logged_run(
+    runner: &dyn ScriptRunner,
+    run_log: Arc<dyn RunLog>,
+    request: ScriptRequest,
+    on_event: impl Fn(&ScriptEvent) + Send + 'static,
+) -> Result<String, String> {
+    let mut capture = RunCapture::new(&request, now_ms());
+    let (tx, mut rx) = unbounded_channel();
+    let run_id = runner.start(request, tx)?;
+
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            on_event(&event);
+            if let Some(record) = capture.observe(&event, now_ms()) {
+                let run_log = run_log.clone();
+                let _ = tokio::task::spawn_blocking(move || run_log.append(&record)).await;
+            }
+        }
+    });
+
+    Ok(run_id)
+}
+
+/// Forwards run events to the terminal view as `script-start`, `script-output` and
+/// `script-done`, each carrying the run id.
+fn emit_to(window: tauri::Window) -> impl Fn(&ScriptEvent) + Send + 'static {
+    move |event| {
+        let name = match event {
+            ScriptEvent::Start { .. } => "script-start",
+            ScriptEvent::Output { .. } => "script-output",
+            ScriptEvent::Done {
+                run_id,
+                code,
+                status,
+            } => {
+                let _ = window.emit(
+                    "script-output",
+                    ScriptEvent::Output {
+                        run_id: run_id.clone(),
+                        text: status.message(*code),
+                    },
+                );
+                "script-done"
+            }
+        };
+        let _ = window.emit(name, event);
+    }
+}
+
+/// What to do with a successful run's last line under `mode`, or `None` when the
+/// terminal view shows the output. Failed runs of such scripts are reported instead,
+/// since nothing else would show them.
+pub fn output_action_logic(
+    mode: ScriptOutputMode,
+    name: &str,
+    status: RunStatus,
+    code: Option<i32>,
+    line: Option<String>,
+) -> Option<OutputAction> {
+    if mode == ScriptOutputMode::Terminal {
+        return None;
+    }
+    if status != RunStatus::Success {
+        let message = status.message(code);
+        return Some(OutputAction::Notify {
+            summary: format!("{} failed", name),
+            body: Some(message.trim_start_matches("> ").trim_end().to_string()),
+        });
+    }
+    let line = line?;
+    Some(match mode {
+        ScriptOutputMode::Copy => OutputAction::Copy(line),
+        ScriptOutputMode::Open => OutputAction::Open(line),
+        ScriptOutputMode::Paste => OutputAction::Paste(line),
+        _ => OutputAction::Notify {
+            summary: name.to_string(),
+            body: Some(line),
+        },
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputAction {
+    Copy(String),
+    Open(String),
+    Paste(String),
+    Notify {
+        summary: String,
+        body: Option<String>,
+    },
+}
+
+impl OutputAction {
+    pub async fn perform(self, windows: Arc<dyn WindowService>) -> Result<(), String> {
+        match self {
+            OutputAction::Copy(text) => copy_to_clipboard(text).await,
+            OutputAction::Paste(text) => {
+                copy_to_clipboard(text).await?;
+                tokio::task::spawn_blocking(move || windows.paste())
+                    .await
+                    .map_err(|e| e.to_string())?
+            }
+            OutputAction::Open(target) => {
+                open_entity(expand_tilde(&target).display().to_string()).await
+            }
+            OutputAction::Notify { summary, body } => notify_desktop(summary, body).await,
+        }
+    }
+}
+
+/// Wraps `on_event` so that the script's output action runs once it finishes.
+/// `windows` pastes for the paste mode.
+pub fn with_output_action(
+    script: &ScriptConfig,
+    pty: bool,
+    windows: Arc<dyn WindowService>,
+    on_event: impl Fn(&ScriptEvent) + Send + 'static,
+) -> impl Fn(&ScriptEvent) + Send + 'static {
+    let mode = script.output_mode;
+    let name = script.title.clone().unwrap_or_else(|| script.alias.clone());
+    let last_line = Mutex::new(LastLine::default());
+    move |event| {
+        on_event(event);
+        if mode == ScriptOutputMode::Terminal {
+            return;
+        }
+        let mut last_line = last_line.lock().unwrap();
+        match event {
+            ScriptEvent::Output { text, .. } => last_line.observe(text, pty),
+            ScriptEvent::Done { code, status, .. } => {
+                if let Some(action) =
+                    output_action_logic(mode, &name, *status, *code, last_line.get())
+                {
+                    let (name, windows) = (name.clone(), windows.clone());
+                    tauri::async_runtime::spawn(async move {
+                        // These runs have no terminal view to show the error in
+                        if let Err(e) = action.perform(windows).await {
+                            let _ = notify_desktop(format!("{} failed", name), Some(e)).await;
+                        }
+                    });
+                }
+            }
+            ScriptEvent::Start { .. } => {}
+        }
+    }
+}
+
+/// Starts the script and returns its run id.
 #[tauri::command]
 pub async fn execute_script(
     window: tauri::Window,
+    state: State<'_, AppState>,
     path: String,
     args: Option<String>,
+    arguments: Option<Vec<String>>,
+    size: Option<PtySize>,
+) -> Result<String, String> {
+    let script = find_script(state.config_service.as_ref(), &path);
+    let request = script_request_logic(&script, &path, args, arguments.unwrap_or_default(), size)?;
+    let on_event = with_output_action(
+        &script,
+        request.pty.is_some(),
+        state.window_service.clone(),
+        emit_to(window),
+    );
+    start_logged_run(
+        state.script_runner.as_ref(),
+        state.run_log.clone(),
+        request,
+        on_event,
+    )
+}
+
+/// Past runs, newest first, without their output.
+#[tauri::command]
+pub async fn list_script_runs(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+) -> Result<Vec<RunRecord>, String> {
+    let run_log = state.run_log.clone();
+    tokio::task::spawn_blocking(move || run_log.list(limit.unwrap_or(50)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn get_script_run(
+    state: State<'_, AppState>,
+    run_id: String,
+) -> Result<Option<RunRecord>, String> {
+    let run_log = state.run_log.clone();
+    tokio::task::spawn_blocking(move || run_log.get(&run_id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Runs a logged invocation again with the same arguments, under the script's
+/// current settings. Returns the new run id.
+#[tauri::command]
+pub async fn rerun_script(
+    window: tauri::Window,
+    state: State<'_, AppState>,
+    run_id: String,
+    size: Option<PtySize>,
+) -> Result<String, String> {
+    let run_log = state.run_log.clone();
+    let record = tokio::task::spawn_blocking(move || run_log.get(&run_id))
+        .await
+        .map_err(|e| e.to_string())??
+        .ok_or("That run is no longer in the run log")?;
+    let script = find_script(state.config_service.as_ref(), &record.path);
+    let request = script_request_logic(&script, &record.path, None, record.args, size)?;
+    let on_event = with_output_action(
+        &script,
+        request.pty.is_some(),
+        state.window_service.clone(),
+        emit_to(window),
+    );
+    start_logged_run(
+        state.script_runner.as_ref(),
+        state.run_log.clone(),
+        request,
+        on_event,
+    )
+}
+
+/// Checks a cron expression or interval before it is saved.
+#[tauri::command]
+pub async fn validate_schedule(schedule: ScriptSchedule) -> Result<(), String> {
+    Schedule::from_config(&schedule).map(|_| ())
+}
+
+/// Stops a run along with every process it started.
+#[tauri::command]
+pub async fn kill_script(state: State<'_, AppState>, run_id: String) -> Result<(), String> {
+    state.script_runner.kill(&run_id)
+}
+
+/// Sends what the user typed in the terminal view to the script.
+#[tauri::command]
+pub async fn write_script_stdin(
+    state: State<'_, AppState>,
+    run_id: String,
+    text: String,
 ) -> Result<(), String> {
-    let path = path.trim();
-    let mut cmd;
+    state.script_runner.write_stdin(&run_id, &text)
+}
 
-    if path.ends_with(".sh") {
-        cmd = Command::new("sh");
-        cmd.arg(path);
-    } else {
-        cmd = Command::new(path);
-    }
+/// Lets a script reading its input until EOF finish.
+#[tauri::command]
+pub async fn close_script_stdin(state: State<'_, AppState>, run_id: String) -> Result<(), String> {
+    state.script_runner.close_stdin(&run_id)
+}
 
-    if let Some(args_str) = args {
-        let parts = shell_words::split(&args_str).map_err(|e| e.to_string())?;
-        cmd.args(parts);
-    }
+#[tauri::command]
+pub async fn resize_script(
+    state: State<'_, AppState>,
+    run_id: String,
+    size: PtySize,
+) -> Result<(), String> {
+    state.script_runner.resize(&run_id, size)
+}
 
-    cmd.stdout(std::process::Stdio::piped());
-    cmd.stderr(std::process::Stdio::piped());
+#[tauri::command]
+pub async fn list_running_scripts(
+    state: State<'_, AppState>,
+) -> Result<Vec<RunningScript>, String> {
+    Ok(state.script_runner.running())
+}
 
-    let mut child = cmd
-        .spawn()
-        .map_err(|e| format!("Failed to spawn script: {}", e))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::config::{AppConfig, ScriptConfig};
+    use crate::ports::config_port::MockConfigService;
+    use crate::ports::run_log_port::MockRunLog;
+    use crate::ports::script_runner_port::MockScriptRunner;
+    use std::collections::HashMap;
 
-    let stdout = child.stdout.take().ok_or("Failed to open stdout")?;
-    let stderr = child.stderr.take().ok_or("Failed to open stderr")?;
+    #[test]
+    fn test_script_request_combines_args_and_config_timeout() {
+        let mut mock = MockConfigService::new();
+        mock.expect_load_config().times(1).returning(|| AppConfig {
+            scripts: vec![ScriptConfig {
+                id: "1".to_string(),
+                alias: "deploy".to_string(),
+                path: "/scripts/deploy.sh".to_string(),
+                timeout_ms: Some(5000),
+                pty: true,
+                cwd: Some("/srv/app".to_string()),
+                env: HashMap::from([("STAGE".to_string(), "eu".to_string())]),
+                interpreter: Some("bash -x".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
 
-    let mut stdout_reader = BufReader::new(stdout).lines();
-    let mut stderr_reader = BufReader::new(stderr).lines();
+        let script = find_script(&mock, " /scripts/deploy.sh");
+        let request = script_request_logic(
+            &script,
+            " /scripts/deploy.sh",
+            Some("--env 'staging eu'".to_string()),
+            vec!["v2".to_string()],
+            None,
+        )
+        .unwrap();
 
-    window.emit("script-start", ()).map_err(|e| e.to_string())?;
+        assert_eq!(request.script_id.as_deref(), Some("1"));
+        assert_eq!(request.path, "/scripts/deploy.sh");
+        assert_eq!(request.args, vec!["--env", "staging eu", "v2"]);
+        assert_eq!(request.timeout, Some(Duration::from_millis(5000)));
+        assert_eq!(request.pty, Some(PtySize::default()));
+        assert_eq!(request.cwd.as_deref(), Some("/srv/app"));
+        assert_eq!(request.env["STAGE"], "eu");
+        assert_eq!(request.interpreter.as_deref(), Some("bash -x"));
+        assert!(!request.unattended);
 
-    // Spawn tasks to handle stdout and stderr concurrently
-    let window_clone1 = window.clone();
-    tokio::spawn(async move {
-        while let Ok(Some(line)) = stdout_reader.next_line().await {
-            let _ = window_clone1.emit("script-output", format!("> {}\n", line));
-        }
-    });
+        // Runs without the terminal view have nobody to answer prompts
+        let silent = ScriptConfig {
+            mode: Some(ScriptMode::Silent),
+            ..script
+        };
+        let request =
+            script_request_logic(&silent, "/scripts/deploy.sh", None, Vec::new(), None).unwrap();
+        assert!(request.unattended);
+    }
 
-    let window_clone2 = window.clone();
-    tokio::spawn(async move {
-        while let Ok(Some(line)) = stderr_reader.next_line().await {
-            let _ = window_clone2.emit("script-output", format!("ERR> {}\n", line));
-        }
-    });
+    #[test]
+    fn test_unknown_script_runs_without_timeout_or_pty() {
+        let mut mock = MockConfigService::new();
+        mock.expect_load_config().returning(AppConfig::default);
 
-    // Wait for the child process to finish
-    let output = child.wait().await.map_err(|e| e.to_string())?;
+        let size = PtySize { rows: 10, cols: 40 };
+        let script = find_script(&mock, "/tmp/other");
+        let request =
+            script_request_logic(&script, "/tmp/other", None, Vec::new(), Some(size)).unwrap();
+        assert_eq!(request.script_id, None);
+        assert_eq!(request.timeout, None);
+        assert_eq!(request.pty, None);
+        // Runs next to the script
+        assert_eq!(request.cwd.as_deref(), Some("/tmp"));
+        assert!(request.args.is_empty());
+    }
 
-    let status_msg = if output.success() {
-        format!("> Success! (Exit code 0)\n")
-    } else {
-        format!("> Failed! (Exit code {})\n", output.code().unwrap_or(-1))
-    };
+    #[test]
+    fn test_output_action_for_mode_and_status() {
+        let action = |mode, status, line: Option<&str>| {
+            output_action_logic(mode, "UUID", status, Some(1), line.map(String::from))
+        };
+        let uuid = Some("0b9e…");
+
+        assert_eq!(
+            action(ScriptOutputMode::Terminal, RunStatus::Success, uuid),
+            None
+        );
+        assert_eq!(
+            action(ScriptOutputMode::Copy, RunStatus::Success, uuid),
+            Some(OutputAction::Copy("0b9e…".to_string()))
+        );
+        assert_eq!(
+            action(
+                ScriptOutputMode::Open,
+                RunStatus::Success,
+                Some("https://x.io")
+            ),
+            Some(OutputAction::Open("https://x.io".to_string()))
+        );
+        assert_eq!(
+            action(ScriptOutputMode::Notify, RunStatus::Success, uuid),
+            Some(OutputAction::Notify {
+                summary: "UUID".to_string(),
+                body: Some("0b9e…".to_string()),
+            })
+        );
+        assert_eq!(
+            action(ScriptOutputMode::Paste, RunStatus::Success, uuid),
+            Some(OutputAction::Paste("0b9e…".to_string()))
+        );
+        // Nothing printed, nothing to copy
+        assert_eq!(
+            action(ScriptOutputMode::Copy, RunStatus::Success, None),
+            None
+        );
+        assert_eq!(
+            action(ScriptOutputMode::Copy, RunStatus::Failed, uuid),
+            Some(OutputAction::Notify {
+                summary: "UUID failed".to_string(),
+                body: Some("Failed! (Exit code 1)".to_string()),
+            })
+        );
+    }
 
-    window
-        .emit("script-output", status_msg)
-        .map_err(|e| e.to_string())?;
+    #[tokio::test]
+    async fn test_logged_run_saves_record_when_done() {
+        let mut runner = MockScriptRunner::new();
+        runner.expect_start().returning(|_, events| {
+            let run_id = "run-7".to_string();
+            for event in [
+                ScriptEvent::Output {
+                    run_id: run_id.clone(),
+                    text: "> done\n".to_string(),
+                },
+                ScriptEvent::Done {
+                    run_id: run_id.clone(),
+                    code: Some(0),
+                    status: RunStatus::Success,
+                },
+            ] {
+                events.send(event).unwrap();
+            }
+            Ok(run_id)
+        });
+        let (saved_tx, saved_rx) = std::sync::mpsc::channel();
+        let mut run_log = MockRunLog::new();
+        run_log.expect_append().returning(move |record| {
+            saved_tx.send(record.clone()).unwrap();
+            Ok(())
+        });
+        let (seen_tx, seen_rx) = std::sync::mpsc::channel();
 
-    window.emit("script-done", ()).map_err(|e| e.to_string())?;
+        let run_id = start_logged_run(
+            &runner,
+            Arc::new(run_log),
+            ScriptRequest {
+                script_id: Some("1".to_string()),
+                path: "/scripts/deploy.sh".to_string(),
+                args: vec!["prod".to_string()],
+                ..Default::default()
+            },
+            move |event| seen_tx.send(event.clone()).unwrap(),
+        )
+        .unwrap();
 
-    Ok(())
+        let record = tokio::task::spawn_blocking(move || {
+            saved_rx.recv_timeout(std::time::Duration::from_secs(5))
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(run_id, "run-7");
+        assert_eq!(record.run_id, "run-7");
+        assert_eq!(record.script_id.as_deref(), Some("1"));
+        assert_eq!(record.args, vec!["prod"]);
+        assert_eq!(record.output, "> done\n");
+        assert_eq!(seen_rx.try_iter().count(), 2);
+    }
 }
@@ -0,0 +1,166 @@
+use crate::domain::action::Action;
+use crate::domain::frecency::{now_ms, Frecency};
+use crate::domain::search::{dedupe_results, rank_results, SearchResult};
+use crate::ports::search_provider_port::{SearchContext, SearchProvider};
+use crate::state::AppState;
+use std::sync::Arc;
+use tauri::State;
+
+/// Runs every provider concurrently on the blocking pool, each within its own time
+/// budget. Providers that fail or run late are left out; the rest are deduplicated
+/// and ranked together.
+pub async fn aggregate_search(
+    providers: &[Arc<dyn SearchProvider>],
+    context: SearchContext,
+    limit: usize,
+) -> Vec<SearchResult> {
+    let context = Arc::new(context);
+    let tasks: Vec<_> = providers
+        .iter()
+        .map(|provider| {
+            let budget = provider.budget();
+            let provider = provider.clone();
+            let context = context.clone();
+            let task = tokio::task::spawn_blocking(move || provider.search(&context));
+            tokio::time::timeout(budget, task)
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    for task in tasks {
+        // A late provider keeps running in the background, but nobody waits for it
+        if let Ok(Ok(Ok(found))) = task.await {
+            results.extend(found);
+        }
+    }
+
+    let mut results = dedupe_results(results);
+    rank_results(&mut results, limit);
+    results
+}
+
+/// Recorded history, most recent first. Read from the repository once, then from
+/// the snapshot that recording an action refreshes.
+pub async fn load_actions(state: &AppState) -> Vec<Action> {
+    if let Some(actions) = state.history_snapshot.get() {
+        return actions.to_vec();
+    }
+    refresh_history(state).await.to_vec()
+}
+
+/// Reloads the history snapshot from the repository.
+pub async fn refresh_history(state: &AppState) -> Arc<Vec<Action>> {
+    let actions = state
+        .history_repository
+        .get_recent(usize::MAX)
+        .await
+        .unwrap_or_default();
+    state.history_snapshot.set(actions)
+}
+
+/// History-aware frecency table for `query`, built from every recorded action.
+pub async fn load_frecency(state: &AppState, query: &str) -> Frecency {
+    Frecency::from_actions(&load_actions(state).await, query, now_ms())
+}
+
+/// One ranked list of apps, windows, scripts, files and recently used entries, with
+/// the user's history pushing frequently and recently used entries up.
+#[tauri::command]
+pub async fn search(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchResult>, String> {
+    let query = query.trim().to_string();
+    let recent = load_actions(&state).await;
+    let context = SearchContext {
+        frecency: Frecency::from_actions(&recent, &query, now_ms()),
+        query,
+        recent,
+    };
+    Ok(aggregate_search(&state.search_providers, context, limit.unwrap_or(20)).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::search::ResultKind;
+    use crate::ports::search_provider_port::MockSearchProvider;
+    use std::time::Duration;
+
+    fn result(kind: ResultKind, title: &str, score: i32) -> SearchResult {
+        SearchResult {
+            score,
+            ..SearchResult::new(kind, format!("app:{}", title), title, "", title)
+        }
+    }
+
+    fn provider(
+        delay_ms: u64,
+        found: Result<Vec<SearchResult>, String>,
+    ) -> Arc<dyn SearchProvider> {
+        let mut mock = MockSearchProvider::new();
+        mock.expect_budget()
+            .return_const(Duration::from_millis(100));
+        mock.expect_search().returning(move |_| {
+            std::thread::sleep(Duration::from_millis(delay_ms));
+            found.clone()
+        });
+        Arc::new(mock)
+    }
+
+    fn context(query: &str) -> SearchContext {
+        SearchContext {
+            query: query.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_merges_dedupes_and_ranks() {
+        let providers = vec![
+            provider(
+                0,
+                Ok(vec![
+                    result(ResultKind::App, "Firefox", 40),
+                    result(ResultKind::App, "Files", 20),
+                ]),
+            ),
+            provider(
+                0,
+                Ok(vec![
+                    result(ResultKind::App, "Firefox", 70),
+                    result(ResultKind::File, "Firefox", 50),
+                ]),
+            ),
+        ];
+
+        let results = aggregate_search(&providers, context("f"), 10).await;
+        let summary: Vec<(ResultKind, i32)> = results.iter().map(|r| (r.kind, r.score)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (ResultKind::App, 70),
+                (ResultKind::File, 50),
+                (ResultKind::App, 20)
+            ]
+        );
+
+        assert_eq!(aggregate_search(&providers, context("f"), 1).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_skips_slow_and_failing_providers() {
+        let providers = vec![
+            provider(0, Ok(vec![result(ResultKind::App, "Firefox", 40)])),
+            provider(400, Ok(vec![result(ResultKind::File, "late.txt", 90)])),
+            provider(0, Err("no compositor".to_string())),
+        ];
+
+        let started = std::time::Instant::now();
+        let results = aggregate_search(&providers, context("f"), 10).await;
+        assert!(started.elapsed() < Duration::from_millis(400));
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Firefox");
+    }
+}
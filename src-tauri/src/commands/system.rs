@@ -1,4 +1,17 @@
-use walkdir::WalkDir;
+use crate::commands::search::load_frecency;
+use crate::domain::content_search::{looks_binary, ContentMatch};
+use crate::domain::editor::editor_command;
+use crate::domain::file_query::{FileQuery, QueryError};
+use crate::domain::files::{FileMatch, IndexStatus};
+use crate::domain::frecency::Frecency;
+use crate::domain::search_session::SearchBatch;
+use crate::ports::file_index_port::FileIndex;
+use crate::ports::mime_port::MimeResolver;
+use crate::state::AppState;
+use std::os::unix::process::CommandExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use tauri::{Emitter, State};
 
 #[tauri::command]
 pub fn greet(name: &str) -> String {
@@ -13,6 +26,24 @@ pub async fn open_entity(path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Shows a desktop notification through `notify-send`.
+#[tauri::command]
+pub async fn notify_desktop(summary: String, body: Option<String>) -> Result<(), String> {
+    let mut command = tokio::process::Command::new("notify-send");
+    command.args(["--app-name", "Stratos", "--", &summary]);
+    if let Some(body) = body {
+        command.arg(body);
+    }
+    let status = command
+        .status()
+        .await
+        .map_err(|e| format!("Failed to run notify-send: {}", e))?;
+    if !status.success() {
+        return Err(format!("notify-send exited with {}", status));
+    }
+    Ok(())
+}
+
 #[derive(serde::Serialize)]
 pub struct FileMetadata {
     size: u64,
@@ -22,93 +53,191 @@ pub struct FileMetadata {
     mime_type: Option<String>,
 }
 
-fn calculate_file_score(name: &str, ext: Option<&str>, query: &str) -> i32 {
-    let mut score = 0;
-    let lower_name = name.to_lowercase();
-    let lower_query = query.to_lowercase();
-
-    // Name match bonuses
-    if lower_name == lower_query {
-        score += 100;
-    } else if lower_name.starts_with(&lower_query) {
-        score += 50;
-    }
-
-    // Extension prioritization
-    if let Some(e) = ext {
-        let e_lower = e.to_lowercase();
-        match e_lower.as_str() {
-            // Images (Highest priority)
-            "png" | "jpg" | "jpeg" | "webp" | "gif" | "svg" | "bmp" | "tiff" => score += 40,
-
-            // Videos (Second priority)
-            "mp4" | "mkv" | "avi" | "mov" | "webm" | "flv" | "wmv" => score += 30,
-
-            // Common documents/code (Third priority)
-            "rs" | "js" | "ts" | "vue" | "py" | "html" | "css" | "json" | "md" | "txt" | "pdf"
-            | "csv" | "docx" | "xlsx" => score += 20,
-
-            _ => {}
-        }
-    }
-
-    score
+/// Extra candidates fetched so history can promote files just outside the limit.
+const FRECENCY_HEADROOM: usize = 2;
+
+// Handler logic separated from Tauri state injection for easier testing
+pub fn search_files_logic(
+    index: &dyn FileIndex,
+    frecency: &Frecency,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<FileMatch>, QueryError> {
+    let query = FileQuery::parse(query)?;
+    let mut matches = index.search(&query, limit * FRECENCY_HEADROOM);
+    frecency.rerank_files(&mut matches, limit);
+    Ok(matches)
 }
 
+/// Searches the file index. Supports `ext:`, `in:`, `type:`, `modified:`, `size:`
+/// filters and quoted phrases; parse failures come back as a structured `QueryError`.
 #[tauri::command]
 pub async fn search_files(
+    state: State<'_, AppState>,
     query: String,
-    path: String,
-    include_hidden: bool,
-) -> Result<Vec<String>, String> {
-    let lower_query = query.to_lowercase();
-
-    let walker = WalkDir::new(&path).max_depth(10).into_iter();
-
-    // Collect matches
-    let mut matches = Vec::new();
+    limit: Option<usize>,
+) -> Result<Vec<FileMatch>, QueryError> {
+    let frecency = load_frecency(&state, &query).await;
+    search_files_logic(&*state.file_index, &frecency, &query, limit.unwrap_or(50))
+}
 
-    // Use filter_entry to skip hidden directories efficiently if needed
-    let it = walker.filter_entry(move |e| {
-        if include_hidden {
-            return true;
+/// Runs one streaming search, reporting each batch and then a final `done` batch with
+/// the complete ranking. Nothing more is reported once the search is cancelled.
+pub fn stream_search_logic(
+    index: &dyn FileIndex,
+    frecency: &Frecency,
+    search_id: u64,
+    query: &FileQuery,
+    limit: usize,
+    cancel: &AtomicBool,
+    emit: &mut dyn FnMut(SearchBatch),
+) {
+    let (tx, rx) = mpsc::channel();
+    std::thread::scope(|scope| {
+        let search = scope
+            .spawn(move || index.search_batched(query, limit * FRECENCY_HEADROOM, cancel, &tx));
+
+        // Ends once the search returns and drops its sender
+        for mut results in rx {
+            if !cancel.load(Ordering::SeqCst) {
+                let batch_len = results.len();
+                frecency.rerank_files(&mut results, batch_len);
+                emit(SearchBatch {
+                    search_id,
+                    results,
+                    done: false,
+                });
+            }
+        }
+        if let Ok(Some(mut results)) = search.join() {
+            if !cancel.load(Ordering::SeqCst) {
+                frecency.rerank_files(&mut results, limit);
+                emit(SearchBatch {
+                    search_id,
+                    results,
+                    done: true,
+                });
+            }
         }
-        !is_hidden(e)
     });
+}
 
-    for entry in it.filter_map(|e| e.ok()) {
-        let path = entry.path();
-        let path_str = path.to_string_lossy();
+/// Starts a streaming file search and returns its id right away. Results arrive as
+/// `search-results` events; starting another search cancels this one.
+#[tauri::command]
+pub async fn start_file_search(
+    window: tauri::Window,
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<u64, QueryError> {
+    let frecency = load_frecency(&state, &query).await;
+    let query = FileQuery::parse(&query)?;
+    let (search_id, cancel) = state.search_sessions.begin();
+    let index = state.file_index.clone();
+    let sessions = state.search_sessions.clone();
 
-        if path_str.to_lowercase().contains(&lower_query) {
-            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-            let ext = path.extension().and_then(|e| e.to_str());
+    std::thread::spawn(move || {
+        stream_search_logic(
+            &*index,
+            &frecency,
+            search_id,
+            &query,
+            limit.unwrap_or(50),
+            &cancel,
+            &mut |batch| {
+                let _ = window.emit("search-results", batch);
+            },
+        );
+        sessions.finish(search_id);
+    });
 
-            let score = calculate_file_score(name, ext, &query);
+    Ok(search_id)
+}
 
-            matches.push((path_str.to_string(), score));
-            if matches.len() > 500 {
-                // Increased limit slightly to allow better sorting
-                break;
-            }
-        }
-    }
+/// Cancels the given streaming search, or whichever one is running if no id is passed.
+#[tauri::command]
+pub async fn cancel_file_search(
+    state: State<'_, AppState>,
+    search_id: Option<u64>,
+) -> Result<bool, String> {
+    Ok(state.search_sessions.cancel(search_id))
+}
 
-    // Sort by score descending
-    matches.sort_by(|a, b| b.1.cmp(&a.1));
+pub fn search_content_logic(
+    index: &dyn FileIndex,
+    query: &str,
+    limit: usize,
+    cancel: &AtomicBool,
+) -> Result<Vec<ContentMatch>, QueryError> {
+    let query = FileQuery::parse(query)?;
+    Ok(index
+        .search_content(&query, limit, cancel)
+        .unwrap_or_default())
+}
 
-    // Take top 50
-    let results: Vec<String> = matches.into_iter().take(50).map(|(p, _)| p).collect();
+/// Greps inside indexed files. The same filters as `search_files` apply; everything
+/// else is the text to find. Shares the search session, so a newer search cancels it.
+#[tauri::command]
+pub async fn search_content(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<ContentMatch>, QueryError> {
+    let (search_id, cancel) = state.search_sessions.begin();
+    let index = state.file_index.clone();
+    let sessions = state.search_sessions.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let result = search_content_logic(&*index, &query, limit.unwrap_or(50), &cancel);
+        sessions.finish(search_id);
+        result
+    })
+    .await
+    .unwrap_or_else(|_| Ok(Vec::new()))
+}
 
-    Ok(results)
+/// Opens `path` at `line` in the configured editor, falling back to `$VISUAL` / `$EDITOR`
+/// and finally to the default application.
+#[tauri::command]
+pub async fn open_in_editor(
+    state: State<'_, AppState>,
+    path: String,
+    line: Option<usize>,
+    column: Option<usize>,
+) -> Result<(), String> {
+    let config = state.config_service.load_config();
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok();
+    let terminal = std::env::var("TERMINAL").unwrap_or_else(|_| "x-terminal-emulator".to_string());
+
+    match editor_command(
+        config.editor.as_deref(),
+        editor.as_deref(),
+        &terminal,
+        &path,
+        line.unwrap_or(1),
+        column.unwrap_or(1),
+    ) {
+        Some(argv) => {
+            std::process::Command::new(&argv[0])
+                .args(&argv[1..])
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .process_group(0)
+                .spawn()
+                .map_err(|e| format!("Failed to launch editor: {}", e))?;
+            Ok(())
+        }
+        None => open_entity(path).await,
+    }
 }
 
-fn is_hidden(entry: &walkdir::DirEntry) -> bool {
-    entry
-        .file_name()
-        .to_str()
-        .map(|s| s.starts_with('.') && s != "." && s != "..")
-        .unwrap_or(false)
+#[tauri::command]
+pub async fn get_file_index_status(state: State<'_, AppState>) -> Result<IndexStatus, String> {
+    Ok(state.file_index.status())
 }
 
 #[tauri::command]
@@ -122,16 +251,19 @@ pub async fn read_file_preview(path: String, max_bytes: Option<usize>) -> Result
     buffer.truncate(n);
 
     // Check for null bytes to detect binary
-    if buffer.contains(&0) {
+    if looks_binary(&buffer) {
         return Err("Binary file detected".to_string());
     }
 
     String::from_utf8(buffer).map_err(|e| format!("Not valid UTF-8: {}", e))
 }
 
-#[tauri::command]
-pub async fn get_file_metadata(path: String) -> Result<FileMetadata, String> {
-    let metadata = std::fs::metadata(&path).map_err(|e| e.to_string())?;
+/// Metadata for the file preview; the MIME type comes from the shared-mime-info database.
+pub fn get_file_metadata_logic(
+    mime_resolver: &dyn MimeResolver,
+    path: &str,
+) -> Result<FileMetadata, String> {
+    let metadata = std::fs::metadata(path).map_err(|e| e.to_string())?;
 
     let created = metadata
         .created()
@@ -144,10 +276,18 @@ pub async fn get_file_metadata(path: String) -> Result<FileMetadata, String> {
         created,
         is_dir: metadata.is_dir(),
         readonly: metadata.permissions().readonly(),
-        mime_type: None, // Could use `mime_guess` crate if added, but frontend handles mostly
+        mime_type: mime_resolver.mime_type(path),
     })
 }
 
+#[tauri::command]
+pub async fn get_file_metadata(
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<FileMetadata, String> {
+    get_file_metadata_logic(state.mime_resolver.as_ref(), &path)
+}
+
 #[tauri::command]
 pub async fn get_selection_context() -> Result<String, String> {
     use arboard::Clipboard;
@@ -267,6 +407,8 @@ pub async fn generate_video_thumbnail(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ports::file_index_port::MockFileIndex;
+    use crate::ports::mime_port::MockMimeResolver;
     use std::io::Write;
 
     #[tokio::test]
@@ -294,117 +436,140 @@ mod tests {
         assert_eq!(result.err().unwrap(), "Binary file detected");
     }
 
-    #[tokio::test]
-    async fn test_get_file_metadata() {
+    #[test]
+    fn test_get_file_metadata() {
         let temp_dir = std::env::temp_dir();
         let file_path = temp_dir.join("test_meta.txt");
         let mut file = std::fs::File::create(&file_path).unwrap();
         writeln!(file, "Meta test").unwrap();
 
-        let metadata = get_file_metadata(file_path.to_string_lossy().to_string())
-            .await
-            .unwrap();
+        let mut mime_resolver = MockMimeResolver::new();
+        mime_resolver
+            .expect_mime_type()
+            .returning(|_| Some("text/plain".to_string()));
+
+        let metadata =
+            get_file_metadata_logic(&mime_resolver, &file_path.to_string_lossy()).unwrap();
         assert!(metadata.size > 0);
         assert!(!metadata.is_dir);
+        assert_eq!(metadata.mime_type.as_deref(), Some("text/plain"));
     }
     #[test]
-    fn test_calculate_file_score() {
-        // Exact match
-        assert_eq!(calculate_file_score("test", None, "test"), 100);
-
-        // Starts with
-        assert_eq!(calculate_file_score("test_file", None, "test"), 50);
-
-        // Extensions
-        // Image (40)
-        assert_eq!(calculate_file_score("pic", Some("png"), "pic"), 140); // 100 + 40
-        assert_eq!(calculate_file_score("foo", Some("jpg"), "bar"), 40);
-
-        // Video (30)
-        assert_eq!(calculate_file_score("vid", Some("mp4"), "bar"), 30);
-
-        // Common (20)
-        assert_eq!(calculate_file_score("doc", Some("txt"), "bar"), 20);
+    fn test_search_files_logic() {
+        let mut mock = MockFileIndex::new();
+        mock.expect_search()
+            .withf(|query, limit| {
+                query.terms == vec!["notes"] && query.extensions == vec!["md"] && *limit == 100
+            })
+            .times(1)
+            .returning(|_, _| {
+                vec![FileMatch {
+                    path: "/home/user/notes.md".to_string(),
+                    is_dir: false,
+                    score: 120,
+                    positions: vec![11, 12, 13, 14, 15],
+                    root: Some("Home".to_string()),
+                }]
+            });
+
+        let results = search_files_logic(&mock, &Frecency::default(), "notes ext:md", 50).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "/home/user/notes.md");
+    }
 
-        // Prioritization check
-        let img = calculate_file_score("a", Some("png"), "query");
-        let vid = calculate_file_score("a", Some("mp4"), "query");
-        let doc = calculate_file_score("a", Some("txt"), "query");
-        let other = calculate_file_score("a", Some("bin"), "query");
+    #[test]
+    fn test_search_files_logic_reports_query_errors() {
+        let mut mock = MockFileIndex::new();
+        mock.expect_search().times(0);
+
+        let err =
+            search_files_logic(&mock, &Frecency::default(), "notes size:huge", 50).unwrap_err();
+        assert_eq!(err.token, "size:huge");
+        assert_eq!(err.position, 6);
+    }
 
-        assert!(img > vid);
-        assert!(vid > doc);
-        assert!(doc > other);
+    fn file_match(path: &str) -> FileMatch {
+        FileMatch {
+            path: path.to_string(),
+            is_dir: false,
+            score: 10,
+            positions: vec![],
+            root: None,
+        }
     }
 
-    #[tokio::test]
-    async fn test_search_files_hidden() {
-        let temp_dir = std::env::temp_dir().join("test_search_hidden");
-        let _ = std::fs::remove_dir_all(&temp_dir); // Cleanup previous
-        std::fs::create_dir_all(&temp_dir).unwrap();
-
-        let visible = temp_dir.join("visible.txt");
-        let hidden = temp_dir.join(".hidden.txt");
-        let hidden_dir = temp_dir.join(".hidden_dir");
-        let file_in_hidden = hidden_dir.join("file.txt");
-
-        std::fs::File::create(&visible).unwrap();
-        std::fs::File::create(&hidden).unwrap();
-        std::fs::create_dir(&hidden_dir).unwrap();
-        std::fs::File::create(&file_in_hidden).unwrap();
-
-        // Test 1: Exclude hidden (default)
-        // Should find 'visible.txt' but not '.hidden.txt' or 'file.txt' inside hidden dir
-        // searching for "txt" or "." or ""
-        let results = search_files(
-            "txt".to_string(),
-            temp_dir.to_string_lossy().to_string(),
-            false,
-        )
-        .await
-        .unwrap();
-
-        // Results are absolute paths
-        let found_visible = results.iter().any(|r| r.contains("visible.txt"));
-        let found_hidden = results.iter().any(|r| r.contains(".hidden.txt"));
-        let found_in_hidden = results
-            .iter()
-            .any(|r| r.contains("file.txt") && r.contains(".hidden_dir"));
-
-        assert!(found_visible, "Should find visible file");
-        assert!(
-            !found_hidden,
-            "Should NOT find hidden file when include_hidden=false"
-        );
-        assert!(
-            !found_in_hidden,
-            "Should NOT find file in hidden dir when include_hidden=false"
+    #[test]
+    fn test_stream_search_logic_emits_batches_then_done() {
+        let mut mock = MockFileIndex::new();
+        mock.expect_search_batched()
+            .times(1)
+            .returning(|_, _, _, batches| {
+                batches.send(vec![file_match("/a/notes.md")]).unwrap();
+                batches.send(vec![file_match("/b/notes.txt")]).unwrap();
+                Some(vec![file_match("/a/notes.md"), file_match("/b/notes.txt")])
+            });
+
+        let mut events = Vec::new();
+        let query = FileQuery::parse("notes").unwrap();
+        stream_search_logic(
+            &mock,
+            &Frecency::default(),
+            7,
+            &query,
+            50,
+            &AtomicBool::new(false),
+            &mut |batch| events.push(batch),
         );
 
-        // Test 2: Include hidden
-        let results_all = search_files(
-            "txt".to_string(),
-            temp_dir.to_string_lossy().to_string(),
-            true,
-        )
-        .await
-        .unwrap();
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|e| e.search_id == 7));
+        assert!(!events[0].done && !events[1].done);
+        assert!(events[2].done);
+        assert_eq!(events[2].results.len(), 2);
+    }
 
-        let found_hidden_2 = results_all.iter().any(|r| r.contains(".hidden.txt"));
-        let found_in_hidden_2 = results_all
-            .iter()
-            .any(|r| r.contains("file.txt") && r.contains(".hidden_dir"));
+    #[test]
+    fn test_search_content_logic() {
+        let mut mock = MockFileIndex::new();
+        mock.expect_search_content()
+            .withf(|query, limit, _| {
+                query.text == "fn main" && query.extensions == vec!["rs"] && *limit == 20
+            })
+            .times(1)
+            .returning(|_, _, _| {
+                Some(vec![ContentMatch {
+                    path: "/src/main.rs".to_string(),
+                    line: 1,
+                    column: 1,
+                    snippet: "fn main() {".to_string(),
+                    positions: vec![0, 1, 2, 3, 4, 5, 6],
+                }])
+            });
+
+        let found =
+            search_content_logic(&mock, "fn main ext:rs", 20, &AtomicBool::new(false)).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].line, 1);
+    }
 
-        assert!(
-            found_hidden_2,
-            "Should find hidden file when include_hidden=true"
-        );
-        assert!(
-            found_in_hidden_2,
-            "Should find file in hidden dir when include_hidden=true"
+    #[test]
+    fn test_stream_search_logic_stays_silent_when_cancelled() {
+        let mut mock = MockFileIndex::new();
+        mock.expect_search_batched()
+            .times(1)
+            .returning(|_, _, _, _| None);
+
+        let mut events = Vec::new();
+        let query = FileQuery::parse("notes").unwrap();
+        stream_search_logic(
+            &mock,
+            &Frecency::default(),
+            1,
+            &query,
+            50,
+            &AtomicBool::new(true),
+            &mut |batch| events.push(batch),
         );
-
-        // Cleanup
-        let _ = std::fs::remove_dir_all(&temp_dir);
+        assert!(events.is_empty());
     }
 }
@@ -1,5 +1,10 @@
 use serde::{Deserialize, Serialize};
 
+/// Selections made for a query lose half their weight every two weeks.
+pub const ASSOCIATION_HALF_LIFE_MS: u64 = 14 * 24 * 60 * 60 * 1000;
+/// Queries remembered per action; the weakest ones are dropped first.
+const MAX_ASSOCIATIONS: usize = 20;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Action {
     pub id: String,
@@ -9,4 +14,146 @@ pub struct Action {
     pub icon: Option<String>,
     pub last_accessed: u64,
     pub frequency: u64,
+    /// What was typed when this action was picked, e.g. `te` for Telegram.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub associations: Vec<QueryAssociation>,
+}
+
+/// A query this action was chosen for. `weight` is as of `last_used` and decays
+/// from there, so old habits fade out.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueryAssociation {
+    pub query: String,
+    pub weight: f64,
+    pub last_used: u64,
+}
+
+/// Lowercased, trimmed, single-spaced form queries are stored and compared in.
+pub fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// `weight` after `age_ms` of exponential decay.
+pub fn decayed(weight: f64, age_ms: u64) -> f64 {
+    weight * 0.5f64.powf(age_ms as f64 / ASSOCIATION_HALF_LIFE_MS as f64)
+}
+
+impl QueryAssociation {
+    pub fn weight_at(&self, now_ms: u64) -> f64 {
+        decayed(self.weight, now_ms.saturating_sub(self.last_used))
+    }
+}
+
+impl Action {
+    /// Remembers that this action was picked after typing `query`.
+    pub fn learn(&mut self, query: &str, now_ms: u64) {
+        let query = normalize_query(query);
+        if query.is_empty() {
+            return;
+        }
+        match self.associations.iter_mut().find(|a| a.query == query) {
+            Some(existing) => {
+                existing.weight = existing.weight_at(now_ms) + 1.0;
+                existing.last_used = now_ms;
+            }
+            None => self.associations.push(QueryAssociation {
+                query,
+                weight: 1.0,
+                last_used: now_ms,
+            }),
+        }
+        if self.associations.len() > MAX_ASSOCIATIONS {
+            self.associations
+                .sort_by(|a, b| b.weight_at(now_ms).total_cmp(&a.weight_at(now_ms)));
+            self.associations.truncate(MAX_ASSOCIATIONS);
+        }
+    }
+
+    /// How strongly `query` is associated with this action. Exact matches count
+    /// fully; queries that extend or shorten a remembered one (`tel` vs `te`)
+    /// count half, so the habit carries over while typing.
+    pub fn affinity(&self, query: &str, now_ms: u64) -> f64 {
+        let query = normalize_query(query);
+        if query.is_empty() {
+            return 0.0;
+        }
+        self.associations
+            .iter()
+            .map(|a| {
+                let factor = if a.query == query {
+                    1.0
+                } else if a.query.starts_with(&query) || query.starts_with(&a.query) {
+                    0.5
+                } else {
+                    0.0
+                };
+                factor * a.weight_at(now_ms)
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action() -> Action {
+        Action {
+            id: "app:telegram".to_string(),
+            kind: "app".to_string(),
+            content: "telegram".to_string(),
+            name: "Telegram".to_string(),
+            icon: None,
+            last_accessed: 0,
+            frequency: 1,
+            associations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_learn_accumulates_per_query() {
+        let mut telegram = action();
+        telegram.learn("te", 0);
+        telegram.learn(" TE ", 0);
+        telegram.learn("chat", 0);
+        telegram.learn("  ", 0);
+
+        assert_eq!(telegram.associations.len(), 2);
+        assert_eq!(telegram.affinity("te", 0), 2.0);
+        assert_eq!(telegram.affinity("tel", 0), 1.0);
+        assert_eq!(telegram.affinity("signal", 0), 0.0);
+    }
+
+    #[test]
+    fn test_associations_decay() {
+        let mut telegram = action();
+        telegram.learn("te", 0);
+        assert_eq!(telegram.affinity("te", ASSOCIATION_HALF_LIFE_MS), 0.5);
+
+        // Learning again starts from the decayed weight
+        telegram.learn("te", ASSOCIATION_HALF_LIFE_MS);
+        assert_eq!(telegram.associations[0].weight, 1.5);
+    }
+
+    #[test]
+    fn test_weakest_associations_are_dropped() {
+        let mut telegram = action();
+        telegram.learn("old", 0);
+        for i in 0..MAX_ASSOCIATIONS {
+            telegram.learn(&format!("q{}", i), ASSOCIATION_HALF_LIFE_MS);
+        }
+        assert_eq!(telegram.associations.len(), MAX_ASSOCIATIONS);
+        assert!(!telegram.associations.iter().any(|a| a.query == "old"));
+    }
+
+    #[test]
+    fn test_old_history_without_associations_loads() {
+        let json = r#"{"id":"app:a","kind":"app","content":"a","name":"A","icon":null,"last_accessed":1,"frequency":3}"#;
+        let parsed: Action = serde_json::from_str(json).unwrap();
+        assert!(parsed.associations.is_empty());
+    }
 }
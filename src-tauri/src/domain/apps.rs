@@ -1,10 +1,81 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+/// An application (or `Type=Link` entry) from a desktop file, with its strings
+/// translated for the user's locale.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub struct AppEntry {
+    /// Desktop-file id, e.g. `org.gnome.Nautilus.desktop`; history, shortcuts and
+    /// launches refer to apps by it.
+    #[serde(default)]
+    pub id: String,
     pub name: String,
+    /// The `Exec` line as written, field codes included; see `domain::desktop_exec`.
     pub exec: String,
     pub icon: Option<String>,
+    /// Path of the desktop file, which `%k` expands to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desktop_file: Option<String>,
+    /// What kind of app it is, e.g. "Web Browser".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generic_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    /// Types the app says it can open.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mime_types: Vec<String>,
+    /// Program that must be installed for the entry to be shown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub try_exec: Option<String>,
+    #[serde(default)]
+    pub hidden: bool,
+    /// `NoDisplay`: kept out of menus and search, but still a handler for its MIME
+    /// types and launchable by id.
+    #[serde(default)]
+    pub no_display: bool,
+    /// `XDG_CURRENT_DESKTOP` names the entry is limited to or excluded from.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub only_show_in: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub not_show_in: Vec<String>,
+    /// Target of a `Type=Link` entry, which `exec` opens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// The `[Desktop Action ...]` groups named in `Actions`, e.g. "New Private Window".
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<AppAction>,
+}
+
+/// An extra way to start an app, launched through its own `exec`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct AppAction {
+    pub id: String,
+    pub name: String,
+    pub exec: String,
+    /// The action's own icon, or the app's.
+    pub icon: Option<String>,
+}
+
+impl AppEntry {
+    /// Whether the entry should be listed under the given desktop environments
+    /// (`XDG_CURRENT_DESKTOP`, split on `:`).
+    pub fn shown_in(&self, desktops: &[String]) -> bool {
+        let current = |names: &[String]| {
+            names
+                .iter()
+                .any(|name| desktops.iter().any(|d| d.eq_ignore_ascii_case(name)))
+        };
+        if self.hidden {
+            return false;
+        }
+        if !self.only_show_in.is_empty() {
+            return current(&self.only_show_in);
+        }
+        !current(&self.not_show_in)
+    }
 }
 
 #[cfg(test)]
@@ -17,10 +88,32 @@ mod tests {
             name: "Firefox".to_string(),
             exec: "firefox".to_string(),
             icon: Some("firefox.png".to_string()),
+            ..Default::default()
         };
 
         assert_eq!(app.name, "Firefox");
         assert_eq!(app.exec, "firefox");
         assert_eq!(app.icon, Some("firefox.png".to_string()));
     }
+
+    #[test]
+    fn test_shown_in_desktop() {
+        let desktops = vec!["ubuntu".to_string(), "GNOME".to_string()];
+        let app = |only: &[&str], not: &[&str]| AppEntry {
+            only_show_in: only.iter().map(|d| d.to_string()).collect(),
+            not_show_in: not.iter().map(|d| d.to_string()).collect(),
+            ..Default::default()
+        };
+
+        assert!(app(&[], &[]).shown_in(&desktops));
+        assert!(app(&["KDE", "GNOME"], &[]).shown_in(&desktops));
+        assert!(!app(&["KDE"], &[]).shown_in(&desktops));
+        assert!(!app(&[], &["GNOME"]).shown_in(&desktops));
+        assert!(app(&[], &["KDE"]).shown_in(&[]));
+        assert!(!AppEntry {
+            hidden: true,
+            ..Default::default()
+        }
+        .shown_in(&desktops));
+    }
 }
@@ -19,6 +19,126 @@ pub struct ScriptConfig {
     pub alias: String,
     pub path: String,
     pub args: Option<String>,
+    /// Script filter: typing `<alias> <query>` runs the script with the query and
+    /// lists the JSON items it prints instead of running it fire-and-forget.
+    #[serde(default)]
+    pub filter: bool,
+    /// Display name; the alias is shown when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Emoji, image path or URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Values prompted for before running, passed as extra positional arguments.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arguments: Vec<ScriptArgument>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<ScriptMode>,
+    #[serde(default)]
+    pub output_mode: ScriptOutputMode,
+    /// Runs still going after this many milliseconds are killed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Run in a pseudo-terminal instead of with piped output.
+    #[serde(default)]
+    pub pty: bool,
+    /// Working directory; the script's own directory when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// Command the script is passed to, e.g. `python3 -u`; the shebang decides when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interpreter: Option<String>,
+    /// Runs the script in the background while the app is running.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<ScriptSchedule>,
+}
+
+/// `{"cron": "0 8 * * 1-5"}` or `{"interval_minutes": 60}`; see `domain::schedule`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptSchedule {
+    Cron(String),
+    IntervalMinutes(u64),
+}
+
+/// How a script's output is presented, named after Raycast's modes.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ScriptMode {
+    /// The terminal view with everything the script prints.
+    FullOutput,
+    /// Only the last line of output.
+    Compact,
+    /// Nothing; the omnibar hides right away.
+    Silent,
+    /// The last line of output, shown in place of the subtitle.
+    Inline,
+}
+
+/// What is done with the last line a successful run prints. Anything but
+/// `Terminal` runs without the terminal view.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ScriptOutputMode {
+    #[default]
+    Terminal,
+    /// Copied to the clipboard.
+    Copy,
+    /// Opened as a URL or path.
+    Open,
+    /// Shown as a desktop notification.
+    Notify,
+    /// Copied, then pasted into the window that had focus before the omnibar.
+    Paste,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ArgumentKind {
+    #[default]
+    Text,
+    Password,
+    Dropdown,
+}
+
+/// A typed prompt shown before the script runs (Raycast's `argumentN`).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptArgument {
+    #[serde(rename = "type", default)]
+    pub kind: ArgumentKind,
+    #[serde(default)]
+    pub placeholder: String,
+    #[serde(default)]
+    pub optional: bool,
+    /// URL-encode the value before passing it on.
+    #[serde(default)]
+    pub percent_encoded: bool,
+    /// Choices for dropdown arguments.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub data: Vec<DropdownOption>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DropdownOption {
+    pub title: String,
+    pub value: String,
+}
+
+/// An external executable that contributes omnibar results over the JSON-lines
+/// plugin protocol (see `domain::plugin`).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PluginConfig {
+    pub id: String,
+    pub command: String,
+    pub args: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// How long a query waits for this plugin's results.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
@@ -47,19 +167,138 @@ pub struct AppConfig {
     #[serde(default)]
     pub scripts: Vec<ScriptConfig>,
 
+    /// Folder scanned for scripts with Raycast `@raycast.*` metadata headers;
+    /// those show up next to `scripts` without being registered.
+    #[serde(default)]
+    pub scripts_dir: Option<String>,
+
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,
+
     #[serde(default)]
     pub window_scale: Option<f32>,
 
+    /// Command used to open files at a line, with `{file}`, `{line}` and `{column}`
+    /// placeholders. Falls back to `$VISUAL` / `$EDITOR` when unset.
+    #[serde(default)]
+    pub editor: Option<String>,
+
     pub theme: Option<ThemeConfig>,
 
     #[serde(default)]
     pub file_search: FileSearchConfig,
 }
 
-#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct FileSearchConfig {
     #[serde(default)]
     pub include_hidden: bool,
+
+    /// Directories crawled by the file index. Empty means the home directory.
+    #[serde(default)]
+    pub roots: Vec<SearchRoot>,
+
+    /// Gitignore-style patterns (relative to each root) that are never indexed.
+    #[serde(default = "default_exclude")]
+    pub exclude: Vec<String>,
+
+    /// Skip whatever `.gitignore` / `.ignore` files exclude, like ripgrep does.
+    #[serde(default = "default_true")]
+    pub respect_ignore_files: bool,
+}
+
+impl Default for FileSearchConfig {
+    fn default() -> Self {
+        Self {
+            include_hidden: false,
+            roots: Vec::new(),
+            exclude: default_exclude(),
+            respect_ignore_files: true,
+        }
+    }
+}
+
+/// A directory crawled by the file index. Unset options fall back to the
+/// global `FileSearchConfig` values.
+///
+/// Older configs listed roots as plain path strings; those still load.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(from = "SearchRootEntry")]
+pub struct SearchRoot {
+    pub name: String,
+    pub path: String,
+    pub max_depth: Option<usize>,
+    pub include_hidden: Option<bool>,
+    pub respect_ignore_files: Option<bool>,
+}
+
+impl SearchRoot {
+    pub fn new(path: &str) -> Self {
+        Self {
+            name: default_root_name(path),
+            path: path.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SearchRootEntry {
+    Path(String),
+    Full {
+        #[serde(default)]
+        name: String,
+        path: String,
+        #[serde(default)]
+        max_depth: Option<usize>,
+        #[serde(default)]
+        include_hidden: Option<bool>,
+        #[serde(default)]
+        respect_ignore_files: Option<bool>,
+    },
+}
+
+impl From<SearchRootEntry> for SearchRoot {
+    fn from(entry: SearchRootEntry) -> Self {
+        match entry {
+            SearchRootEntry::Path(path) => SearchRoot::new(&path),
+            SearchRootEntry::Full {
+                name,
+                path,
+                max_depth,
+                include_hidden,
+                respect_ignore_files,
+            } => SearchRoot {
+                name: if name.is_empty() {
+                    default_root_name(&path)
+                } else {
+                    name
+                },
+                path,
+                max_depth,
+                include_hidden,
+                respect_ignore_files,
+            },
+        }
+    }
+}
+
+fn default_root_name(path: &str) -> String {
+    path.trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(path)
+        .to_string()
+}
+
+fn default_exclude() -> Vec<String> {
+    vec!["node_modules/".to_string(), "__pycache__/".to_string()]
+}
+
+fn default_true() -> bool {
+    true
 }
 
 impl AppConfig {
@@ -152,4 +391,20 @@ mod tests {
         // But should still fill in missing ones (ai_tools)
         assert!(!config.ai_tools.is_empty());
     }
+
+    #[test]
+    fn test_search_roots_accept_plain_paths_and_objects() {
+        let config: FileSearchConfig = serde_json::from_str(
+            r#"{"roots": ["~/Documents", {"name": "Code", "path": "~/code", "max_depth": 5}, {"path": "/srv/data/"}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.roots[0], SearchRoot::new("~/Documents"));
+        assert_eq!(config.roots[0].name, "Documents");
+        assert_eq!(config.roots[1].name, "Code");
+        assert_eq!(config.roots[1].max_depth, Some(5));
+        assert_eq!(config.roots[1].include_hidden, None);
+        assert_eq!(config.roots[2].name, "data");
+        assert!(config.respect_ignore_files);
+    }
 }
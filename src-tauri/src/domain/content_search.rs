@@ -0,0 +1,162 @@
+//! Full-text search inside files ("grep mode").
+//!
+//! The needle is matched literally with smart case: all-lowercase needles
+//! match case-insensitively, anything with an uppercase letter is exact.
+
+use serde::Serialize;
+
+/// Longest snippet (in chars) sent to the omnibar for a single hit.
+const SNIPPET_CHARS: usize = 120;
+/// Chars of context kept before the match when a long line has to be cut.
+const SNIPPET_LEAD: usize = 30;
+
+/// A line of a file that contains the searched text.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ContentMatch {
+    pub path: String,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based char column of the first match on the line.
+    pub column: usize,
+    /// The (possibly shortened) line, trimmed of surrounding whitespace.
+    pub snippet: String,
+    /// Char indices into `snippet` covered by the match, for highlighting.
+    pub positions: Vec<usize>,
+}
+
+/// Same heuristic as the file preview: a NUL byte means binary.
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    bytes.contains(&0)
+}
+
+#[derive(Debug, Clone)]
+pub struct TextMatcher {
+    needle: Vec<char>,
+    case_sensitive: bool,
+}
+
+impl TextMatcher {
+    /// Returns `None` for an empty needle.
+    pub fn new(needle: &str) -> Option<Self> {
+        if needle.trim().is_empty() {
+            return None;
+        }
+        let case_sensitive = needle.chars().any(char::is_uppercase);
+        Some(Self {
+            needle: needle.chars().map(|c| fold(c, case_sensitive)).collect(),
+            case_sensitive,
+        })
+    }
+
+    /// Char offset of the first occurrence of the needle in `line`.
+    pub fn find(&self, line: &str) -> Option<usize> {
+        let chars: Vec<char> = line.chars().map(|c| fold(c, self.case_sensitive)).collect();
+        chars
+            .windows(self.needle.len())
+            .position(|window| window == self.needle.as_slice())
+    }
+
+    /// Every matching line of `content`, at most `limit` of them.
+    pub fn search(&self, path: &str, content: &str, limit: usize) -> Vec<ContentMatch> {
+        let mut found = Vec::new();
+        for (idx, line) in content.lines().enumerate() {
+            if found.len() >= limit {
+                break;
+            }
+            if let Some(start) = self.find(line) {
+                let (snippet, positions) = snippet(line, start, self.needle.len());
+                found.push(ContentMatch {
+                    path: path.to_string(),
+                    line: idx + 1,
+                    column: start + 1,
+                    snippet,
+                    positions,
+                });
+            }
+        }
+        found
+    }
+}
+
+fn fold(c: char, case_sensitive: bool) -> char {
+    if case_sensitive {
+        c
+    } else {
+        c.to_lowercase().next().unwrap_or(c)
+    }
+}
+
+/// Trims `line` to at most `SNIPPET_CHARS` around the match at char `start`.
+fn snippet(line: &str, start: usize, len: usize) -> (String, Vec<usize>) {
+    let chars: Vec<char> = line.chars().collect();
+    let leading = chars.iter().take_while(|c| c.is_whitespace()).count();
+    let trailing = chars.iter().rev().take_while(|c| c.is_whitespace()).count();
+    let (mut from, mut to) = (leading.min(start), chars.len() - trailing.min(chars.len()));
+    to = to.max(start + len);
+
+    if to - from > SNIPPET_CHARS {
+        from = from.max(start.saturating_sub(SNIPPET_LEAD));
+        to = to.min(from + SNIPPET_CHARS).max(start + len);
+    }
+
+    let text: String = chars[from..to].iter().collect();
+    let positions = (start - from..start - from + len).collect();
+    (text, positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smart_case() {
+        let lower = TextMatcher::new("todo").unwrap();
+        assert_eq!(lower.find("// TODO: fix"), Some(3));
+        assert_eq!(lower.find("nothing here"), None);
+
+        let exact = TextMatcher::new("TODO").unwrap();
+        assert_eq!(exact.find("// todo: later"), None);
+        assert_eq!(exact.find("// TODO: now"), Some(3));
+
+        assert!(TextMatcher::new("  ").is_none());
+    }
+
+    #[test]
+    fn test_search_reports_lines_and_snippets() {
+        let matcher = TextMatcher::new("needle").unwrap();
+        let content = "first line\n    let needle = 1;\nno\nneedle again\n";
+        let found = matcher.search("/tmp/a.rs", content, 10);
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].line, 2);
+        assert_eq!(found[0].column, 9);
+        assert_eq!(found[0].snippet, "let needle = 1;");
+        assert_eq!(found[0].positions, vec![4, 5, 6, 7, 8, 9]);
+        assert_eq!(found[1].line, 4);
+
+        assert_eq!(matcher.search("/tmp/a.rs", content, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_long_lines_are_cut_around_the_match() {
+        let line = format!("{}ñeedle{}", "x".repeat(500), "y".repeat(500));
+        let matcher = TextMatcher::new("ñeedle").unwrap();
+        let found = matcher.search("/tmp/big.txt", &line, 1);
+
+        let hit = &found[0];
+        assert_eq!(hit.column, 501);
+        assert!(hit.snippet.chars().count() <= SNIPPET_CHARS);
+        let highlighted: String = hit
+            .positions
+            .iter()
+            .map(|&i| hit.snippet.chars().nth(i).unwrap())
+            .collect();
+        assert_eq!(highlighted, "ñeedle");
+    }
+
+    #[test]
+    fn test_looks_binary() {
+        assert!(looks_binary(b"ELF\0\x01"));
+        assert!(!looks_binary(b"plain text"));
+    }
+}
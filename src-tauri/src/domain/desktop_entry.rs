@@ -0,0 +1,287 @@
+//! Desktop Entry files as the freedesktop spec defines them:
+//!
+//! ```text
+//! [Desktop Entry]
+//! Type=Application
+//! Name=Firefox
+//! Name[de]=Firefox Webbrowser
+//! Keywords=Internet;WWW;Browser;
+//! Exec=firefox %u
+//! ```
+//!
+//! Values may use the `\s`, `\n`, `\t`, `\r` and `\\` escapes, lists are separated
+//! by `;` (`\;` being a literal one) and `Key[locale]` variants are picked by the
+//! user's `LC_MESSAGES`.
+
+use std::collections::HashMap;
+
+/// One `[Group Name]` and its keys, localized variants included as `Key[de_DE]`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DesktopGroup {
+    entries: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DesktopFile {
+    groups: Vec<(String, DesktopGroup)>,
+}
+
+/// `lang_COUNTRY.ENCODING@MODIFIER`, as in `LC_MESSAGES`; the encoding is ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Locale {
+    lang: String,
+    country: Option<String>,
+    modifier: Option<String>,
+}
+
+impl Locale {
+    /// `None` for the `C` and `POSIX` locales, which have no translations.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (rest, modifier) = match value.split_once('@') {
+            Some((rest, modifier)) => (rest, Some(modifier.to_string())),
+            None => (value, None),
+        };
+        let rest = rest.split('.').next().unwrap_or_default();
+        let (lang, country) = match rest.split_once('_') {
+            Some((lang, country)) => (lang, Some(country.to_string())),
+            None => (rest, None),
+        };
+        if lang.is_empty() || lang == "C" || lang == "POSIX" {
+            return None;
+        }
+        Some(Self {
+            lang: lang.to_string(),
+            country,
+            modifier,
+        })
+    }
+
+    /// The messages locale: `LC_ALL`, then `LC_MESSAGES`, then `LANG`.
+    pub fn from_env() -> Option<Self> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::parse(&value))
+    }
+
+    /// Keys to look for, most specific first, in the order the spec gives.
+    fn candidates(&self) -> Vec<String> {
+        let mut keys = Vec::new();
+        if let (Some(country), Some(modifier)) = (&self.country, &self.modifier) {
+            keys.push(format!("{}_{}@{}", self.lang, country, modifier));
+        }
+        if let Some(country) = &self.country {
+            keys.push(format!("{}_{}", self.lang, country));
+        }
+        if let Some(modifier) = &self.modifier {
+            keys.push(format!("{}@{}", self.lang, modifier));
+        }
+        keys.push(self.lang.clone());
+        keys
+    }
+}
+
+/// Resolves the general escape sequences; `\;` is kept for list splitting.
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => out.push(' '),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Splits a `;`-separated list, honoring `\;`; the trailing separator is optional.
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut item = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(';') => item.push(';'),
+                // Left for `unescape`
+                Some(next) => {
+                    item.push('\\');
+                    item.push(next);
+                }
+                None => item.push('\\'),
+            },
+            ';' => items.push(std::mem::take(&mut item)),
+            c => item.push(c),
+        }
+    }
+    items.push(item);
+    items
+        .into_iter()
+        .map(|item| unescape(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+impl DesktopFile {
+    /// Lenient about what it doesn't understand: malformed lines are skipped and
+    /// the first occurrence of a duplicated key wins.
+    pub fn parse(content: &str) -> Self {
+        let mut groups: Vec<(String, DesktopGroup)> = Vec::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                groups.push((name.to_string(), DesktopGroup::default()));
+                continue;
+            }
+            let (Some((_, group)), Some((key, value))) = (groups.last_mut(), line.split_once('='))
+            else {
+                continue;
+            };
+            group
+                .entries
+                .entry(key.trim().to_string())
+                .or_insert_with(|| value.trim().to_string());
+        }
+        Self { groups }
+    }
+
+    pub fn group(&self, name: &str) -> Option<&DesktopGroup> {
+        self.groups
+            .iter()
+            .find(|(group, _)| group == name)
+            .map(|(_, group)| group)
+    }
+
+    /// The `[Desktop Entry]` group every desktop file starts with.
+    pub fn entry(&self) -> Option<&DesktopGroup> {
+        self.group("Desktop Entry")
+    }
+}
+
+impl DesktopGroup {
+    /// Every key, localized variants included.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    fn raw(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(String::as_str)
+    }
+
+    /// A localized key's raw value, falling back to the untranslated one.
+    fn raw_localized(&self, key: &str, locale: Option<&Locale>) -> Option<&str> {
+        locale
+            .into_iter()
+            .flat_map(Locale::candidates)
+            .find_map(|suffix| self.raw(&format!("{}[{}]", key, suffix)))
+            .or_else(|| self.raw(key))
+    }
+
+    pub fn string(&self, key: &str) -> Option<String> {
+        self.raw(key)
+            .map(unescape)
+            .filter(|value| !value.is_empty())
+    }
+
+    pub fn localized(&self, key: &str, locale: Option<&Locale>) -> Option<String> {
+        self.raw_localized(key, locale)
+            .map(unescape)
+            .filter(|value| !value.is_empty())
+    }
+
+    pub fn boolean(&self, key: &str) -> bool {
+        self.raw(key) == Some("true")
+    }
+
+    pub fn list(&self, key: &str) -> Vec<String> {
+        self.raw(key).map(split_list).unwrap_or_default()
+    }
+
+    pub fn localized_list(&self, key: &str, locale: Option<&Locale>) -> Vec<String> {
+        self.raw_localized(key, locale)
+            .map(split_list)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIREFOX: &str = r"
+# Comment
+[Desktop Entry]
+Type=Application
+Name=Firefox
+Name[de]=Firefox Webbrowser
+Name[sr@latin]=Firefox (latinica)
+Name[pt_BR]=Navegador Firefox
+Name=Ignored duplicate
+GenericName = Web Browser
+Comment=Browse the\sWorld\nWide Web
+Keywords=Internet;WWW;Browser;Web\;Explorer;
+Keywords[de]=Internet;Browser;
+NoDisplay=false
+Hidden=true
+
+[Desktop Action new-window]
+Name=New Window
+";
+
+    #[test]
+    fn test_parses_groups_escapes_and_lists() {
+        let file = DesktopFile::parse(FIREFOX);
+        let entry = file.entry().unwrap();
+
+        assert_eq!(entry.string("Name").as_deref(), Some("Firefox"));
+        assert_eq!(entry.string("GenericName").as_deref(), Some("Web Browser"));
+        assert_eq!(
+            entry.string("Comment").as_deref(),
+            Some("Browse the World\nWide Web")
+        );
+        assert_eq!(
+            entry.list("Keywords"),
+            vec!["Internet", "WWW", "Browser", "Web;Explorer"]
+        );
+        assert!(entry.boolean("Hidden"));
+        assert!(!entry.boolean("NoDisplay"));
+        assert_eq!(entry.string("Exec"), None);
+
+        let action = file.group("Desktop Action new-window").unwrap();
+        assert_eq!(action.string("Name").as_deref(), Some("New Window"));
+        assert!(DesktopFile::parse("Name=Orphan").entry().is_none());
+    }
+
+    #[test]
+    fn test_locale_fallback() {
+        let entry = DesktopFile::parse(FIREFOX).entry().unwrap().clone();
+        let name = |locale: &str| entry.localized("Name", Locale::parse(locale).as_ref());
+
+        assert_eq!(name("de_DE.UTF-8").as_deref(), Some("Firefox Webbrowser"));
+        assert_eq!(name("pt_BR.UTF-8").as_deref(), Some("Navegador Firefox"));
+        assert_eq!(name("sr_RS@latin").as_deref(), Some("Firefox (latinica)"));
+        // No Portuguese from Portugal, no plain `pt`
+        assert_eq!(name("pt_PT").as_deref(), Some("Firefox"));
+        assert_eq!(name("C.UTF-8").as_deref(), Some("Firefox"));
+        assert_eq!(
+            entry.localized_list("Keywords", Locale::parse("de_AT").as_ref()),
+            vec!["Internet", "Browser"]
+        );
+    }
+}
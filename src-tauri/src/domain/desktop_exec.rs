@@ -0,0 +1,300 @@
+//! The `Exec` key of desktop entries: arguments split on spaces, double-quoted
+//! where needed (with `\"`, `` \` ``, `\$` and `\\` escapes inside quotes) and
+//! field codes standing in for what the app is launched with:
+//!
+//! - `%f` / `%u`: one file / URL; the app is started once per target
+//! - `%F` / `%U`: all files / URLs as separate arguments
+//! - `%i`: `--icon <Icon>`, `%c`: the translated name, `%k`: the desktop file
+//! - `%%`: a literal `%`; the deprecated `%d`, `%D`, `%n`, `%N`, `%v` and `%m` are dropped
+
+/// What field codes expand to.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExecContext<'a> {
+    /// Files and URLs to open.
+    pub targets: &'a [String],
+    pub icon: Option<&'a str>,
+    pub name: &'a str,
+    pub desktop_file: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Text(String),
+    Code(char),
+}
+
+/// An argument as written, with field codes still in place.
+type Arg = Vec<Piece>;
+
+/// Characters that must be quoted in an argument.
+const RESERVED: &[char] = &[
+    ' ', '\t', '\n', '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(', ')',
+    '`',
+];
+
+fn tokenize(exec: &str) -> Result<Vec<Arg>, String> {
+    let mut args = Vec::new();
+    let mut arg: Arg = Vec::new();
+    let mut text = String::new();
+    let mut in_arg = false;
+    let mut quoted = false;
+    let mut chars = exec.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            '\\' => {
+                text.push(chars.next().ok_or("Exec line ends in a backslash")?);
+                in_arg = true;
+            }
+            ' ' | '\t' | '\n' if !quoted => {
+                if in_arg {
+                    if !text.is_empty() {
+                        arg.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    args.push(std::mem::take(&mut arg));
+                    in_arg = false;
+                }
+            }
+            // Field codes are not expanded inside quotes, `%%` still is
+            '%' if quoted => {
+                chars.next_if_eq(&'%');
+                text.push('%');
+            }
+            '%' => {
+                let code = chars.next().ok_or("Exec line ends in a lone %")?;
+                if code == '%' {
+                    text.push('%');
+                } else {
+                    if !text.is_empty() {
+                        arg.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    arg.push(Piece::Code(code));
+                }
+                in_arg = true;
+            }
+            c => {
+                text.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if quoted {
+        return Err("Unterminated quote in Exec line".to_string());
+    }
+    if in_arg {
+        if !text.is_empty() {
+            arg.push(Piece::Text(text));
+        }
+        args.push(arg);
+    }
+    Ok(args)
+}
+
+/// `file://` URLs as paths; remote URLs can't be handed to `%f`.
+fn as_file(target: &str) -> Option<String> {
+    match target.strip_prefix("file://") {
+        Some(path) => Some(percent_decode(path)),
+        None if target.contains("://") => None,
+        None => Some(target.to_string()),
+    }
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Whether the Exec line takes files or URLs at all.
+pub fn accepts_targets(exec: &str) -> bool {
+    tokenize(exec)
+        .map(|args| {
+            args.iter()
+                .flatten()
+                .any(|piece| matches!(piece, Piece::Code('f' | 'F' | 'u' | 'U')))
+        })
+        .unwrap_or(false)
+}
+
+/// The command lines to run, one per launch: a single-target code (`%f`, `%u`)
+/// starts the app once per target, otherwise it is started once.
+pub fn expand_exec(exec: &str, context: &ExecContext) -> Result<Vec<Vec<String>>, String> {
+    let args = tokenize(exec)?;
+    if args.is_empty() {
+        return Err("Empty command".to_string());
+    }
+    let codes: Vec<char> = args
+        .iter()
+        .flatten()
+        .filter_map(|piece| match piece {
+            Piece::Code(code) => Some(*code),
+            Piece::Text(_) => None,
+        })
+        .collect();
+    let files: Vec<String> = context.targets.iter().filter_map(|t| as_file(t)).collect();
+
+    let single = if codes.contains(&'f') {
+        Some(files.clone())
+    } else if codes.contains(&'u') {
+        Some(context.targets.to_vec())
+    } else {
+        None
+    };
+    let launches: Vec<Option<String>> = match single {
+        Some(targets) if !targets.is_empty() => targets.into_iter().map(Some).collect(),
+        _ => vec![None],
+    };
+
+    Ok(launches
+        .into_iter()
+        .map(|target| {
+            let mut argv = Vec::new();
+            for arg in &args {
+                match arg.as_slice() {
+                    // List codes only expand when they stand alone
+                    [Piece::Code('F')] => argv.extend(files.iter().cloned()),
+                    [Piece::Code('U')] => argv.extend(context.targets.iter().cloned()),
+                    [Piece::Code('i')] => {
+                        if let Some(icon) = context.icon {
+                            argv.push("--icon".to_string());
+                            argv.push(icon.to_string());
+                        }
+                    }
+                    // A lone code with nothing to expand to drops the argument
+                    [Piece::Code('f' | 'u')] if target.is_none() => {}
+                    [Piece::Code('k')] if context.desktop_file.is_none() => {}
+                    [Piece::Code(code)] if !matches!(code, 'f' | 'u' | 'c' | 'k') => {}
+                    pieces => argv.push(
+                        pieces
+                            .iter()
+                            .map(|piece| match piece {
+                                Piece::Text(text) => text.as_str(),
+                                Piece::Code('f' | 'u') => target.as_deref().unwrap_or_default(),
+                                Piece::Code('c') => context.name,
+                                Piece::Code('k') => context.desktop_file.unwrap_or_default(),
+                                Piece::Code(_) => "",
+                            })
+                            .collect(),
+                    ),
+                }
+            }
+            argv
+        })
+        .collect())
+}
+
+/// `value` as a single Exec argument, quoted if it needs to be.
+pub fn quote_exec_arg(value: &str) -> String {
+    let value = value.replace('%', "%%");
+    if !value.is_empty() && !value.contains(RESERVED) {
+        return value;
+    }
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(exec: &str, targets: &[&str]) -> Vec<Vec<String>> {
+        let targets: Vec<String> = targets.iter().map(|t| t.to_string()).collect();
+        expand_exec(
+            exec,
+            &ExecContext {
+                targets: &targets,
+                icon: Some("firefox"),
+                name: "Firefox Web Browser",
+                desktop_file: Some("/usr/share/applications/firefox.desktop"),
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_field_codes() {
+        assert_eq!(
+            expand("vlc %U", &["/a b.mp4", "https://x.io/s"]),
+            vec![vec!["vlc", "/a b.mp4", "https://x.io/s"]]
+        );
+        // Remote URLs can't be opened as files
+        assert_eq!(
+            expand("gimp %F", &["file:///tmp/a%20b.png", "https://x.io/c.png"]),
+            vec![vec!["gimp", "/tmp/a b.png"]]
+        );
+        // One launch per target for the single-target codes
+        assert_eq!(
+            expand("app --open=%f", &["/a", "/b"]),
+            vec![vec!["app", "--open=/a"], vec!["app", "--open=/b"]]
+        );
+        assert_eq!(expand("firefox %u", &[]), vec![vec!["firefox"]]);
+        assert_eq!(
+            expand("app %i --name %c %k 100%%", &[]),
+            vec![vec![
+                "app",
+                "--icon",
+                "firefox",
+                "--name",
+                "Firefox Web Browser",
+                "/usr/share/applications/firefox.desktop",
+                "100%",
+            ]]
+        );
+        assert_eq!(expand("app %d %m", &[]), vec![vec!["app"]]);
+        assert!(accepts_targets("gimp %F"));
+        assert!(!accepts_targets("gnome-calculator"));
+    }
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(
+            expand(
+                r#""/opt/My App/run" "say \"hi\" \$HOME \\ 50%" %f"#,
+                &["/x"]
+            ),
+            vec![vec!["/opt/My App/run", r#"say "hi" $HOME \ 50%"#, "/x"]]
+        );
+        assert_eq!(expand("a  b\tc", &[]), vec![vec!["a", "b", "c"]]);
+        assert_eq!(expand(r#"app """#, &[]), vec![vec!["app", ""]]);
+        assert!(expand_exec("app \"open", &ExecContext::default()).is_err());
+        assert!(expand_exec("  ", &ExecContext::default()).is_err());
+
+        for value in [
+            "https://example.com/docs",
+            "/opt/My App/run",
+            "50% \"off\" $x",
+        ] {
+            let quoted = quote_exec_arg(value);
+            assert_eq!(expand(&quoted, &[]), vec![vec![value]]);
+        }
+        assert_eq!(quote_exec_arg("plain"), "plain");
+    }
+}
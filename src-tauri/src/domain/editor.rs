@@ -0,0 +1,137 @@
+//! Figuring out how to open a file at a given line in the user's editor.
+
+/// Editors that need a terminal to run in; they all understand `+LINE FILE`.
+const TERMINAL_EDITORS: &[&str] = &["vi", "vim", "nvim", "nano", "micro", "hx", "helix", "kak"];
+
+/// Builds the command line that opens `path` at `line`/`column` (both 1-based).
+///
+/// `template` is the user's configured editor command, where `{file}`, `{line}`
+/// and `{column}` are substituted; without one, `editor` (usually `$VISUAL` or
+/// `$EDITOR`) is invoked with its known "go to line" syntax. Terminal editors are
+/// wrapped in `terminal -e`. Returns `None` if nothing usable is configured.
+pub fn editor_command(
+    template: Option<&str>,
+    editor: Option<&str>,
+    terminal: &str,
+    path: &str,
+    line: usize,
+    column: usize,
+) -> Option<Vec<String>> {
+    let (line, column) = (line.to_string(), column.to_string());
+
+    if let Some(template) = template.filter(|t| !t.trim().is_empty()) {
+        let mut argv: Vec<String> = shell_words::split(template)
+            .ok()?
+            .into_iter()
+            .map(|arg| {
+                arg.replace("{file}", path)
+                    .replace("{line}", &line)
+                    .replace("{column}", &column)
+            })
+            .collect();
+        if !template.contains("{file}") {
+            argv.push(path.to_string());
+        }
+        return (!argv.is_empty()).then_some(argv);
+    }
+
+    let mut argv = shell_words::split(editor?).ok()?;
+    let program = argv.first()?.rsplit('/').next()?.to_string();
+    match program.as_str() {
+        "code" | "code-insiders" | "codium" | "cursor" => {
+            argv.extend([
+                "--goto".to_string(),
+                format!("{}:{}:{}", path, line, column),
+            ]);
+        }
+        "subl" | "zed" => argv.push(format!("{}:{}:{}", path, line, column)),
+        "kate" => argv.extend([
+            "-l".to_string(),
+            line,
+            "-c".to_string(),
+            column,
+            path.to_string(),
+        ]),
+        "gedit" | "gnome-text-editor" => {
+            argv.extend([format!("+{}:{}", line, column), path.to_string()])
+        }
+        "emacs" | "emacsclient" => {
+            argv.extend([format!("+{}:{}", line, column), path.to_string()]);
+            if argv.iter().any(|a| a == "-nw" || a == "-t" || a == "--tty") {
+                return Some(in_terminal(terminal, argv));
+            }
+        }
+        p if TERMINAL_EDITORS.contains(&p) => {
+            argv.extend([format!("+{}", line), path.to_string()]);
+            return Some(in_terminal(terminal, argv));
+        }
+        _ => argv.push(path.to_string()),
+    }
+    Some(argv)
+}
+
+fn in_terminal(terminal: &str, argv: Vec<String>) -> Vec<String> {
+    let mut wrapped = vec![terminal.to_string(), "-e".to_string()];
+    wrapped.extend(argv);
+    wrapped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmd(template: Option<&str>, editor: Option<&str>) -> Option<Vec<String>> {
+        editor_command(template, editor, "foot", "/src/main.rs", 12, 5)
+    }
+
+    #[test]
+    fn test_template_placeholders() {
+        assert_eq!(
+            cmd(Some("idea --line {line} {file}"), Some("vim")).unwrap(),
+            vec!["idea", "--line", "12", "/src/main.rs"]
+        );
+        // Without {file} the path is appended
+        assert_eq!(
+            cmd(Some("myedit"), None).unwrap(),
+            vec!["myedit", "/src/main.rs"]
+        );
+    }
+
+    #[test]
+    fn test_known_gui_editors() {
+        assert_eq!(
+            cmd(None, Some("code --wait")).unwrap(),
+            vec!["code", "--wait", "--goto", "/src/main.rs:12:5"]
+        );
+        assert_eq!(
+            cmd(None, Some("/usr/bin/zed")).unwrap(),
+            vec!["/usr/bin/zed", "/src/main.rs:12:5"]
+        );
+        assert_eq!(
+            cmd(None, Some("kate")).unwrap(),
+            vec!["kate", "-l", "12", "-c", "5", "/src/main.rs"]
+        );
+    }
+
+    #[test]
+    fn test_terminal_editors_get_a_terminal() {
+        assert_eq!(
+            cmd(None, Some("nvim")).unwrap(),
+            vec!["foot", "-e", "nvim", "+12", "/src/main.rs"]
+        );
+        assert_eq!(
+            cmd(None, Some("emacs -nw")).unwrap(),
+            vec!["foot", "-e", "emacs", "-nw", "+12:5", "/src/main.rs"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_or_missing_editor() {
+        assert_eq!(
+            cmd(None, Some("myeditor")).unwrap(),
+            vec!["myeditor", "/src/main.rs"]
+        );
+        assert!(cmd(None, None).is_none());
+        assert!(cmd(Some("  "), None).is_none());
+    }
+}
@@ -0,0 +1,520 @@
+//! Small query language for file search.
+//!
+//! `report ext:pdf,docx in:~/work type:file modified:<7d size:>10M "q3 draft"`
+//!
+//! Bare words are fuzzy-matched against the path, quoted phrases must appear
+//! verbatim (case-insensitively) and `key:value` tokens narrow the results.
+//!
+//! `modified:` ages need a unit: `s`, `min`, `h`, `d`, `w`, `mo` or `y`. `size:`
+//! takes `b` (the default), `k`, `m` or `g`, optionally followed by `b`.
+
+use crate::domain::files::{expand_tilde, FileMatch, IndexedFile};
+use crate::domain::fuzzy::fuzzy_match_path;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    File,
+    Dir,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comparison {
+    pub op: CmpOp,
+    pub value: u64,
+}
+
+impl Comparison {
+    pub fn test(&self, actual: u64) -> bool {
+        match self.op {
+            CmpOp::Lt => actual < self.value,
+            CmpOp::Le => actual <= self.value,
+            CmpOp::Gt => actual > self.value,
+            CmpOp::Ge => actual >= self.value,
+            CmpOp::Eq => actual == self.value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileQuery {
+    pub terms: Vec<String>,
+    pub phrases: Vec<String>,
+    pub extensions: Vec<String>,
+    pub within: Vec<PathBuf>,
+    pub kind: Option<FileKind>,
+    /// Age of the last modification, in seconds.
+    pub modified: Option<Comparison>,
+    /// Size in bytes. Only files can satisfy a size filter.
+    pub size: Option<Comparison>,
+    /// Bare words and phrases in their original case and order, for content search.
+    pub text: String,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryErrorCode {
+    UnterminatedQuote,
+    EmptyValue,
+    InvalidType,
+    InvalidDuration,
+    InvalidSize,
+}
+
+/// Parse failure with enough context for the omnibar to point at the culprit.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub code: QueryErrorCode,
+    pub message: String,
+    pub token: String,
+    /// Char offset of the offending token in the query.
+    pub position: usize,
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (at {})", self.message, self.position)
+    }
+}
+
+struct Token {
+    text: String,
+    position: usize,
+    quoted: bool,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut current: Option<Token> = None;
+    let mut in_quote = false;
+    let mut quote_start = 0;
+
+    for (pos, c) in input.chars().enumerate() {
+        if c == '"' {
+            in_quote = !in_quote;
+            quote_start = pos;
+            let token = current.get_or_insert_with(|| Token {
+                text: String::new(),
+                position: pos,
+                quoted: false,
+            });
+            token.quoted = true;
+        } else if c.is_whitespace() && !in_quote {
+            tokens.extend(current.take());
+        } else {
+            current
+                .get_or_insert_with(|| Token {
+                    text: String::new(),
+                    position: pos,
+                    quoted: false,
+                })
+                .text
+                .push(c);
+        }
+    }
+
+    if in_quote {
+        return Err(QueryError {
+            code: QueryErrorCode::UnterminatedQuote,
+            message: "Missing closing quote".to_string(),
+            token: input.chars().skip(quote_start).collect(),
+            position: quote_start,
+        });
+    }
+    tokens.extend(current);
+    Ok(tokens)
+}
+
+fn split_comparison(value: &str) -> (CmpOp, &str) {
+    for (prefix, op) in [
+        ("<=", CmpOp::Le),
+        (">=", CmpOp::Ge),
+        ("<", CmpOp::Lt),
+        (">", CmpOp::Gt),
+        ("=", CmpOp::Eq),
+    ] {
+        if let Some(rest) = value.strip_prefix(prefix) {
+            return (op, rest);
+        }
+    }
+    (CmpOp::Eq, value)
+}
+
+/// Splits `12.5kb` into `(12.5, "kb")`.
+fn split_number(value: &str) -> Option<(f64, String)> {
+    let idx = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let number = value[..idx].parse::<f64>().ok()?;
+    Some((number, value[idx..].to_lowercase()))
+}
+
+/// Reads an age like `7d`. There is no default unit, and minutes are spelled `min`,
+/// so `modified:<7` or `modified:<7m` can't be mistaken for days or megabytes.
+fn parse_duration(value: &str) -> Option<u64> {
+    let (number, unit) = split_number(value)?;
+    let seconds = match unit.as_str() {
+        "s" => 1.0,
+        "min" => 60.0,
+        "h" => 3600.0,
+        "d" => 86_400.0,
+        "w" => 7.0 * 86_400.0,
+        "mo" => 30.0 * 86_400.0,
+        "y" => 365.0 * 86_400.0,
+        _ => return None,
+    };
+    Some((number * seconds) as u64)
+}
+
+fn parse_size(value: &str) -> Option<u64> {
+    let (number, unit) = split_number(value)?;
+    let bytes = match unit.as_str() {
+        "" | "b" => 1.0,
+        "k" | "kb" => 1024.0,
+        "m" | "mb" => 1024.0 * 1024.0,
+        "g" | "gb" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((number * bytes) as u64)
+}
+
+impl FileQuery {
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let mut query = FileQuery::default();
+        let mut text = Vec::new();
+
+        for token in tokenize(input)? {
+            if token.quoted && !token.text.contains(':') {
+                if !token.text.is_empty() {
+                    query.phrases.push(token.text.to_lowercase());
+                    text.push(token.text);
+                }
+                continue;
+            }
+
+            let (key, value) = match token.text.split_once(':') {
+                Some((key, value)) => (key.to_lowercase(), value),
+                None => {
+                    text.push(token.text.clone());
+                    query.terms.push(token.text);
+                    continue;
+                }
+            };
+
+            let error = |code: QueryErrorCode, message: String| QueryError {
+                code,
+                message,
+                token: token.text.clone(),
+                position: token.position,
+            };
+
+            let known = matches!(key.as_str(), "ext" | "in" | "type" | "modified" | "size");
+            if !known {
+                // Not a filter (e.g. "http://..."): treat it as text
+                query.terms.push(token.text.clone());
+                text.push(token.text.clone());
+                continue;
+            }
+            if value.is_empty() {
+                return Err(error(
+                    QueryErrorCode::EmptyValue,
+                    format!("'{}:' needs a value", key),
+                ));
+            }
+
+            match key.as_str() {
+                "ext" => query.extensions.extend(
+                    value
+                        .split(',')
+                        .map(|e| e.trim_start_matches('.').to_lowercase())
+                        .filter(|e| !e.is_empty()),
+                ),
+                "in" => query.within.push(expand_tilde(value)),
+                "type" => {
+                    query.kind = Some(match value.to_lowercase().as_str() {
+                        "file" | "f" => FileKind::File,
+                        "dir" | "d" | "folder" | "directory" => FileKind::Dir,
+                        other => {
+                            return Err(error(
+                                QueryErrorCode::InvalidType,
+                                format!("Unknown type '{}', expected 'file' or 'dir'", other),
+                            ))
+                        }
+                    })
+                }
+                "modified" => {
+                    let (op, rest) = split_comparison(value);
+                    let seconds = parse_duration(rest).ok_or_else(|| {
+                        error(
+                            QueryErrorCode::InvalidDuration,
+                            format!(
+                                "Invalid duration '{}', use s, min, h, d, w, mo or y, e.g. <7d",
+                                value
+                            ),
+                        )
+                    })?;
+                    query.modified = Some(Comparison { op, value: seconds });
+                }
+                "size" => {
+                    let (op, rest) = split_comparison(value);
+                    let bytes = parse_size(rest).ok_or_else(|| {
+                        error(
+                            QueryErrorCode::InvalidSize,
+                            format!("Invalid size '{}', try e.g. >10M or <500k", value),
+                        )
+                    })?;
+                    query.size = Some(Comparison { op, value: bytes });
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        query.text = text.join(" ");
+        Ok(query)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+            && self.phrases.is_empty()
+            && self.extensions.is_empty()
+            && self.within.is_empty()
+            && self.kind.is_none()
+            && self.modified.is_none()
+            && self.size.is_none()
+    }
+
+    /// Whether the entry passes all `key:value` filters. `now` is seconds since UNIX epoch.
+    pub fn passes_filters(&self, file: &IndexedFile, now: u64) -> bool {
+        if let Some(kind) = self.kind {
+            if (kind == FileKind::Dir) != file.is_dir {
+                return false;
+            }
+        }
+        if !self.extensions.is_empty() {
+            let ext = file.extension().map(|e| e.to_lowercase());
+            if !ext.is_some_and(|ext| self.extensions.contains(&ext)) {
+                return false;
+            }
+        }
+        if !self.within.is_empty() {
+            let path = Path::new(&file.path);
+            if !self
+                .within
+                .iter()
+                .any(|dir| path != dir && path.starts_with(dir))
+            {
+                return false;
+            }
+        }
+        if let Some(size) = self.size {
+            if file.is_dir || !size.test(file.size) {
+                return false;
+            }
+        }
+        if let Some(modified) = self.modified {
+            match file.modified {
+                Some(mtime) if modified.test(now.saturating_sub(mtime)) => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Filters and fuzzy-ranks a single entry. Every term and phrase must match.
+    pub fn score(&self, file: &IndexedFile, now: u64) -> Option<FileMatch> {
+        if !self.passes_filters(file, now) {
+            return None;
+        }
+
+        let mut score = 0;
+        let mut positions = Vec::new();
+
+        if !self.phrases.is_empty() {
+            let lower_path = file.path.to_lowercase();
+            for phrase in &self.phrases {
+                let byte_idx = lower_path.find(phrase.as_str())?;
+                let start = lower_path[..byte_idx].chars().count();
+                positions.extend(start..start + phrase.chars().count());
+                score += phrase.chars().count() as i32 * 16;
+            }
+        }
+
+        for term in &self.terms {
+            let found = fuzzy_match_path(term, &file.path)?;
+            score += found.score;
+            positions.extend(found.positions);
+        }
+
+        positions.sort_unstable();
+        positions.dedup();
+
+        Some(FileMatch {
+            path: file.path.clone(),
+            is_dir: file.is_dir,
+            score,
+            positions,
+            root: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86_400;
+    const NOW: u64 = 1_700_000_000;
+
+    fn file(path: &str, is_dir: bool, size: u64, age: u64) -> IndexedFile {
+        IndexedFile {
+            path: path.to_string(),
+            is_dir,
+            size,
+            modified: Some(NOW - age),
+        }
+    }
+
+    #[test]
+    fn test_parse_terms_phrases_and_filters() {
+        let query = FileQuery::parse(
+            r#"report "Q3 Draft" ext:PDF,.docx in:/work type:file modified:<7d size:>=10M"#,
+        )
+        .unwrap();
+
+        assert_eq!(query.terms, vec!["report"]);
+        assert_eq!(query.phrases, vec!["q3 draft"]);
+        assert_eq!(query.text, "report Q3 Draft");
+        assert_eq!(query.extensions, vec!["pdf", "docx"]);
+        assert_eq!(query.within, vec![PathBuf::from("/work")]);
+        assert_eq!(query.kind, Some(FileKind::File));
+        assert_eq!(
+            query.modified,
+            Some(Comparison {
+                op: CmpOp::Lt,
+                value: 7 * DAY
+            })
+        );
+        assert_eq!(
+            query.size,
+            Some(Comparison {
+                op: CmpOp::Ge,
+                value: 10 * 1024 * 1024
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_in_expands_tilde_and_quotes() {
+        let home = dirs::home_dir().unwrap();
+        let query = FileQuery::parse(r#"in:~/work in:"/mnt/My Drive""#).unwrap();
+        assert_eq!(
+            query.within,
+            vec![home.join("work"), PathBuf::from("/mnt/My Drive")]
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys_are_plain_terms() {
+        let query = FileQuery::parse("http://example todo:").unwrap();
+        assert_eq!(query.terms, vec!["http://example", "todo:"]);
+    }
+
+    #[test]
+    fn test_parse_errors_are_structured() {
+        let err = FileQuery::parse("notes size:>lots").unwrap_err();
+        assert_eq!(err.code, QueryErrorCode::InvalidSize);
+        assert_eq!(err.token, "size:>lots");
+        assert_eq!(err.position, 6);
+
+        let err = FileQuery::parse("modified:<7x").unwrap_err();
+        assert_eq!(err.code, QueryErrorCode::InvalidDuration);
+
+        for unitless in ["notes modified:<7", "modified:>7m"] {
+            let err = FileQuery::parse(unitless).unwrap_err();
+            assert_eq!(err.code, QueryErrorCode::InvalidDuration);
+            assert_eq!(err.token, unitless.trim_start_matches("notes "));
+        }
+        let query = FileQuery::parse("modified:<90min").unwrap();
+        assert_eq!(query.modified.unwrap().value, 90 * 60);
+
+        let err = FileQuery::parse("type:pipe").unwrap_err();
+        assert_eq!(err.code, QueryErrorCode::InvalidType);
+
+        let err = FileQuery::parse("ext:").unwrap_err();
+        assert_eq!(err.code, QueryErrorCode::EmptyValue);
+
+        let err = FileQuery::parse(r#"foo "bar baz"#).unwrap_err();
+        assert_eq!(err.code, QueryErrorCode::UnterminatedQuote);
+        assert_eq!(err.position, 4);
+    }
+
+    #[test]
+    fn test_filters() {
+        let recent_pdf = file("/work/report.pdf", false, 20 * 1024 * 1024, DAY);
+        let old_pdf = file("/home/old.pdf", false, 1024, 30 * DAY);
+        let dir = file("/work/reports", true, 4096, DAY);
+
+        let query = FileQuery::parse("ext:pdf").unwrap();
+        assert!(query.passes_filters(&recent_pdf, NOW));
+        assert!(!query.passes_filters(&dir, NOW));
+
+        let query = FileQuery::parse("in:/work").unwrap();
+        assert!(query.passes_filters(&recent_pdf, NOW));
+        assert!(!query.passes_filters(&old_pdf, NOW));
+        assert!(!query.passes_filters(&file("/workshop/a.pdf", false, 1, DAY), NOW));
+
+        let query = FileQuery::parse("type:dir").unwrap();
+        assert!(query.passes_filters(&dir, NOW));
+        assert!(!query.passes_filters(&recent_pdf, NOW));
+
+        let query = FileQuery::parse("modified:<7d").unwrap();
+        assert!(query.passes_filters(&recent_pdf, NOW));
+        assert!(!query.passes_filters(&old_pdf, NOW));
+
+        let query = FileQuery::parse("size:>10M").unwrap();
+        assert!(query.passes_filters(&recent_pdf, NOW));
+        assert!(!query.passes_filters(&old_pdf, NOW));
+        assert!(!query.passes_filters(&dir, NOW));
+    }
+
+    #[test]
+    fn test_score_requires_all_terms_and_phrases() {
+        let entry = file("/work/Q3 Draft/report.pdf", false, 10, DAY);
+
+        let found = FileQuery::parse(r#"rep "q3 draft""#)
+            .unwrap()
+            .score(&entry, NOW)
+            .unwrap();
+        assert_eq!(
+            found.positions,
+            vec![6, 7, 8, 9, 10, 11, 12, 13, 15, 16, 17]
+        );
+
+        assert!(FileQuery::parse("rep zzz")
+            .unwrap()
+            .score(&entry, NOW)
+            .is_none());
+        assert!(FileQuery::parse(r#""final""#)
+            .unwrap()
+            .score(&entry, NOW)
+            .is_none());
+    }
+
+    #[test]
+    fn test_filter_only_query_matches_everything_that_passes() {
+        let query = FileQuery::parse("ext:pdf").unwrap();
+        let found = query.score(&file("/a/b.pdf", false, 1, DAY), NOW).unwrap();
+        assert_eq!(found.score, 0);
+        assert!(found.positions.is_empty());
+    }
+}
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A single entry of the persistent file index.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IndexedFile {
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<u64>, // Seconds since UNIX epoch
+}
+
+impl IndexedFile {
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    pub fn extension(&self) -> Option<&str> {
+        if self.is_dir {
+            return None;
+        }
+        let name = self.file_name();
+        match name.rfind('.') {
+            Some(0) | None => None,
+            Some(idx) => Some(&name[idx + 1..]),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexState {
+    Idle,
+    Crawling,
+    Ready,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct IndexStatus {
+    pub state: IndexState,
+    pub indexed: usize,
+    pub roots: Vec<String>,
+    /// Why some directories aren't watched, so the index can go stale there.
+    pub watch_error: Option<String>,
+}
+
+/// A ranked file search hit.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FileMatch {
+    pub path: String,
+    pub is_dir: bool,
+    pub score: i32,
+    /// Char indices into `path` that matched the query, for highlighting.
+    pub positions: Vec<usize>,
+    /// Name of the search root the file was found under.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+}
+
+fn name_len(path: &str) -> usize {
+    path.len() - path.rfind('/').map(|i| i + 1).unwrap_or(0)
+}
+
+/// Best matches first; ties go to the tighter file name, then the shallower path.
+pub fn rank_matches(matches: &mut Vec<FileMatch>, limit: usize) {
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| name_len(&a.path).cmp(&name_len(&b.path)))
+            .then_with(|| a.path.len().cmp(&b.path.len()))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    matches.truncate(limit);
+}
+
+/// Expands a leading `~` to the user's home directory.
+pub fn expand_tilde(path: &str) -> PathBuf {
+    if path == "~" {
+        return dirs::home_dir().unwrap_or_else(|| PathBuf::from(path));
+    }
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::file_query::FileQuery;
+
+    fn file(path: &str) -> IndexedFile {
+        IndexedFile {
+            path: path.to_string(),
+            is_dir: false,
+            size: 0,
+            modified: None,
+        }
+    }
+
+    #[test]
+    fn test_rank_matches_orders_and_limits() {
+        let query = FileQuery::parse("foo").unwrap();
+        let mut matches: Vec<FileMatch> =
+            ["/a/very/deep/foo.rs", "/a/src/foo", "/a/foo.rs", "/a/f_o_o"]
+                .iter()
+                .filter_map(|p| query.score(&file(p), 0))
+                .collect();
+        rank_matches(&mut matches, 3);
+
+        assert_eq!(matches.len(), 3);
+        assert_eq!(matches[0].path, "/a/src/foo");
+        assert_eq!(matches[1].path, "/a/foo.rs");
+        assert_eq!(matches[2].path, "/a/very/deep/foo.rs");
+    }
+
+    #[test]
+    fn test_indexed_file_name_and_extension() {
+        let file = IndexedFile {
+            path: "/home/user/notes.today.md".to_string(),
+            is_dir: false,
+            size: 10,
+            modified: None,
+        };
+        assert_eq!(file.file_name(), "notes.today.md");
+        assert_eq!(file.extension(), Some("md"));
+
+        let dotfile = IndexedFile {
+            path: "/home/user/.bashrc".to_string(),
+            is_dir: false,
+            size: 10,
+            modified: None,
+        };
+        assert_eq!(dotfile.extension(), None);
+    }
+
+    #[test]
+    fn test_expand_tilde() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_tilde("~"), home);
+        assert_eq!(expand_tilde("~/work"), home.join("work"));
+        assert_eq!(expand_tilde("/tmp/x"), PathBuf::from("/tmp/x"));
+    }
+}
@@ -0,0 +1,232 @@
+//! Frecency: how often *and* how recently something was picked.
+//!
+//! Every recorded action gets `frequency × recency weight`, where the weight
+//! falls off in buckets like Firefox's URL bar does. Search code turns that
+//! into a bounded bonus on top of the fuzzy match score, so history can
+//! reorder good matches without dragging in bad ones.
+//!
+//! On top of that comes what was learned for the query being typed: if
+//! Telegram keeps getting picked for `te`, it gets a larger bonus for `te`
+//! than Terminal does, whatever their overall frecency.
+
+use crate::domain::action::Action;
+use crate::domain::files::{rank_matches, FileMatch};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+const HOUR_MS: u64 = 60 * 60 * 1000;
+const DAY_MS: u64 = 24 * HOUR_MS;
+
+/// Largest bonus history can add to a match score.
+pub const MAX_BOOST: i32 = 64;
+/// Points per unit of `ln(1 + frecency)`.
+const BOOST_SCALE: f64 = 10.0;
+/// Largest bonus a learned query association can add.
+pub const MAX_LEARNED_BOOST: i32 = 96;
+/// Points per unit of `ln(1 + affinity)`; ten picks for a query about max it out.
+const LEARNED_SCALE: f64 = 40.0;
+
+/// Weight for an action last used `age_ms` ago.
+fn recency_weight(age_ms: u64) -> f64 {
+    match age_ms {
+        a if a < 4 * HOUR_MS => 100.0,
+        a if a < DAY_MS => 80.0,
+        a if a < 3 * DAY_MS => 60.0,
+        a if a < 7 * DAY_MS => 40.0,
+        a if a < 30 * DAY_MS => 20.0,
+        a if a < 90 * DAY_MS => 10.0,
+        _ => 1.0,
+    }
+}
+
+/// Raw frecency of an action. `now_ms` uses the same clock as `last_accessed`.
+pub fn frecency(action: &Action, now_ms: u64) -> f64 {
+    action.frequency.max(1) as f64 * recency_weight(now_ms.saturating_sub(action.last_accessed))
+}
+
+/// Score bonus for a given raw frecency, growing logarithmically up to `MAX_BOOST`.
+pub fn boost_for(frecency: f64) -> i32 {
+    ((frecency.ln_1p() * BOOST_SCALE) as i32).clamp(0, MAX_BOOST)
+}
+
+/// Score bonus for how strongly an action is associated with the typed query.
+pub fn learned_boost_for(affinity: f64) -> i32 {
+    ((affinity.ln_1p() * LEARNED_SCALE) as i32).clamp(0, MAX_LEARNED_BOOST)
+}
+
+/// Frecency lookup by action id (`app:<exec>`, `file:<path>`, ...), built for
+/// one query so learned associations can be folded in.
+#[derive(Debug, Clone, Default)]
+pub struct Frecency {
+    scores: HashMap<String, f64>,
+    learned: HashMap<String, i32>,
+}
+
+impl Frecency {
+    pub fn from_actions(actions: &[Action], query: &str, now_ms: u64) -> Self {
+        Self {
+            scores: actions
+                .iter()
+                .map(|action| (action.id.clone(), frecency(action, now_ms)))
+                .collect(),
+            learned: actions
+                .iter()
+                .map(|action| {
+                    let boost = learned_boost_for(action.affinity(query, now_ms));
+                    (action.id.clone(), boost)
+                })
+                .filter(|(_, boost)| *boost > 0)
+                .collect(),
+        }
+    }
+
+    pub fn score(&self, id: &str) -> f64 {
+        self.scores.get(id).copied().unwrap_or(0.0)
+    }
+
+    /// Bonus from what was learned for this query alone.
+    pub fn learned_boost(&self, id: &str) -> i32 {
+        self.learned.get(id).copied().unwrap_or(0)
+    }
+
+    /// Total history bonus: general frecency plus the learned association.
+    pub fn boost(&self, id: &str) -> i32 {
+        boost_for(self.score(id)) + self.learned_boost(id)
+    }
+
+    /// Adds the history bonus to every file match, then re-ranks and truncates.
+    pub fn rerank_files(&self, matches: &mut Vec<FileMatch>, limit: usize) {
+        for file in matches.iter_mut() {
+            file.score += self.boost(&format!("file:{}", file.path));
+        }
+        rank_matches(matches, limit);
+    }
+}
+
+/// Recorded history as of the last time it changed, so searching doesn't go back
+/// to the history repository on every keystroke.
+#[derive(Default)]
+pub struct HistorySnapshot {
+    actions: RwLock<Option<Arc<Vec<Action>>>>,
+}
+
+impl HistorySnapshot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cached history, or `None` until it was first loaded.
+    pub fn get(&self) -> Option<Arc<Vec<Action>>> {
+        self.actions.read().unwrap().clone()
+    }
+
+    /// Replaces the cached history, most recent first.
+    pub fn set(&self, actions: Vec<Action>) -> Arc<Vec<Action>> {
+        let actions = Arc::new(actions);
+        *self.actions.write().unwrap() = Some(actions.clone());
+        actions
+    }
+}
+
+/// Milliseconds since the UNIX epoch, the clock `Action::last_accessed` uses.
+pub fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(id: &str, frequency: u64, last_accessed: u64) -> Action {
+        Action {
+            id: id.to_string(),
+            kind: "app".to_string(),
+            content: String::new(),
+            name: String::new(),
+            icon: None,
+            last_accessed,
+            frequency,
+            associations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_recent_and_frequent_wins() {
+        let now = 100 * DAY_MS;
+        let often_recent = action("a", 10, now - HOUR_MS);
+        let often_old = action("b", 10, now - 60 * DAY_MS);
+        let once_recent = action("c", 1, now - HOUR_MS);
+
+        assert!(frecency(&often_recent, now) > frecency(&often_old, now));
+        assert!(frecency(&often_recent, now) > frecency(&once_recent, now));
+    }
+
+    #[test]
+    fn test_boost_is_bounded_and_monotonic() {
+        assert_eq!(boost_for(0.0), 0);
+        assert!(boost_for(100.0) > boost_for(10.0));
+        assert_eq!(boost_for(1e12), MAX_BOOST);
+    }
+
+    #[test]
+    fn test_lookup_by_id() {
+        let now = 10 * DAY_MS;
+        let frecency = Frecency::from_actions(&[action("app:firefox", 5, now)], "", now);
+
+        assert!(frecency.boost("app:firefox") > 0);
+        assert_eq!(frecency.boost("app:chromium"), 0);
+    }
+
+    #[test]
+    fn test_rerank_files() {
+        let file = |path: &str, score| FileMatch {
+            path: path.to_string(),
+            is_dir: false,
+            score,
+            positions: vec![],
+            root: None,
+        };
+        let mut matches = vec![file("/a/report.pdf", 50), file("/b/report.pdf", 45)];
+        let now = 10 * DAY_MS;
+        let frecency = Frecency::from_actions(&[action("file:/b/report.pdf", 10, now)], "", now);
+        frecency.rerank_files(&mut matches, 1);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].path, "/b/report.pdf");
+    }
+
+    #[test]
+    fn test_learned_choice_beats_frecency() {
+        let now = 10 * DAY_MS;
+        let terminal = action("app:terminal", 50, now);
+        let mut telegram = action("app:telegram", 2, now - 5 * DAY_MS);
+        for _ in 0..10 {
+            telegram.learn("te", now - 5 * DAY_MS);
+        }
+        let actions = [terminal, telegram];
+
+        let typed_te = Frecency::from_actions(&actions, "te", now);
+        assert!(typed_te.boost("app:telegram") > typed_te.boost("app:terminal"));
+        assert!(typed_te.learned_boost("app:telegram") <= MAX_LEARNED_BOOST);
+
+        // Other queries only see plain frecency
+        let typed_x = Frecency::from_actions(&actions, "x", now);
+        assert_eq!(typed_x.learned_boost("app:telegram"), 0);
+        assert!(typed_x.boost("app:telegram") < typed_x.boost("app:terminal"));
+    }
+
+    #[test]
+    fn test_history_snapshot() {
+        let snapshot = HistorySnapshot::new();
+        assert!(snapshot.get().is_none());
+
+        snapshot.set(vec![action("app:firefox", 1, 0)]);
+        snapshot.set(vec![action("app:zed", 1, 0)]);
+        let actions = snapshot.get().unwrap();
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].id, "app:zed");
+    }
+}
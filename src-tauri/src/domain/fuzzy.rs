@@ -0,0 +1,324 @@
+//! Subsequence fuzzy matcher in the spirit of fzf's v2 algorithm.
+//!
+//! Every pattern character must appear in the candidate in order. Among all
+//! possible alignments the best scoring one is picked: matches right after a
+//! path separator, a word boundary or a camelCase hump earn bonuses, runs of
+//! consecutive characters are rewarded and gaps between matches cost points.
+
+const SCORE_MATCH: i32 = 16;
+const SCORE_GAP_START: i32 = -3;
+const SCORE_GAP_EXTENSION: i32 = -1;
+
+const BONUS_PATH: i32 = 9;
+const BONUS_BOUNDARY: i32 = 8;
+const BONUS_CAMEL: i32 = 7;
+const BONUS_CONSECUTIVE: i32 = -(SCORE_GAP_START + SCORE_GAP_EXTENSION);
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+
+/// Extra points when the whole pattern matches inside the file name of a path.
+const BONUS_BASENAME: i32 = 24;
+
+const UNREACHABLE: i32 = i32::MIN / 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    /// Indices (in chars, not bytes) of the matched characters in the candidate.
+    pub positions: Vec<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Separator,
+    Delimiter,
+    Lower,
+    Upper,
+    Digit,
+    Other,
+}
+
+fn char_class(c: char) -> CharClass {
+    match c {
+        '/' | '\\' => CharClass::Separator,
+        ' ' | '_' | '-' | '.' | ',' | ':' | ';' | '|' => CharClass::Delimiter,
+        c if c.is_lowercase() => CharClass::Lower,
+        c if c.is_uppercase() => CharClass::Upper,
+        c if c.is_numeric() => CharClass::Digit,
+        _ => CharClass::Other,
+    }
+}
+
+/// Bonus for matching a character of class `current` that follows one of class `prev`.
+fn position_bonus(prev: CharClass, current: CharClass) -> i32 {
+    match (prev, current) {
+        (_, CharClass::Separator) | (_, CharClass::Delimiter) => 0,
+        (CharClass::Separator, _) => BONUS_PATH,
+        (CharClass::Delimiter, _) => BONUS_BOUNDARY,
+        (CharClass::Lower, CharClass::Upper) => BONUS_CAMEL,
+        (CharClass::Lower, CharClass::Digit) | (CharClass::Upper, CharClass::Digit) => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Cheap check used to reject candidates before running the full scorer.
+fn is_subsequence(pattern: &[char], candidate: &[char]) -> bool {
+    let mut wanted = pattern.iter();
+    let mut next = wanted.next();
+    for &c in candidate {
+        match next {
+            Some(&p) if p == fold(c) => next = wanted.next(),
+            Some(_) => {}
+            None => break,
+        }
+    }
+    next.is_none()
+}
+
+/// Scores `candidate` against `pattern`, case-insensitively.
+///
+/// Returns `None` if the pattern is not a subsequence of the candidate.
+/// An empty pattern matches everything with a score of zero.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern.chars().map(fold).collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    let (n, m) = (pattern.len(), chars.len());
+
+    if n == 0 {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+    if n > m || !is_subsequence(&pattern, &chars) {
+        return None;
+    }
+
+    let mut bonus = Vec::with_capacity(m);
+    let mut prev = CharClass::Separator; // The start of the string counts as a boundary
+    for &c in &chars {
+        let class = char_class(c);
+        bonus.push(position_bonus(prev, class));
+        prev = class;
+    }
+
+    // matched[i][j]: best score with pattern[i] matched at chars[j].
+    // gapped[i][j]: best score with pattern[i] matched before j and a gap running through j.
+    // For backtracking we remember whether a match extended a consecutive run,
+    // and where the match that opened each gap sits.
+    let idx = |i: usize, j: usize| i * m + j;
+    let mut matched = vec![UNREACHABLE; n * m];
+    let mut gapped = vec![UNREACHABLE; n * m];
+    let mut consecutive = vec![false; n * m];
+    let mut gap_origin = vec![0usize; n * m];
+    // Like fzf, a consecutive run keeps the bonus its first character earned
+    let mut run_bonus = vec![0; n * m];
+
+    for i in 0..n {
+        for j in i..m {
+            if fold(chars[j]) == pattern[i] {
+                run_bonus[idx(i, j)] = bonus[j];
+                if i == 0 {
+                    matched[idx(i, j)] = SCORE_MATCH + bonus[j] * BONUS_FIRST_CHAR_MULTIPLIER;
+                } else if j > 0 {
+                    let run = matched[idx(i - 1, j - 1)];
+                    let jump = gapped[idx(i - 1, j - 1)];
+                    let carried = run_bonus[idx(i - 1, j - 1)]
+                        .max(bonus[j])
+                        .max(BONUS_CONSECUTIVE);
+                    let run_score = if run > UNREACHABLE {
+                        run + SCORE_MATCH + carried
+                    } else {
+                        UNREACHABLE
+                    };
+                    let jump_score = if jump > UNREACHABLE {
+                        jump + SCORE_MATCH + bonus[j]
+                    } else {
+                        UNREACHABLE
+                    };
+                    if run_score >= jump_score {
+                        matched[idx(i, j)] = run_score;
+                        consecutive[idx(i, j)] = run_score > UNREACHABLE;
+                        run_bonus[idx(i, j)] = carried;
+                    } else {
+                        matched[idx(i, j)] = jump_score;
+                    }
+                }
+            }
+
+            if j > 0 {
+                let open = matched[idx(i, j - 1)];
+                let extend = gapped[idx(i, j - 1)];
+                let open_score = if open > UNREACHABLE {
+                    open + SCORE_GAP_START
+                } else {
+                    UNREACHABLE
+                };
+                let extend_score = if extend > UNREACHABLE {
+                    extend + SCORE_GAP_EXTENSION
+                } else {
+                    UNREACHABLE
+                };
+                if open_score >= extend_score {
+                    gapped[idx(i, j)] = open_score;
+                    gap_origin[idx(i, j)] = j - 1;
+                } else {
+                    gapped[idx(i, j)] = extend_score;
+                    gap_origin[idx(i, j)] = gap_origin[idx(i, j - 1)];
+                }
+            }
+        }
+    }
+
+    // Trailing characters after the last match are free
+    let (mut j, score) = (0..m)
+        .map(|j| (j, matched[idx(n - 1, j)]))
+        .filter(|&(_, score)| score > UNREACHABLE)
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))?;
+
+    let mut positions = vec![0; n];
+    for i in (0..n).rev() {
+        positions[i] = j;
+        if i > 0 {
+            j = if consecutive[idx(i, j)] {
+                j - 1
+            } else {
+                gap_origin[idx(i - 1, j - 1)]
+            };
+        }
+    }
+
+    Some(FuzzyMatch { score, positions })
+}
+
+/// Scores a filesystem path, preferring matches that fall entirely within the file name.
+///
+/// Positions always refer to chars of the full `path`.
+pub fn fuzzy_match_path(pattern: &str, path: &str) -> Option<FuzzyMatch> {
+    let trimmed = path.trim_end_matches('/');
+    let name_start = trimmed.rfind('/').map(|i| i + 1).unwrap_or(0);
+    let name = &trimmed[name_start..];
+
+    if !pattern.contains('/') {
+        if let Some(mut found) = fuzzy_match(pattern, name) {
+            let offset = path[..name_start].chars().count();
+            found.positions.iter_mut().for_each(|p| *p += offset);
+            found.score += BONUS_BASENAME;
+            return Some(found);
+        }
+    }
+
+    fuzzy_match(pattern, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(pattern: &str, candidate: &str) -> i32 {
+        fuzzy_match(pattern, candidate)
+            .unwrap_or_else(|| panic!("{:?} should match {:?}", pattern, candidate))
+            .score
+    }
+
+    fn positions(pattern: &str, candidate: &str) -> Vec<usize> {
+        fuzzy_match(pattern, candidate).unwrap().positions
+    }
+
+    #[test]
+    fn test_requires_subsequence() {
+        assert!(fuzzy_match("abc", "a-b-c").is_some());
+        assert!(fuzzy_match("abc", "acb").is_none());
+        assert!(fuzzy_match("abcd", "abc").is_none());
+        assert!(fuzzy_match("x", "").is_none());
+    }
+
+    #[test]
+    fn test_empty_pattern_matches_everything() {
+        let found = fuzzy_match("", "anything").unwrap();
+        assert_eq!(found.score, 0);
+        assert!(found.positions.is_empty());
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        assert!(fuzzy_match("README", "readme.md").is_some());
+        assert!(fuzzy_match("readme", "README.md").is_some());
+        assert_eq!(score("abc", "ABC"), score("abc", "abc"));
+    }
+
+    #[test]
+    fn test_positions_are_char_indices() {
+        assert_eq!(positions("fb", "foo_bar"), vec![0, 4]);
+        // Multi-byte characters count as one position
+        assert_eq!(positions("nb", "ñandú_bar"), vec![2, 6]);
+    }
+
+    #[test]
+    fn test_picks_best_alignment_not_first() {
+        // The greedy leftmost alignment would be "b" in "abc"; the boundary after '_' is better
+        assert_eq!(positions("bar", "abc_bar"), vec![4, 5, 6]);
+        assert_eq!(positions("fs", "fooSearch"), vec![0, 3]);
+    }
+
+    #[test]
+    fn test_consecutive_beats_scattered() {
+        assert!(score("foo", "foo_x") > score("foo", "f_o_o"));
+        assert!(score("config", "config.json") > score("config", "c_o_n_f_i_g"));
+    }
+
+    #[test]
+    fn test_word_boundary_bonus() {
+        assert!(score("b", "foo_bar") > score("b", "foobar"));
+        assert!(score("b", "foo-bar") > score("b", "foobar"));
+        assert!(score("b", "foo bar") > score("b", "foobar"));
+    }
+
+    #[test]
+    fn test_camel_case_bonus() {
+        assert!(score("fs", "fileSearch") > score("fs", "filesearch"));
+        assert!(score("fs", "FileSearch") > score("fs", "Filesearch"));
+    }
+
+    #[test]
+    fn test_path_separator_bonus() {
+        assert!(score("s", "a/src") > score("s", "a_src"));
+        assert!(score("sf", "src/foo") > score("sf", "xsxf"));
+    }
+
+    #[test]
+    fn test_first_char_bonus_prefers_prefix() {
+        assert!(score("rep", "report.txt") > score("rep", "the_report.txt"));
+    }
+
+    #[test]
+    fn test_gaps_are_penalized() {
+        assert!(score("ab", "a_b") > score("ab", "a____b"));
+        assert!(score("ab", "axb") > score("ab", "axxxxxxb"));
+    }
+
+    #[test]
+    fn test_path_prefers_basename() {
+        let in_name = fuzzy_match_path("foo", "/home/user/src/foo.rs").unwrap();
+        let in_dir = fuzzy_match_path("foo", "/home/user/foo/bar.rs").unwrap();
+        assert!(in_name.score > in_dir.score);
+        assert_eq!(in_name.positions, vec![15, 16, 17]);
+        assert_eq!(in_dir.positions, vec![11, 12, 13]);
+    }
+
+    #[test]
+    fn test_path_pattern_with_separator_matches_full_path() {
+        let found = fuzzy_match_path("src/foo", "/work/src/foo.rs").unwrap();
+        assert_eq!(found.positions, vec![6, 7, 8, 9, 10, 11, 12]);
+        assert!(fuzzy_match_path("src/foo", "/work/foo.rs").is_none());
+    }
+
+    #[test]
+    fn test_path_directory_with_trailing_slash() {
+        let found = fuzzy_match_path("docs", "/home/docs/").unwrap();
+        assert_eq!(found.positions, vec![6, 7, 8, 9]);
+    }
+}
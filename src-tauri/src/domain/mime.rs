@@ -0,0 +1,312 @@
+//! MIME types and the apps that open them. Types are guessed from file names with
+//! the shared-mime-info database (`globs2`, `aliases`, `subclasses`); which apps
+//! handle a type comes from the desktop files' `MimeType=` lines and the
+//! `mimeapps.list` files described by the mime-apps spec.
+
+use crate::domain::apps::AppEntry;
+use crate::domain::desktop_entry::DesktopFile;
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Glob {
+    weight: u32,
+    mime: String,
+    pattern: String,
+    case_sensitive: bool,
+}
+
+/// The parts of shared-mime-info needed to type files by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MimeDatabase {
+    globs: Vec<Glob>,
+    aliases: HashMap<String, String>,
+    parents: HashMap<String, Vec<String>>,
+}
+
+/// `key value` lines, as in the `aliases` and `subclasses` files.
+fn pairs(content: &str) -> impl Iterator<Item = (&str, &str)> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(' '))
+}
+
+impl MimeDatabase {
+    /// Adds one data dir's `globs2`, `aliases` and `subclasses`; earlier dirs win
+    /// on conflicting aliases and parents.
+    pub fn add(&mut self, globs2: &str, aliases: &str, subclasses: &str) {
+        for line in globs2.lines().filter(|line| !line.starts_with('#')) {
+            // weight:type:glob[:flags]
+            let mut fields = line.splitn(4, ':');
+            let (Some(weight), Some(mime), Some(pattern)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let Ok(weight) = weight.parse() else {
+                continue;
+            };
+            self.globs.push(Glob {
+                weight,
+                mime: mime.to_string(),
+                pattern: pattern.to_string(),
+                case_sensitive: fields.next().is_some_and(|flags| flags.contains("cs")),
+            });
+        }
+        for (alias, mime) in pairs(aliases) {
+            self.aliases
+                .entry(alias.to_string())
+                .or_insert_with(|| mime.to_string());
+        }
+        for (mime, parent) in pairs(subclasses) {
+            self.parents
+                .entry(mime.to_string())
+                .or_default()
+                .push(parent.to_string());
+        }
+    }
+
+    /// The type of a file called `name`: the heaviest matching glob, and among
+    /// those the longest pattern.
+    pub fn type_for_name(&self, name: &str) -> Option<String> {
+        let lower = name.to_lowercase();
+        self.globs
+            .iter()
+            .filter(|glob| {
+                let name = if glob.case_sensitive { name } else { &lower };
+                let pattern = if glob.case_sensitive {
+                    glob.pattern.clone()
+                } else {
+                    glob.pattern.to_lowercase()
+                };
+                match pattern.strip_prefix('*') {
+                    Some(suffix) if !suffix.contains(['*', '?', '[']) => name.ends_with(suffix),
+                    _ if !pattern.contains(['*', '?', '[']) => name == pattern,
+                    _ => glob::Pattern::new(&pattern).is_ok_and(|p| p.matches(name)),
+                }
+            })
+            .max_by_key(|glob| (glob.weight, glob.pattern.len()))
+            .map(|glob| glob.mime.clone())
+    }
+
+    pub fn canonical(&self, mime: &str) -> String {
+        self.aliases
+            .get(mime)
+            .cloned()
+            .unwrap_or_else(|| mime.to_string())
+    }
+
+    /// `mime` followed by the types it is a kind of, closest first, e.g.
+    /// `text/x-python`, `application/x-executable`, `text/plain`.
+    pub fn with_parents(&self, mime: &str) -> Vec<String> {
+        let mut types = vec![self.canonical(mime)];
+        let mut i = 0;
+        while let Some(current) = types.get(i).cloned() {
+            let mut parents = self.parents.get(&current).cloned().unwrap_or_default();
+            // Every text format can be read as plain text
+            if current.starts_with("text/") && current != "text/plain" {
+                parents.push("text/plain".to_string());
+            }
+            for parent in parents {
+                let parent = self.canonical(&parent);
+                if !types.contains(&parent) {
+                    types.push(parent);
+                }
+            }
+            i += 1;
+        }
+        types
+    }
+}
+
+/// One `mimeapps.list`, keyed by MIME type, each listing desktop-file ids.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MimeApps {
+    defaults: HashMap<String, Vec<String>>,
+    added: HashMap<String, Vec<String>>,
+    removed: HashMap<String, Vec<String>>,
+}
+
+impl MimeApps {
+    pub fn parse(content: &str) -> Self {
+        let file = DesktopFile::parse(content);
+        let section = |name: &str| {
+            file.group(name)
+                .map(|group| {
+                    group
+                        .keys()
+                        .map(|mime| (mime.to_string(), group.list(mime)))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        Self {
+            defaults: section("Default Applications"),
+            added: section("Added Associations"),
+            removed: section("Removed Associations"),
+        }
+    }
+}
+
+/// The apps a file can be opened with.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct OpenWith {
+    pub mime_type: Option<String>,
+    pub default: Option<AppEntry>,
+    /// Every other app that handles the type or one of its parents.
+    pub others: Vec<AppEntry>,
+}
+
+/// Resolves the apps for `types` (a type and its parents, as `with_parents` gives
+/// them) from `lists`, highest precedence first, and the installed `apps`.
+/// Defaults are taken from the first list that names an installed app, falling
+/// back to the first associated app; associations removed in any list are dropped.
+pub fn open_with(types: &[String], lists: &[MimeApps], apps: &[AppEntry]) -> OpenWith {
+    let installed = |id: &str| apps.iter().find(|app| app.id == id);
+    let mut default = None;
+    let mut associated: Vec<&AppEntry> = Vec::new();
+
+    for mime in types {
+        let removed: Vec<&String> = lists
+            .iter()
+            .filter_map(|list| list.removed.get(mime))
+            .flatten()
+            .collect();
+        if default.is_none() {
+            default = lists
+                .iter()
+                .filter_map(|list| list.defaults.get(mime))
+                .flatten()
+                .find_map(|id| installed(id));
+        }
+        let added = lists
+            .iter()
+            .filter_map(|list| list.added.get(mime))
+            .flatten()
+            .filter_map(|id| installed(id));
+        let declared = apps.iter().filter(|app| app.mime_types.contains(mime));
+        for app in added.chain(declared) {
+            if !removed.contains(&&app.id) && !associated.iter().any(|a| a.id == app.id) {
+                associated.push(app);
+            }
+        }
+    }
+
+    let default = default.or_else(|| associated.first().copied());
+    OpenWith {
+        mime_type: types.first().cloned(),
+        default: default.cloned(),
+        others: associated
+            .into_iter()
+            .filter(|app| Some(&app.id) != default.map(|d| &d.id))
+            .cloned()
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database() -> MimeDatabase {
+        let mut database = MimeDatabase::default();
+        database.add(
+            "# comment\n50:text/x-python:*.py\n50:text/x-csrc:*.c:cs\n\
+             50:text/x-c++src:*.C:cs\n60:application/x-compressed-tar:*.tar.gz\n\
+             50:application/gzip:*.gz\n50:text/x-makefile:makefile\n\
+             50:text/x-readme:README*\n",
+            "application/x-gzip application/gzip\n",
+            "application/x-compressed-tar application/gzip\ntext/x-csrc text/plain\n",
+        );
+        database
+    }
+
+    fn app(name: &str, mime_types: &[&str]) -> AppEntry {
+        AppEntry {
+            id: format!("{}.desktop", name.to_lowercase()),
+            name: name.to_string(),
+            mime_types: mime_types.iter().map(|m| m.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_types_by_name() {
+        let database = database();
+        assert_eq!(
+            database.type_for_name("main.py").as_deref(),
+            Some("text/x-python")
+        );
+        assert_eq!(
+            database.type_for_name("MAIN.PY").as_deref(),
+            Some("text/x-python")
+        );
+        assert_eq!(
+            database.type_for_name("a.c").as_deref(),
+            Some("text/x-csrc")
+        );
+        assert_eq!(
+            database.type_for_name("a.C").as_deref(),
+            Some("text/x-c++src")
+        );
+        assert_eq!(
+            database.type_for_name("src.tar.gz").as_deref(),
+            Some("application/x-compressed-tar")
+        );
+        assert_eq!(
+            database.type_for_name("Makefile").as_deref(),
+            Some("text/x-makefile")
+        );
+        assert_eq!(
+            database.type_for_name("README.md").as_deref(),
+            Some("text/x-readme")
+        );
+        assert_eq!(database.type_for_name("notes"), None);
+
+        assert_eq!(
+            database.with_parents("application/x-compressed-tar"),
+            vec!["application/x-compressed-tar", "application/gzip"]
+        );
+        assert_eq!(
+            database.with_parents("text/x-python"),
+            vec!["text/x-python", "text/plain"]
+        );
+        assert_eq!(database.canonical("application/x-gzip"), "application/gzip");
+    }
+
+    #[test]
+    fn test_open_with_follows_mimeapps_lists() {
+        let apps = vec![
+            app("Gedit", &["text/plain"]),
+            app("Code", &["text/plain", "text/x-python"]),
+            app("Vim", &["text/plain"]),
+            app("Idle", &[]),
+        ];
+        let user = MimeApps::parse(
+            "[Default Applications]\ntext/x-python=missing.desktop;gedit.desktop;\n\n\
+             [Added Associations]\ntext/x-python=idle.desktop;\n\n\
+             [Removed Associations]\ntext/plain=vim.desktop;\n",
+        );
+        let system = MimeApps::parse("[Default Applications]\ntext/x-python=code.desktop\n");
+        let types = database().with_parents("text/x-python");
+
+        let resolved = open_with(&types, &[user.clone(), system.clone()], &apps);
+        assert_eq!(resolved.mime_type.as_deref(), Some("text/x-python"));
+        assert_eq!(resolved.default.unwrap().name, "Gedit");
+        let others: Vec<&str> = resolved.others.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(others, vec!["Idle", "Code"]);
+
+        // Without a user default the system's applies
+        let resolved = open_with(&types, &[system], &apps);
+        assert_eq!(resolved.default.unwrap().name, "Code");
+        // Without any default the first associated app is used
+        let resolved = open_with(&["text/plain".to_string()], &[], &apps);
+        assert_eq!(resolved.default.unwrap().name, "Gedit");
+        assert_eq!(resolved.others.len(), 2);
+        assert_eq!(
+            open_with(&["image/png".to_string()], &[user], &apps).default,
+            None
+        );
+    }
+}
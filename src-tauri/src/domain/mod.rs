@@ -4,4 +4,20 @@ pub mod config;
 pub mod windows;
 // Future domains: ai, etc.
 pub mod action;
+pub mod content_search;
+pub mod desktop_entry;
+pub mod desktop_exec;
+pub mod editor;
+pub mod file_query;
+pub mod files;
+pub mod frecency;
+pub mod fuzzy;
+pub mod mime;
+pub mod plugin;
+pub mod schedule;
+pub mod script_filter;
+pub mod script_metadata;
+pub mod script_run;
+pub mod search;
+pub mod search_session;
 pub mod translation;
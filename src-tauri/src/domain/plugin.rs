@@ -0,0 +1,132 @@
+//! JSON-lines protocol spoken with external search plugins.
+//!
+//! A plugin is a long-lived process. Every line it gets on stdin and writes to
+//! stdout is one JSON object with a `type` field:
+//!
+//! ```text
+//! -> {"type":"query","id":7,"query":"INC-12"}
+//! <- {"type":"results","id":7,"results":[{"id":"INC-1234","title":"INC-1234 Login broken"}]}
+//! <- {"type":"results","id":7,"results":[...],"done":true}
+//! -> {"type":"action","result_id":"INC-1234","action":"open"}
+//! ```
+//!
+//! Results for a query may come in several batches; `done` marks the last one.
+//! Replies to queries that are no longer current are ignored, as are lines the
+//! host doesn't understand.
+
+use serde::{Deserialize, Serialize};
+
+/// Messages sent to a plugin.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HostMessage {
+    Query {
+        id: u64,
+        query: String,
+    },
+    /// The user picked `action` on one of the plugin's results.
+    Action {
+        result_id: String,
+        action: String,
+    },
+}
+
+/// Messages read from a plugin.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PluginMessage {
+    Results {
+        id: u64,
+        results: Vec<PluginResult>,
+        #[serde(default)]
+        done: bool,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PluginResult {
+    /// Plugin-chosen id, echoed back in `action` messages.
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub subtitle: String,
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Relevance as judged by the plugin; fuzzy matching on the title otherwise.
+    #[serde(default)]
+    pub score: Option<i32>,
+    /// First one is the default. Plugins that list none get a single `open`.
+    #[serde(default)]
+    pub actions: Vec<PluginAction>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PluginAction {
+    pub id: String,
+    pub title: String,
+}
+
+/// A result together with the plugin that produced it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginHit {
+    pub plugin: String,
+    pub result: PluginResult,
+}
+
+impl HostMessage {
+    /// The message as one protocol line, newline included.
+    pub fn to_line(&self) -> String {
+        let mut line = serde_json::to_string(self).unwrap_or_default();
+        line.push('\n');
+        line
+    }
+}
+
+impl PluginMessage {
+    /// `None` for blank, malformed or unknown lines.
+    pub fn parse(line: &str) -> Option<Self> {
+        serde_json::from_str(line.trim()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_messages_are_single_lines() {
+        let query = HostMessage::Query {
+            id: 3,
+            query: "inc \"12\"".to_string(),
+        };
+        assert_eq!(
+            query.to_line(),
+            "{\"type\":\"query\",\"id\":3,\"query\":\"inc \\\"12\\\"\"}\n"
+        );
+
+        let action = HostMessage::Action {
+            result_id: "INC-1".to_string(),
+            action: "open".to_string(),
+        };
+        assert_eq!(
+            action.to_line(),
+            "{\"type\":\"action\",\"result_id\":\"INC-1\",\"action\":\"open\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_results() {
+        let line = r#"{"type":"results","id":3,"results":[{"id":"a","title":"Alice","actions":[{"id":"page","title":"Page"}]}]}"#;
+        let Some(PluginMessage::Results { id, results, done }) = PluginMessage::parse(line) else {
+            panic!("not parsed");
+        };
+        assert_eq!(id, 3);
+        assert!(!done);
+        assert_eq!(results[0].subtitle, "");
+        assert_eq!(results[0].actions[0].id, "page");
+
+        assert!(PluginMessage::parse("").is_none());
+        assert!(PluginMessage::parse("debug: starting").is_none());
+        assert!(PluginMessage::parse(r#"{"type":"log","text":"hi"}"#).is_none());
+    }
+}
@@ -0,0 +1,214 @@
+//! When scheduled scripts are due: standard five-field cron expressions
+//! (`minute hour day-of-month month day-of-week`, with `*`, lists, ranges and
+//! steps, plus `@hourly`-style shorthands) or a fixed interval.
+
+use crate::domain::config::ScriptSchedule;
+
+const MINUTE_MS: u64 = 60_000;
+
+/// Wall-clock time in the user's timezone, the way cron sees it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalTime {
+    pub minute: u32,
+    pub hour: u32,
+    /// 1-31
+    pub day: u32,
+    /// 1-12
+    pub month: u32,
+    /// 0 is Sunday
+    pub weekday: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    // Cron matches either day field when both are restricted
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+fn parse_number(value: &str, field: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' in cron field '{}'", value, field))
+}
+
+/// Bitmask of the values `field` selects within `min..=max`.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, parse_number(step, field)?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(format!("Step of zero in cron field '{}'", field));
+        }
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_number(start, field)?, parse_number(end, field)?)
+        } else {
+            // `5/15` means every 15 starting at 5
+            let start = parse_number(range, field)?;
+            (start, if step > 1 { max } else { start })
+        };
+        if start < min || end > max || start > end {
+            return Err(format!(
+                "'{}' is out of range in cron field '{}'",
+                part, field
+            ));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            expr => expr,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Cron expression '{}' needs 5 fields, got {}",
+                expr,
+                fields.len()
+            ));
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        // Both 0 and 7 are Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+
+    pub fn matches(&self, time: &LocalTime) -> bool {
+        let has = |mask: u64, value: u32| mask & (1 << value) != 0;
+        let day = has(self.days, time.day);
+        let weekday = has(self.weekdays, time.weekday);
+        let day_matches = if self.days_restricted && self.weekdays_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        };
+        has(self.minutes, time.minute)
+            && has(self.hours, time.hour)
+            && has(self.months, time.month)
+            && day_matches
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    Cron(CronExpr),
+    IntervalMs(u64),
+}
+
+impl Schedule {
+    pub fn from_config(schedule: &ScriptSchedule) -> Result<Self, String> {
+        match schedule {
+            ScriptSchedule::Cron(expr) => CronExpr::parse(expr).map(Schedule::Cron),
+            ScriptSchedule::IntervalMinutes(0) => Err("Interval must be at least a minute".into()),
+            ScriptSchedule::IntervalMinutes(minutes) => {
+                Ok(Schedule::IntervalMs(minutes * MINUTE_MS))
+            }
+        }
+    }
+
+    /// Whether a run is due at `now` (`local` being the same instant), given when
+    /// the script last ran or, if it hasn't yet, when scheduling began.
+    pub fn is_due(&self, last: u64, now: u64, local: &LocalTime) -> bool {
+        match self {
+            Schedule::IntervalMs(interval) => now.saturating_sub(last) >= *interval,
+            // At most once per matching minute
+            Schedule::Cron(expr) => expr.matches(local) && now / MINUTE_MS != last / MINUTE_MS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32, day: u32, month: u32, weekday: u32) -> LocalTime {
+        LocalTime {
+            minute,
+            hour,
+            day,
+            month,
+            weekday,
+        }
+    }
+
+    #[test]
+    fn test_cron_fields() {
+        let weekday_mornings = CronExpr::parse("30 8 * * 1-5").unwrap();
+        assert!(weekday_mornings.matches(&at(8, 30, 14, 10, 2)));
+        assert!(!weekday_mornings.matches(&at(8, 30, 18, 10, 6)));
+        assert!(!weekday_mornings.matches(&at(8, 31, 14, 10, 2)));
+
+        let every_quarter = CronExpr::parse("*/15 9-17/4 * 1,6 *").unwrap();
+        assert!(every_quarter.matches(&at(13, 45, 3, 6, 0)));
+        assert!(!every_quarter.matches(&at(11, 45, 3, 6, 0)));
+        assert!(!every_quarter.matches(&at(13, 45, 3, 7, 0)));
+
+        // Either day field matches when both are given; 7 is Sunday too
+        let first_or_sunday = CronExpr::parse("0 0 1 * 7").unwrap();
+        assert!(first_or_sunday.matches(&at(0, 0, 1, 3, 3)));
+        assert!(first_or_sunday.matches(&at(0, 0, 9, 3, 0)));
+        assert!(!first_or_sunday.matches(&at(0, 0, 9, 3, 1)));
+
+        assert_eq!(
+            CronExpr::parse("@daily").unwrap(),
+            CronExpr::parse("0 0 * * *").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_cron() {
+        assert!(CronExpr::parse("* * * *").is_err());
+        assert!(CronExpr::parse("60 * * * *").is_err());
+        assert!(CronExpr::parse("*/0 * * * *").is_err());
+        assert!(CronExpr::parse("5-1 * * * *").is_err());
+        assert!(CronExpr::parse("a * * * *").is_err());
+        assert!(Schedule::from_config(&ScriptSchedule::IntervalMinutes(0)).is_err());
+    }
+
+    #[test]
+    fn test_due() {
+        let noon = at(12, 0, 1, 1, 1);
+        let hourly = Schedule::from_config(&ScriptSchedule::IntervalMinutes(60)).unwrap();
+        assert!(!hourly.is_due(0, 59 * MINUTE_MS, &noon));
+        assert!(hourly.is_due(0, 60 * MINUTE_MS, &noon));
+
+        let cron = Schedule::from_config(&ScriptSchedule::Cron("0 12 * * *".into())).unwrap();
+        let now = 1000 * MINUTE_MS + 20_000;
+        assert!(cron.is_due(now - MINUTE_MS, now, &noon));
+        // Already ran earlier in this minute
+        assert!(!cron.is_due(now - 10_000, now, &noon));
+        assert!(!cron.is_due(0, now, &at(12, 1, 1, 1, 1)));
+    }
+}
@@ -0,0 +1,123 @@
+//! Script filters: configured scripts that turn a query into a list of items.
+//!
+//! The script gets the query as its last argument and prints JSON, either a
+//! bare array of items or `{"items": [...]}` like Alfred:
+//!
+//! ```text
+//! [{"title": "PR #12", "subtitle": "Fix login", "action": {"type": "open", "target": "https://…"}},
+//!  {"title": "Copy token", "action": {"type": "copy", "text": "abc"}},
+//!  {"title": "Deploy", "icon": "🚀", "action": {"type": "run", "command": "deploy --prod"}}]
+//! ```
+
+use crate::domain::config::ScriptConfig;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ScriptFilterItem {
+    pub title: String,
+    #[serde(default)]
+    pub subtitle: String,
+    #[serde(default)]
+    pub icon: Option<String>,
+    pub action: ItemAction,
+}
+
+/// What choosing an item does.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ItemAction {
+    /// A URL or path, handed to the default handler.
+    Open {
+        target: String,
+    },
+    Copy {
+        text: String,
+    },
+    /// A command line, run detached.
+    Run {
+        command: String,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Output {
+    Items(Vec<ScriptFilterItem>),
+    Wrapped { items: Vec<ScriptFilterItem> },
+}
+
+/// Parses what a script filter printed.
+pub fn parse_items(stdout: &str) -> Result<Vec<ScriptFilterItem>, String> {
+    match serde_json::from_str(stdout.trim()) {
+        Ok(Output::Items(items)) | Ok(Output::Wrapped { items }) => Ok(items),
+        Err(e) => Err(format!("Invalid script filter output: {}", e)),
+    }
+}
+
+/// The filter script `query` is addressed to, and the part of the query meant
+/// for it: `gh login bug` goes to the `gh` filter as `login bug`.
+pub fn match_filter<'a>(
+    scripts: &'a [ScriptConfig],
+    query: &'a str,
+) -> Option<(&'a ScriptConfig, &'a str)> {
+    scripts.iter().filter(|s| s.filter).find_map(|script| {
+        let alias = script.alias.trim();
+        let rest = query.strip_prefix(alias)?;
+        if alias.is_empty() || !(rest.is_empty() || rest.starts_with(' ')) {
+            return None;
+        }
+        Some((script, rest.trim()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(alias: &str, filter: bool) -> ScriptConfig {
+        ScriptConfig {
+            alias: alias.to_string(),
+            path: format!("/scripts/{}.sh", alias),
+            filter,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_bare_and_wrapped_lists() {
+        let bare = r#"[{"title": "PR #12", "action": {"type": "open", "target": "https://example.com/12"}}]"#;
+        let items = parse_items(bare).unwrap();
+        assert_eq!(items[0].subtitle, "");
+        assert_eq!(
+            items[0].action,
+            ItemAction::Open {
+                target: "https://example.com/12".to_string()
+            }
+        );
+
+        let wrapped = r#"{"items": [{"title": "Deploy", "icon": "🚀", "action": {"type": "run", "command": "deploy --prod"}}]}"#;
+        let items = parse_items(wrapped).unwrap();
+        assert_eq!(items[0].icon.as_deref(), Some("🚀"));
+
+        assert!(parse_items("[]").unwrap().is_empty());
+        assert!(parse_items("oops").is_err());
+        assert!(parse_items(r#"[{"title": "no action"}]"#).is_err());
+    }
+
+    #[test]
+    fn test_match_filter_by_alias() {
+        let scripts = vec![
+            script("gh", true),
+            script("ghost", false),
+            script("tok", true),
+        ];
+
+        let (found, rest) = match_filter(&scripts, "gh login bug").unwrap();
+        assert_eq!(found.alias, "gh");
+        assert_eq!(rest, "login bug");
+
+        assert_eq!(match_filter(&scripts, "tok").unwrap().1, "");
+        assert!(match_filter(&scripts, "ghost town").is_none());
+        assert!(match_filter(&scripts, "token").is_none());
+    }
+}
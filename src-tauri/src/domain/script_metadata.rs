@@ -0,0 +1,133 @@
+//! Raycast script command headers, e.g.
+//!
+//! ```text
+//! #!/bin/bash
+//! # @raycast.schemaVersion 1
+//! # @raycast.title Open Ticket
+//! # @raycast.mode silent
+//! # @raycast.icon 🎫
+//! # @raycast.argument1 { "type": "text", "placeholder": "Ticket ID" }
+//! ```
+//!
+//! Headers can use any of the usual line comment markers, so Python, Node and
+//! AppleScript-style scripts work too. Unknown keys are ignored.
+
+use crate::domain::config::{ScriptArgument, ScriptMode};
+
+/// Raycast allows up to three arguments.
+const MAX_ARGUMENTS: usize = 3;
+/// Headers live at the top of the file; nothing past this many lines is read.
+const HEADER_LINES: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ScriptMetadata {
+    pub title: String,
+    pub icon: Option<String>,
+    pub mode: Option<ScriptMode>,
+    pub arguments: Vec<ScriptArgument>,
+    /// `@raycast.currentDirectoryPath`
+    pub cwd: Option<String>,
+}
+
+/// `key` and `value` of a `@raycast.key value` comment line.
+fn header(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start();
+    let comment = ["#", "//", "--"]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))?;
+    let rest = comment.trim_start().strip_prefix("@raycast.")?;
+    let (key, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    Some((key, value.trim()))
+}
+
+/// The script's metadata, or `None` if it has no `@raycast.title`.
+pub fn parse_metadata(content: &str) -> Option<ScriptMetadata> {
+    let mut meta = ScriptMetadata::default();
+    let mut arguments: Vec<(usize, ScriptArgument)> = Vec::new();
+
+    for (key, value) in content.lines().take(HEADER_LINES).filter_map(header) {
+        match key {
+            "title" => meta.title = value.to_string(),
+            "icon" if !value.is_empty() => meta.icon = Some(value.to_string()),
+            "mode" => meta.mode = serde_json::from_value(value.into()).ok(),
+            "currentDirectoryPath" if !value.is_empty() => meta.cwd = Some(value.to_string()),
+            _ => {
+                let Some(n) = key.strip_prefix("argument").and_then(|n| n.parse().ok()) else {
+                    continue;
+                };
+                if (1..=MAX_ARGUMENTS).contains(&n) {
+                    if let Ok(argument) = serde_json::from_str(value) {
+                        arguments.push((n, argument));
+                    }
+                }
+            }
+        }
+    }
+
+    if meta.title.is_empty() {
+        return None;
+    }
+    arguments.sort_by_key(|(n, _)| *n);
+    meta.arguments = arguments.into_iter().map(|(_, a)| a).collect();
+    Some(meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::config::ArgumentKind;
+
+    #[test]
+    fn test_parse_raycast_headers() {
+        let script = r#"#!/bin/bash
+
+# Required parameters:
+# @raycast.schemaVersion 1
+# @raycast.title Open Ticket
+# @raycast.mode silent
+
+# Optional parameters:
+# @raycast.icon 🎫
+# @raycast.currentDirectoryPath ~/work
+# @raycast.argument2 { "type": "dropdown", "placeholder": "Env", "data": [{"title": "Prod", "value": "prod"}] }
+# @raycast.argument1 { "type": "text", "placeholder": "Ticket ID", "percentEncoded": true }
+# @raycast.argument4 { "type": "text", "placeholder": "Too many" }
+
+open "https://jira.example.com/browse/$1"
+"#;
+        let meta = parse_metadata(script).unwrap();
+        assert_eq!(meta.title, "Open Ticket");
+        assert_eq!(meta.icon.as_deref(), Some("🎫"));
+        assert_eq!(meta.mode, Some(ScriptMode::Silent));
+        assert_eq!(meta.cwd.as_deref(), Some("~/work"));
+
+        assert_eq!(meta.arguments.len(), 2);
+        assert_eq!(meta.arguments[0].placeholder, "Ticket ID");
+        assert!(meta.arguments[0].percent_encoded);
+        assert!(!meta.arguments[0].optional);
+        assert_eq!(meta.arguments[1].kind, ArgumentKind::Dropdown);
+        assert_eq!(meta.arguments[1].data[0].value, "prod");
+    }
+
+    #[test]
+    fn test_other_comment_styles_and_bad_values() {
+        let script =
+            "// @raycast.title Deploy\n// @raycast.mode fancy\n// @raycast.argument1 {oops}\n";
+        let meta = parse_metadata(script).unwrap();
+        assert_eq!(meta.title, "Deploy");
+        assert_eq!(meta.mode, None);
+        assert!(meta.arguments.is_empty());
+
+        let python = "#!/usr/bin/env python3\n# @raycast.title Clean Downloads\n# @raycast.mode fullOutput\n";
+        assert_eq!(
+            parse_metadata(python).unwrap().mode,
+            Some(ScriptMode::FullOutput)
+        );
+    }
+
+    #[test]
+    fn test_scripts_without_title_are_skipped() {
+        assert!(parse_metadata("#!/bin/sh\necho hi\n").is_none());
+        assert!(parse_metadata("# @raycast.mode compact\n").is_none());
+    }
+}
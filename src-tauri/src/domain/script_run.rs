@@ -0,0 +1,428 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Everything needed to start one script execution.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ScriptRequest {
+    /// Id of the configured or discovered script, if the path belongs to one.
+    pub script_id: Option<String>,
+    pub path: String,
+    pub args: Vec<String>,
+    /// Overrides the shebang, e.g. `python3 -u`.
+    pub interpreter: Option<String>,
+    pub cwd: Option<String>,
+    /// Added to the launcher's own environment.
+    pub env: HashMap<String, String>,
+    /// The run is killed once it has taken this long.
+    pub timeout: Option<Duration>,
+    /// Run attached to a pseudo-terminal of this size instead of pipes, so tools keep
+    /// their colors and progress bars. Output is forwarded raw, escape sequences included.
+    pub pty: Option<PtySize>,
+    /// Nobody is there to type: scheduled and background runs get `/dev/null` as
+    /// stdin and never a terminal, so a prompt reads EOF instead of waiting forever.
+    pub unattended: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PtySize {
+    pub rows: u16,
+    pub cols: u16,
+}
+
+/// Interpreter named by a `#!` line. Like the kernel, everything after the program
+/// is passed as one argument, so `#!/usr/bin/env -S node --no-warnings` hands
+/// `-S node --no-warnings` to `env`, which does its own splitting.
+pub fn parse_shebang(line: &str) -> Option<Vec<String>> {
+    let rest = line.strip_prefix("#!")?.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    Some(match rest.split_once(char::is_whitespace) {
+        Some((program, arg)) => vec![program.to_string(), arg.trim_start().to_string()],
+        None => vec![rest.to_string()],
+    })
+}
+
+/// Program and arguments that run the script at `path`: the configured interpreter,
+/// else the one from its shebang, else `sh` for `.sh` files, else the file itself.
+pub fn resolve_command(
+    path: &str,
+    interpreter: Option<&str>,
+    first_line: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let mut command = match interpreter.filter(|interpreter| !interpreter.trim().is_empty()) {
+        Some(interpreter) => shell_words::split(interpreter).map_err(|e| e.to_string())?,
+        None => match first_line.and_then(parse_shebang) {
+            Some(shebang) => shebang,
+            None if path.ends_with(".sh") => vec!["sh".to_string()],
+            None => Vec::new(),
+        },
+    };
+    command.push(path.to_string());
+    Ok(command)
+}
+
+impl Default for PtySize {
+    fn default() -> Self {
+        Self { rows: 24, cols: 80 }
+    }
+}
+
+/// A script execution that hasn't finished yet.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RunningScript {
+    pub run_id: String,
+    pub path: String,
+    pub pid: u32,
+    pub started_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Success,
+    Failed,
+    Killed,
+    TimedOut,
+}
+
+impl RunStatus {
+    pub fn from_exit(code: Option<i32>) -> Self {
+        if code == Some(0) {
+            RunStatus::Success
+        } else {
+            RunStatus::Failed
+        }
+    }
+
+    /// Last line shown in the terminal view.
+    pub fn message(&self, code: Option<i32>) -> String {
+        match self {
+            RunStatus::Success => "> Success! (Exit code 0)\n".to_string(),
+            RunStatus::Failed => format!("> Failed! (Exit code {})\n", code.unwrap_or(-1)),
+            RunStatus::Killed => "> Killed\n".to_string(),
+            RunStatus::TimedOut => "> Timed out\n".to_string(),
+        }
+    }
+}
+
+/// What a run reports while it executes; every event carries its run id so the
+/// frontend can tell concurrent runs apart.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScriptEvent {
+    /// `command` is what was actually executed, interpreter included.
+    Start {
+        run_id: String,
+        command: Vec<String>,
+        cwd: Option<String>,
+    },
+    Output {
+        run_id: String,
+        text: String,
+    },
+    Done {
+        run_id: String,
+        code: Option<i32>,
+        status: RunStatus,
+    },
+}
+
+impl ScriptEvent {
+    pub fn run_id(&self) -> &str {
+        match self {
+            ScriptEvent::Start { run_id, .. }
+            | ScriptEvent::Output { run_id, .. }
+            | ScriptEvent::Done { run_id, .. } => run_id,
+        }
+    }
+}
+
+/// Output kept per logged run; a chatty script keeps its last lines.
+pub const MAX_LOGGED_OUTPUT: usize = 64 * 1024;
+
+/// A finished run as kept in the run log.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    pub run_id: String,
+    pub script_id: Option<String>,
+    pub path: String,
+    pub args: Vec<String>,
+    pub started_at: u64,
+    pub ended_at: u64,
+    pub code: Option<i32>,
+    pub status: RunStatus,
+    /// Left out of run listings.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub output: String,
+}
+
+/// Raw PTY output waiting for its newline is dropped past this.
+const MAX_PENDING_LINE: usize = 64 * 1024;
+
+/// Terminal text without escape sequences or control characters other than `\r`.
+pub fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                // CSI: parameters up to a final byte in @..~
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC: up to BEL or ST
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\r' => plain.push(c),
+            c if c.is_control() => {}
+            c => plain.push(c),
+        }
+    }
+    plain
+}
+
+/// The last non-empty line a run printed to stdout, which output actions act on.
+/// Piped runs send one `> `-prefixed line per event (stderr is `ERR> `); PTY runs
+/// send raw terminal chunks, where `\r` redraws the line.
+#[derive(Debug, Default)]
+pub struct LastLine {
+    pending: String,
+    last: Option<String>,
+}
+
+impl LastLine {
+    pub fn observe(&mut self, text: &str, pty: bool) {
+        if !pty {
+            if let Some(line) = text.strip_prefix("> ") {
+                self.keep(line);
+            }
+            return;
+        }
+        self.pending.push_str(text);
+        while let Some(end) = self.pending.find('\n') {
+            let line: String = self.pending.drain(..=end).collect();
+            self.keep(&line);
+        }
+        if self.pending.len() > MAX_PENDING_LINE {
+            self.pending.clear();
+        }
+    }
+
+    fn keep(&mut self, line: &str) {
+        if let Some(line) = Self::visible(line) {
+            self.last = Some(line);
+        }
+    }
+
+    fn visible(line: &str) -> Option<String> {
+        let plain = strip_ansi(line);
+        let line = plain
+            .trim_end()
+            .rsplit('\r')
+            .next()
+            .unwrap_or_default()
+            .trim();
+        (!line.is_empty()).then(|| line.to_string())
+    }
+
+    pub fn get(&self) -> Option<String> {
+        Self::visible(&self.pending).or_else(|| self.last.clone())
+    }
+}
+
+/// Collects a run's events into the record saved once it is done.
+pub struct RunCapture {
+    script_id: Option<String>,
+    path: String,
+    args: Vec<String>,
+    started_at: u64,
+    output: String,
+}
+
+impl RunCapture {
+    pub fn new(request: &ScriptRequest, started_at: u64) -> Self {
+        Self {
+            script_id: request.script_id.clone(),
+            path: request.path.clone(),
+            args: request.args.clone(),
+            started_at,
+            output: String::new(),
+        }
+    }
+
+    /// Returns the finished record on `Done`.
+    pub fn observe(&mut self, event: &ScriptEvent, now: u64) -> Option<RunRecord> {
+        match event {
+            ScriptEvent::Start { .. } => None,
+            ScriptEvent::Output { text, .. } => {
+                self.output.push_str(text);
+                if self.output.len() > 2 * MAX_LOGGED_OUTPUT {
+                    self.trim();
+                }
+                None
+            }
+            ScriptEvent::Done {
+                run_id,
+                code,
+                status,
+            } => {
+                self.trim();
+                Some(RunRecord {
+                    run_id: run_id.clone(),
+                    script_id: self.script_id.clone(),
+                    path: self.path.clone(),
+                    args: self.args.clone(),
+                    started_at: self.started_at,
+                    ended_at: now,
+                    code: *code,
+                    status: *status,
+                    output: std::mem::take(&mut self.output),
+                })
+            }
+        }
+    }
+
+    fn trim(&mut self) {
+        if self.output.len() <= MAX_LOGGED_OUTPUT {
+            return;
+        }
+        let mut start = self.output.len() - MAX_LOGGED_OUTPUT;
+        while !self.output.is_char_boundary(start) {
+            start += 1;
+        }
+        self.output.drain(..start);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_from_exit_code() {
+        assert_eq!(RunStatus::from_exit(Some(0)), RunStatus::Success);
+        assert_eq!(RunStatus::from_exit(Some(2)), RunStatus::Failed);
+        // Terminated by a signal
+        assert_eq!(RunStatus::from_exit(None), RunStatus::Failed);
+        assert_eq!(
+            RunStatus::Failed.message(Some(2)),
+            "> Failed! (Exit code 2)\n"
+        );
+    }
+
+    #[test]
+    fn test_resolve_command() {
+        // Override beats shebang
+        assert_eq!(
+            resolve_command("/s/a.py", Some("python3 -u"), Some("#!/usr/bin/python2")).unwrap(),
+            vec!["python3", "-u", "/s/a.py"]
+        );
+        assert_eq!(
+            resolve_command("/s/a.js", None, Some("#!/usr/bin/env node")).unwrap(),
+            vec!["/usr/bin/env", "node", "/s/a.js"]
+        );
+        assert_eq!(
+            resolve_command("/s/a.sh", Some("  "), Some("echo no shebang")).unwrap(),
+            vec!["sh", "/s/a.sh"]
+        );
+        assert_eq!(
+            resolve_command("/s/tool", None, None).unwrap(),
+            vec!["/s/tool"]
+        );
+        assert_eq!(parse_shebang("#!"), None);
+    }
+
+    #[test]
+    fn test_shebang_arguments_stay_one_argument() {
+        assert_eq!(
+            parse_shebang("#!/usr/bin/env -S node --no-warnings").unwrap(),
+            vec!["/usr/bin/env", "-S node --no-warnings"]
+        );
+        assert_eq!(
+            parse_shebang("#! /usr/bin/awk \t -f \n").unwrap(),
+            vec!["/usr/bin/awk", "-f"]
+        );
+        assert_eq!(parse_shebang("#!/bin/sh").unwrap(), vec!["/bin/sh"]);
+    }
+
+    #[test]
+    fn test_capture_builds_record_with_output_tail() {
+        let request = ScriptRequest {
+            script_id: Some("deploy".to_string()),
+            path: "/s/deploy.sh".to_string(),
+            args: vec!["prod".to_string()],
+            ..Default::default()
+        };
+        let mut capture = RunCapture::new(&request, 100);
+        let output = |text: &str| ScriptEvent::Output {
+            run_id: "run-1".to_string(),
+            text: text.to_string(),
+        };
+
+        assert_eq!(capture.observe(&output("first\n"), 110), None);
+        assert_eq!(
+            capture.observe(&output(&"é".repeat(MAX_LOGGED_OUTPUT)), 120),
+            None
+        );
+        let record = capture
+            .observe(
+                &ScriptEvent::Done {
+                    run_id: "run-1".to_string(),
+                    code: Some(0),
+                    status: RunStatus::Success,
+                },
+                150,
+            )
+            .unwrap();
+
+        assert_eq!(record.script_id.as_deref(), Some("deploy"));
+        assert_eq!(record.args, vec!["prod"]);
+        assert_eq!((record.started_at, record.ended_at), (100, 150));
+        assert!(record.output.len() <= MAX_LOGGED_OUTPUT);
+        assert!(!record.output.contains("first"));
+        assert!(record.output.ends_with('é'));
+    }
+
+    #[test]
+    fn test_last_line() {
+        let mut piped = LastLine::default();
+        for text in ["> https://example.com/a\n", "ERR> warning\n", ">   \n"] {
+            piped.observe(text, false);
+        }
+        assert_eq!(piped.get().as_deref(), Some("https://example.com/a"));
+
+        let mut pty = LastLine::default();
+        pty.observe("\x1b[32mfirst\x1b[0m\r\nloading 10%\rloa", true);
+        pty.observe("ding 100%\r\n\x1b]0;title\x07\x1b[1mresult\x1b[0m", true);
+        assert_eq!(pty.get().as_deref(), Some("result"));
+        pty.observe("\r\n\r\n", true);
+        assert_eq!(pty.get().as_deref(), Some("result"));
+        assert_eq!(LastLine::default().get(), None);
+    }
+
+    #[test]
+    fn test_events_serialize_with_run_id() {
+        let event = ScriptEvent::Done {
+            run_id: "run-1".to_string(),
+            code: None,
+            status: RunStatus::TimedOut,
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "done");
+        assert_eq!(json["run_id"], "run-1");
+        assert_eq!(json["status"], "timed_out");
+    }
+}
@@ -0,0 +1,180 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultKind {
+    App,
+    Window,
+    Script,
+    File,
+    Folder,
+    Plugin,
+    /// An item listed by a script filter.
+    #[serde(rename = "script_item")]
+    ScriptItem,
+}
+
+/// Something that can be done with a result; the first one is the default.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ResultAction {
+    /// Stable identifier the frontend dispatches on, e.g. `launch` or `reveal`.
+    /// Plugin results carry the plugin's own action ids.
+    pub id: String,
+    pub title: String,
+}
+
+pub fn action(id: &str, title: &str) -> ResultAction {
+    ResultAction {
+        id: id.to_string(),
+        title: title.to_string(),
+    }
+}
+
+/// Actions offered for each kind of result, default first.
+pub fn actions_for(kind: ResultKind) -> Vec<ResultAction> {
+    match kind {
+        ResultKind::App => vec![action("launch", "Launch")],
+        ResultKind::Window => vec![action("focus", "Switch to Window")],
+        ResultKind::Script => vec![action("run", "Run"), action("edit", "Edit Script")],
+        ResultKind::File => vec![
+            action("open", "Open"),
+            action("edit", "Open in Editor"),
+            action("reveal", "Show in Folder"),
+            action("copy_path", "Copy Path"),
+        ],
+        ResultKind::Folder => vec![
+            action("open", "Open"),
+            action("reveal", "Show in Folder"),
+            action("copy_path", "Copy Path"),
+        ],
+        ResultKind::Plugin => vec![action("open", "Open")],
+        // Set per item from what the script asked for
+        ResultKind::ScriptItem => Vec::new(),
+    }
+}
+
+/// One entry of the unified search, whatever it came from.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    /// Same id scheme as recorded actions, e.g. `app:firefox` or `file:/home/me/notes.md`.
+    pub id: String,
+    pub kind: ResultKind,
+    pub title: String,
+    pub subtitle: String,
+    pub icon: Option<String>,
+    /// What executing the result needs: exec line, window address, script path or file path.
+    pub target: String,
+    pub score: i32,
+    /// Char indices into `title` that matched the query.
+    pub positions: Vec<usize>,
+    pub actions: Vec<ResultAction>,
+    /// Plugin that produced the result, which its actions are routed back to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<String>,
+}
+
+impl SearchResult {
+    /// A result of `kind` carrying that kind's actions.
+    pub fn new(kind: ResultKind, id: String, title: &str, subtitle: &str, target: &str) -> Self {
+        Self {
+            id,
+            kind,
+            title: title.to_string(),
+            subtitle: subtitle.to_string(),
+            icon: None,
+            target: target.to_string(),
+            score: 0,
+            positions: Vec::new(),
+            actions: actions_for(kind),
+            plugin: None,
+        }
+    }
+}
+
+/// Keeps one result per kind and target (several providers can surface the same
+/// app or file), preferring the best scored and filling in a missing icon.
+pub fn dedupe_results(results: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut seen: HashMap<(ResultKind, String), usize> = HashMap::new();
+    let mut unique: Vec<SearchResult> = Vec::with_capacity(results.len());
+    for result in results {
+        match seen.get(&(result.kind, result.target.clone())) {
+            Some(&i) => {
+                let kept = &mut unique[i];
+                let icon = kept.icon.take().or_else(|| result.icon.clone());
+                if result.score > kept.score {
+                    *kept = result;
+                }
+                if kept.icon.is_none() {
+                    kept.icon = icon;
+                }
+            }
+            None => {
+                seen.insert((result.kind, result.target.clone()), unique.len());
+                unique.push(result);
+            }
+        }
+    }
+    unique
+}
+
+/// Highest score first; ties go to the shorter title, then alphabetical order.
+pub fn rank_results(results: &mut Vec<SearchResult>, limit: usize) {
+    results.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.title.len().cmp(&b.title.len()))
+            .then_with(|| a.title.cmp(&b.title))
+    });
+    results.truncate(limit);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(title: &str, score: i32) -> SearchResult {
+        SearchResult {
+            score,
+            ..SearchResult::new(ResultKind::App, format!("app:{}", title), title, "", title)
+        }
+    }
+
+    #[test]
+    fn test_rank_results() {
+        let mut results = vec![
+            result("Files", 50),
+            result("Firefox Nightly", 80),
+            result("Firefox", 80),
+        ];
+        rank_results(&mut results, 2);
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].title, "Firefox");
+        assert_eq!(results[1].title, "Firefox Nightly");
+    }
+
+    #[test]
+    fn test_dedupe_keeps_best_score_and_icon() {
+        let mut with_icon = result("Firefox", 10);
+        with_icon.icon = Some("firefox.png".to_string());
+        let mut as_window = result("Firefox", 5);
+        as_window.kind = ResultKind::Window;
+
+        let unique = dedupe_results(vec![with_icon, result("Firefox", 30), as_window]);
+
+        assert_eq!(unique.len(), 2);
+        assert_eq!(unique[0].score, 30);
+        assert_eq!(unique[0].icon.as_deref(), Some("firefox.png"));
+        assert_eq!(unique[1].kind, ResultKind::Window);
+    }
+
+    #[test]
+    fn test_actions_follow_kind() {
+        assert_eq!(actions_for(ResultKind::App)[0].id, "launch");
+        assert_eq!(actions_for(ResultKind::Window)[0].id, "focus");
+        assert!(!actions_for(ResultKind::Folder)
+            .iter()
+            .any(|a| a.id == "edit"));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::domain::files::FileMatch;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Payload of the `search-results` event.
+///
+/// Intermediate batches carry newly found matches; the final event of a search
+/// has `done` set and carries the complete, ranked result list.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SearchBatch {
+    pub search_id: u64,
+    pub results: Vec<FileMatch>,
+    pub done: bool,
+}
+
+/// Tracks the one search that is currently allowed to run.
+///
+/// Starting a new search cancels the previous one, so stale queries stop as
+/// soon as the user types the next character.
+#[derive(Default)]
+pub struct SearchSessions {
+    next_id: AtomicU64,
+    current: Mutex<Option<(u64, Arc<AtomicBool>)>>,
+}
+
+impl SearchSessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a new search and returns its id and cancellation flag.
+    pub fn begin(&self) -> (u64, Arc<AtomicBool>) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let cancel = Arc::new(AtomicBool::new(false));
+        let previous = self.current.lock().unwrap().replace((id, cancel.clone()));
+        if let Some((_, flag)) = previous {
+            flag.store(true, Ordering::SeqCst);
+        }
+        (id, cancel)
+    }
+
+    /// Cancels the running search if it matches `id` (or whatever runs, if `None`).
+    /// Returns whether something was cancelled.
+    pub fn cancel(&self, id: Option<u64>) -> bool {
+        let mut current = self.current.lock().unwrap();
+        match current.as_ref() {
+            Some((running, flag)) if id.is_none_or(|id| id == *running) => {
+                flag.store(true, Ordering::SeqCst);
+                *current = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Forgets a search that ran to completion.
+    pub fn finish(&self, id: u64) {
+        let mut current = self.current.lock().unwrap();
+        if current.as_ref().is_some_and(|(running, _)| *running == id) {
+            *current = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_begin_cancels_previous_search() {
+        let sessions = SearchSessions::new();
+        let (first, first_cancel) = sessions.begin();
+        let (second, second_cancel) = sessions.begin();
+
+        assert!(second > first);
+        assert!(first_cancel.load(Ordering::SeqCst));
+        assert!(!second_cancel.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_cancel_only_hits_matching_search() {
+        let sessions = SearchSessions::new();
+        let (id, cancel) = sessions.begin();
+
+        assert!(!sessions.cancel(Some(id + 1)));
+        assert!(!cancel.load(Ordering::SeqCst));
+
+        assert!(sessions.cancel(Some(id)));
+        assert!(cancel.load(Ordering::SeqCst));
+        assert!(!sessions.cancel(None));
+    }
+
+    #[test]
+    fn test_finished_search_cannot_be_cancelled() {
+        let sessions = SearchSessions::new();
+        let (id, cancel) = sessions.begin();
+        sessions.finish(id);
+
+        assert!(!sessions.cancel(None));
+        assert!(!cancel.load(Ordering::SeqCst));
+    }
+}
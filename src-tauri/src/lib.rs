@@ -2,17 +2,35 @@ pub mod adapters;
 pub mod commands;
 pub mod domain;
 pub mod ports;
+pub mod scheduler;
 pub mod state;
 pub mod tray;
 pub mod utils;
 
 use adapters::cached_icon_resolver::CachedIconResolver;
 use adapters::file_history::FileHistoryAdapter;
+use adapters::file_run_log::FileRunLog;
 use adapters::fs_app_repository::FsAppRepository;
 use adapters::fs_config_service::FsConfigService;
+use adapters::fs_file_index::FsFileIndex;
 use adapters::google_translation_service::GoogleTranslationService;
 use adapters::http_ai_service::HttpAiService;
 use adapters::linux_window_service::LinuxWindowService;
+use adapters::process_script_runner::ProcessScriptRunner;
+use adapters::script_filter_provider::ScriptFilterSearchProvider;
+use adapters::search_providers::{
+    AppSearchProvider, FileSearchProvider, PluginSearchProvider, RecentSearchProvider,
+    ScriptSearchProvider, WindowSearchProvider,
+};
+use adapters::stdio_plugin_host::StdioPluginHost;
+use adapters::xdg_mime_resolver::XdgMimeResolver;
+use domain::frecency::HistorySnapshot;
+use domain::search_session::SearchSessions;
+use ports::config_port::ConfigService;
+use ports::file_index_port::FileIndex;
+use ports::plugin_port::PluginService;
+use ports::search_provider_port::SearchProvider;
+use scheduler::ScriptScheduler;
 use state::AppState;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -33,6 +51,7 @@ pub fn run() {
             let icon_resolver = Arc::new(CachedIconResolver::new());
             // FsAppRepository needs icon resolver
             let app_repository = Arc::new(FsAppRepository::new(icon_resolver.clone()));
+            let mime_resolver = Arc::new(XdgMimeResolver::new(app_repository.clone()));
             let command_executor = Arc::new(adapters::linux_window_service::StdCommandExecutor);
             let window_service = Arc::new(LinuxWindowService::new(command_executor));
             let ai_service = Arc::new(HttpAiService::new());
@@ -41,8 +60,44 @@ pub fn run() {
                 .path()
                 .app_data_dir()
                 .unwrap_or_else(|_| PathBuf::from("."));
-            let history_repository = Arc::new(FileHistoryAdapter::new(app_data_dir));
+            let history_repository = Arc::new(FileHistoryAdapter::new(app_data_dir.clone()));
+            let run_log = Arc::new(FileRunLog::new(app_data_dir.clone()));
             let translation_service = Arc::new(GoogleTranslationService::new(None));
+            let file_index = Arc::new(FsFileIndex::new(app_data_dir));
+            file_index.start(&config_service.load_config().file_search);
+            let plugin_service = Arc::new(StdioPluginHost::new());
+            plugin_service.configure(&config_service.load_config().plugins);
+            let script_runner = Arc::new(ProcessScriptRunner::new());
+            let scheduler = Arc::new(ScriptScheduler::new(
+                config_service.clone(),
+                script_runner.clone(),
+                run_log.clone(),
+                window_service.clone(),
+            ));
+
+            // Sources of the unified `search` command
+            let search_providers: Vec<Arc<dyn SearchProvider>> = vec![
+                Arc::new(AppSearchProvider {
+                    apps: app_repository.clone(),
+                }),
+                Arc::new(WindowSearchProvider {
+                    windows: window_service.clone(),
+                    icons: icon_resolver.clone(),
+                }),
+                Arc::new(ScriptSearchProvider {
+                    config: config_service.clone(),
+                }),
+                Arc::new(ScriptFilterSearchProvider {
+                    config: config_service.clone(),
+                }),
+                Arc::new(FileSearchProvider {
+                    index: file_index.clone(),
+                }),
+                Arc::new(PluginSearchProvider {
+                    plugins: plugin_service.clone(),
+                }),
+                Arc::new(RecentSearchProvider),
+            ];
 
             // Manage State
             app.manage(AppState {
@@ -50,19 +105,33 @@ pub fn run() {
                 window_service,
                 config_service: config_service.clone(),
                 icon_resolver: icon_resolver.clone(),
+                mime_resolver,
                 ai_service,
                 history_repository,
+                history_snapshot: Arc::new(HistorySnapshot::new()),
                 translation_service,
+                file_index,
+                plugin_service,
+                script_runner,
+                run_log,
+                search_sessions: Arc::new(SearchSessions::new()),
+                search_providers,
             });
 
             // Initialize KSNI Tray Service
             let handle = app.handle().clone();
-            let tray = PaletteTray { handle };
+            let tray = PaletteTray {
+                handle,
+                scheduler: scheduler.clone(),
+            };
             let service = ksni::TrayService::new(tray);
-            let _handle = service.handle();
+            let tray_handle = service.handle();
 
             service.spawn();
 
+            // Failed scheduled runs show up in the tray menu
+            scheduler.spawn(move || tray_handle.update(|_| {}));
+
             Ok(())
         })
         .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
@@ -71,16 +140,25 @@ pub fn run() {
         }))
         .invoke_handler(tauri::generate_handler![
             commands::system::greet,
+            commands::search::search,
             commands::system::search_files,
+            commands::system::start_file_search,
+            commands::system::cancel_file_search,
+            commands::system::search_content,
+            commands::system::open_in_editor,
+            commands::system::get_file_index_status,
             commands::system::open_entity,
             commands::system::read_file_preview,
             commands::system::get_file_metadata,
             commands::system::get_selection_context,
             commands::system::copy_to_clipboard,
+            commands::system::notify_desktop,
             commands::system::check_is_executable,
             commands::system::make_file_executable,
             commands::apps::list_apps,
             commands::apps::launch_app,
+            commands::apps::launch_app_with,
+            commands::apps::get_open_with_apps,
             commands::config::get_config,
             commands::config::save_config,
             commands::ai::ask_ai,
@@ -88,6 +166,15 @@ pub fn run() {
             commands::ai::list_ollama_models,
             commands::scripts::list_scripts,
             commands::scripts::execute_script,
+            commands::scripts::kill_script,
+            commands::scripts::write_script_stdin,
+            commands::scripts::close_script_stdin,
+            commands::scripts::resize_script,
+            commands::scripts::list_running_scripts,
+            commands::scripts::list_script_runs,
+            commands::scripts::get_script_run,
+            commands::scripts::rerun_script,
+            commands::scripts::validate_schedule,
             commands::windows::list_windows,
             commands::windows::focus_window,
             commands::windows::focus_window,
@@ -96,6 +183,7 @@ pub fn run() {
             commands::history::get_recent_actions,
             commands::history::record_action,
             commands::history::clear_history,
+            commands::plugins::run_plugin_action,
             commands::translation::translate,
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,32 @@
+use crate::domain::config::FileSearchConfig;
+use crate::domain::content_search::ContentMatch;
+use crate::domain::file_query::FileQuery;
+use crate::domain::files::{FileMatch, IndexStatus};
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::Sender;
+
+#[cfg_attr(test, mockall::automock)]
+pub trait FileIndex: Send + Sync {
+    /// (Re)starts indexing for the given configuration. Returns immediately;
+    /// crawling happens in the background. No-op if the configuration did not change.
+    fn start(&self, config: &FileSearchConfig);
+    fn search(&self, query: &FileQuery, limit: usize) -> Vec<FileMatch>;
+    /// Like `search`, but sends partial matches to `batches` as they are found and
+    /// checks `cancel` between batches. Returns the final ranking, or `None` if cancelled.
+    fn search_batched(
+        &self,
+        query: &FileQuery,
+        limit: usize,
+        cancel: &AtomicBool,
+        batches: &Sender<Vec<FileMatch>>,
+    ) -> Option<Vec<FileMatch>>;
+    /// Searches the text of indexed files passing the query's filters for `query.text`.
+    /// Binary files are skipped. Returns `None` if cancelled.
+    fn search_content(
+        &self,
+        query: &FileQuery,
+        limit: usize,
+        cancel: &AtomicBool,
+    ) -> Option<Vec<ContentMatch>>;
+    fn status(&self) -> IndexStatus;
+}
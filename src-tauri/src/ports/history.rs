@@ -4,6 +4,8 @@ use async_trait::async_trait;
 #[async_trait]
 pub trait HistoryRepository: Send + Sync {
     async fn get_recent(&self, limit: usize) -> Result<Vec<Action>, String>;
-    async fn record(&self, action: Action) -> Result<(), String>;
+    /// Records a use of `action`; `query` is what was typed to find it, if anything,
+    /// and gets associated with the action for adaptive ranking.
+    async fn record(&self, action: Action, query: Option<String>) -> Result<(), String>;
     async fn clear(&self) -> Result<(), String>;
 }
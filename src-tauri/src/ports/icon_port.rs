@@ -1,3 +1,4 @@
+#[cfg_attr(test, mockall::automock)]
 pub trait IconResolver: Send + Sync {
     fn resolve_icon(&self, icon_name: &str) -> Option<String>;
 }
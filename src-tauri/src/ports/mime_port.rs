@@ -0,0 +1,9 @@
+use crate::domain::mime::OpenWith;
+
+#[cfg_attr(test, mockall::automock)]
+pub trait MimeResolver: Send + Sync {
+    /// The file's MIME type, from its name or, failing that, its contents.
+    fn mime_type(&self, path: &str) -> Option<String>;
+    /// The default app and every other app that can open the file.
+    fn open_with(&self, path: &str) -> Result<OpenWith, String>;
+}
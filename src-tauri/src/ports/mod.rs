@@ -1,7 +1,13 @@
 pub mod ai_port;
 pub mod app_port;
 pub mod config_port;
+pub mod file_index_port;
 pub mod icon_port;
+pub mod mime_port;
+pub mod plugin_port;
+pub mod run_log_port;
+pub mod script_runner_port;
+pub mod search_provider_port;
 pub mod window_port;
 // pub mod ai_port; // To be added
 pub mod history;
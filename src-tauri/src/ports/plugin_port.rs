@@ -0,0 +1,16 @@
+use crate::domain::config::PluginConfig;
+use crate::domain::plugin::PluginHit;
+use std::time::Duration;
+
+/// Runs the external search plugins declared in the config.
+#[cfg_attr(test, mockall::automock)]
+pub trait PluginService: Send + Sync {
+    /// Starts, stops or restarts plugins to match `plugins`.
+    fn configure(&self, plugins: &[PluginConfig]);
+    /// Asks every enabled plugin, collecting what arrives within each one's timeout.
+    fn query(&self, query: &str) -> Vec<PluginHit>;
+    /// Longest timeout among the enabled plugins.
+    fn timeout(&self) -> Duration;
+    /// Hands the action the user picked back to the plugin that produced the result.
+    fn run_action(&self, plugin: &str, result_id: &str, action: &str) -> Result<(), String>;
+}
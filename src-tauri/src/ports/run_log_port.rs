@@ -0,0 +1,10 @@
+use crate::domain::script_run::RunRecord;
+
+/// Keeps finished script runs around after the terminal view is closed.
+#[cfg_attr(test, mockall::automock)]
+pub trait RunLog: Send + Sync {
+    fn append(&self, record: &RunRecord) -> Result<(), String>;
+    /// Most recent runs first, without their output.
+    fn list(&self, limit: usize) -> Result<Vec<RunRecord>, String>;
+    fn get(&self, run_id: &str) -> Result<Option<RunRecord>, String>;
+}
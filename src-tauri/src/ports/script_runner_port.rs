@@ -0,0 +1,23 @@
+use crate::domain::script_run::{PtySize, RunningScript, ScriptEvent, ScriptRequest};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Starts scripts in the background and keeps track of them until they exit.
+#[cfg_attr(test, mockall::automock)]
+pub trait ScriptRunner: Send + Sync {
+    /// Spawns the script and returns its run id; progress is sent to `events`,
+    /// ending with a `Done` event.
+    fn start(
+        &self,
+        request: ScriptRequest,
+        events: UnboundedSender<ScriptEvent>,
+    ) -> Result<String, String>;
+    /// Signals the run's whole process group.
+    fn kill(&self, run_id: &str) -> Result<(), String>;
+    /// Writes `text` to the run's stdin, or types it into its terminal.
+    fn write_stdin(&self, run_id: &str, text: &str) -> Result<(), String>;
+    /// Sends EOF to the run's stdin.
+    fn close_stdin(&self, run_id: &str) -> Result<(), String>;
+    /// Tells a PTY run its terminal view changed size.
+    fn resize(&self, run_id: &str, size: PtySize) -> Result<(), String>;
+    fn running(&self) -> Vec<RunningScript>;
+}
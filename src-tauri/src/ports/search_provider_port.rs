@@ -0,0 +1,25 @@
+use crate::domain::action::Action;
+use crate::domain::frecency::Frecency;
+use crate::domain::search::SearchResult;
+use std::time::Duration;
+
+/// Everything a provider needs for one search, loaded once by the aggregator.
+#[derive(Debug, Clone, Default)]
+pub struct SearchContext {
+    /// Trimmed query as typed.
+    pub query: String,
+    pub frecency: Frecency,
+    /// Recorded history, most recent first.
+    pub recent: Vec<Action>,
+}
+
+/// One source of unified search results (apps, windows, files, ...).
+///
+/// Providers run concurrently on blocking threads; one that takes longer than
+/// its `budget` is left out of the results rather than holding up the others.
+#[cfg_attr(test, mockall::automock)]
+pub trait SearchProvider: Send + Sync {
+    fn budget(&self) -> Duration;
+    /// Scored (history boost included), but not yet deduplicated or ranked.
+    fn search(&self, context: &SearchContext) -> Result<Vec<SearchResult>, String>;
+}
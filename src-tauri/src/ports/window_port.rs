@@ -1,6 +1,9 @@
 use crate::domain::windows::WindowEntry;
 
+#[cfg_attr(test, mockall::automock)]
 pub trait WindowService: Send + Sync {
     fn list_windows(&self) -> Result<Vec<WindowEntry>, String>;
     fn focus_window(&self, id: &str) -> Result<(), String>;
+    /// Sends Ctrl+V to the focused window.
+    fn paste(&self) -> Result<(), String>;
 }
@@ -0,0 +1,375 @@
+//! Starts scripts that have a `schedule` while the app sits in the tray. Scheduled
+//! runs go through the same path as `execute_script`, so they land in the run log
+//! and get their output action; failures are kept for the tray menu.
+
+use crate::adapters::script_discovery::all_scripts;
+use crate::commands::scripts::{script_request_logic, start_logged_run, with_output_action};
+use crate::domain::config::ScriptConfig;
+use crate::domain::frecency::now_ms;
+use crate::domain::schedule::{LocalTime, Schedule};
+use crate::domain::script_run::{RunStatus, ScriptEvent};
+use crate::ports::config_port::ConfigService;
+use crate::ports::run_log_port::RunLog;
+use crate::ports::script_runner_port::ScriptRunner;
+use crate::ports::window_port::WindowService;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Often enough to see every minute for cron schedules.
+const TICK: Duration = Duration::from_secs(20);
+/// The tray lists this many of the latest failures.
+const MAX_FAILURES: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledFailure {
+    pub script: String,
+    pub run_id: Option<String>,
+    pub message: String,
+    pub at: u64,
+}
+
+struct SchedulerState {
+    started_at: u64,
+    last_runs: HashMap<String, u64>,
+    /// Scripts whose previous scheduled run hasn't finished; they are skipped
+    /// rather than started twice.
+    active: HashSet<String>,
+    failures: Vec<ScheduledFailure>,
+}
+
+type Notify = Arc<dyn Fn() + Send + Sync>;
+
+pub struct ScriptScheduler {
+    config: Arc<dyn ConfigService>,
+    runner: Arc<dyn ScriptRunner>,
+    run_log: Arc<dyn RunLog>,
+    /// For scripts that paste their output.
+    windows: Arc<dyn WindowService>,
+    state: Arc<Mutex<SchedulerState>>,
+    /// Called whenever the failure list changes.
+    on_failure: Mutex<Notify>,
+}
+
+/// The local wall-clock time of a ms timestamp.
+pub fn local_time(ms: u64) -> LocalTime {
+    let secs = (ms / 1000) as libc::time_t;
+    // SAFETY: localtime_r only writes the tm it is given
+    let tm = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&secs, &mut tm);
+        tm
+    };
+    LocalTime {
+        minute: tm.tm_min as u32,
+        hour: tm.tm_hour as u32,
+        day: tm.tm_mday as u32,
+        month: tm.tm_mon as u32 + 1,
+        weekday: tm.tm_wday as u32,
+    }
+}
+
+fn display_name(script: &ScriptConfig) -> String {
+    script.title.clone().unwrap_or_else(|| script.alias.clone())
+}
+
+impl ScriptScheduler {
+    pub fn new(
+        config: Arc<dyn ConfigService>,
+        runner: Arc<dyn ScriptRunner>,
+        run_log: Arc<dyn RunLog>,
+        windows: Arc<dyn WindowService>,
+    ) -> Self {
+        Self {
+            config,
+            runner,
+            run_log,
+            windows,
+            state: Arc::new(Mutex::new(SchedulerState {
+                started_at: now_ms(),
+                last_runs: HashMap::new(),
+                active: HashSet::new(),
+                failures: Vec::new(),
+            })),
+            on_failure: Mutex::new(Arc::new(|| {})),
+        }
+    }
+
+    /// Checks the schedules every `TICK` from now on.
+    pub fn spawn(self: Arc<Self>, on_failure: impl Fn() + Send + Sync + 'static) {
+        *self.on_failure.lock().unwrap() = Arc::new(on_failure);
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let now = now_ms();
+                self.tick(now, &local_time(now));
+                tokio::time::sleep(TICK).await;
+            }
+        });
+    }
+
+    /// Starts every scheduled script that is due; returns how many were started.
+    pub fn tick(&self, now: u64, local: &LocalTime) -> usize {
+        let config = self.config.load_config();
+        let mut started = 0;
+        for script in all_scripts(&config) {
+            // Invalid expressions are skipped; the settings page reports them
+            let Some(Ok(schedule)) = script.schedule.as_ref().map(Schedule::from_config) else {
+                continue;
+            };
+            {
+                let mut state = self.state.lock().unwrap();
+                let last = state
+                    .last_runs
+                    .get(&script.id)
+                    .copied()
+                    .unwrap_or(state.started_at);
+                if state.active.contains(&script.id) || !schedule.is_due(last, now, local) {
+                    continue;
+                }
+                state.last_runs.insert(script.id.clone(), now);
+                state.active.insert(script.id.clone());
+            }
+            if let Err(e) = self.start(&script) {
+                self.finish(&script.id, Some((display_name(&script), None, e)));
+            }
+            started += 1;
+        }
+        started
+    }
+
+    fn start(&self, script: &ScriptConfig) -> Result<String, String> {
+        let mut request =
+            script_request_logic(script, &script.path, script.args.clone(), Vec::new(), None)?;
+        // Nothing in the tray can answer a prompt
+        request.unattended = true;
+
+        let state = self.state.clone();
+        let notify = self.on_failure.lock().unwrap().clone();
+        let id = script.id.clone();
+        let name = display_name(script);
+        let pty = request.pty.is_some();
+        start_logged_run(
+            self.runner.as_ref(),
+            self.run_log.clone(),
+            request,
+            with_output_action(script, pty, self.windows.clone(), move |event| {
+                if let ScriptEvent::Done {
+                    run_id,
+                    code,
+                    status,
+                } = event
+                {
+                    let failure = (*status != RunStatus::Success).then(|| {
+                        let message = status.message(*code);
+                        let message = message.trim_start_matches("> ").trim_end().to_string();
+                        (name.clone(), Some(run_id.clone()), message)
+                    });
+                    finish(&state, &notify, &id, failure);
+                }
+            }),
+        )
+    }
+
+    fn finish(&self, id: &str, failure: Option<(String, Option<String>, String)>) {
+        let notify = self.on_failure.lock().unwrap().clone();
+        finish(&self.state, &notify, id, failure);
+    }
+
+    /// Latest failures first.
+    pub fn failures(&self) -> Vec<ScheduledFailure> {
+        self.state.lock().unwrap().failures.clone()
+    }
+
+    pub fn clear_failures(&self) {
+        self.state.lock().unwrap().failures.clear();
+    }
+}
+
+fn finish(
+    state: &Mutex<SchedulerState>,
+    notify: &Notify,
+    id: &str,
+    failure: Option<(String, Option<String>, String)>,
+) {
+    let mut state = state.lock().unwrap();
+    state.active.remove(id);
+    if let Some((script, run_id, message)) = failure {
+        state.failures.insert(
+            0,
+            ScheduledFailure {
+                script,
+                run_id,
+                message,
+                at: now_ms(),
+            },
+        );
+        state.failures.truncate(MAX_FAILURES);
+        drop(state);
+        notify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::process_script_runner::ProcessScriptRunner;
+    use crate::domain::config::{AppConfig, ScriptSchedule};
+    use crate::ports::config_port::MockConfigService;
+    use crate::ports::run_log_port::MockRunLog;
+    use crate::ports::script_runner_port::MockScriptRunner;
+    use crate::ports::window_port::MockWindowService;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn scheduled(id: &str, schedule: ScriptSchedule) -> ScriptConfig {
+        ScriptConfig {
+            id: id.to_string(),
+            alias: id.to_string(),
+            path: format!("/scripts/{}.sh", id),
+            schedule: Some(schedule),
+            ..Default::default()
+        }
+    }
+
+    fn config() -> MockConfigService {
+        let mut config = MockConfigService::new();
+        config.expect_load_config().returning(|| AppConfig {
+            scripts: vec![
+                scheduled("sync", ScriptSchedule::IntervalMinutes(60)),
+                scheduled("broken", ScriptSchedule::Cron("every day".into())),
+                ScriptConfig {
+                    id: "manual".to_string(),
+                    alias: "manual".to_string(),
+                    path: "/scripts/manual.sh".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        });
+        config
+    }
+
+    fn noon() -> LocalTime {
+        LocalTime {
+            minute: 0,
+            hour: 12,
+            day: 1,
+            month: 1,
+            weekday: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_due_scripts_start_once_and_failures_are_kept() {
+        let mut runner = MockScriptRunner::new();
+        runner.expect_start().times(1).returning(|request, events| {
+            assert_eq!(request.script_id.as_deref(), Some("sync"));
+            events
+                .send(ScriptEvent::Done {
+                    run_id: "run-1".to_string(),
+                    code: Some(1),
+                    status: RunStatus::Failed,
+                })
+                .unwrap();
+            Ok("run-1".to_string())
+        });
+        let mut run_log = MockRunLog::new();
+        run_log.expect_append().returning(|_| Ok(()));
+
+        let scheduler = Arc::new(ScriptScheduler::new(
+            Arc::new(config()),
+            Arc::new(runner),
+            Arc::new(run_log),
+            Arc::new(MockWindowService::new()),
+        ));
+        let notified = Arc::new(AtomicUsize::new(0));
+        let counter = notified.clone();
+        *scheduler.on_failure.lock().unwrap() = Arc::new(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let start = scheduler.state.lock().unwrap().started_at;
+        assert_eq!(scheduler.tick(start + 30 * 60_000, &noon()), 0);
+        assert_eq!(scheduler.tick(start + 60 * 60_000, &noon()), 1);
+
+        // The run reports back on the runtime
+        for _ in 0..100 {
+            if !scheduler.failures().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let failures = scheduler.failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].script, "sync");
+        assert_eq!(failures[0].run_id.as_deref(), Some("run-1"));
+        assert_eq!(failures[0].message, "Failed! (Exit code 1)");
+        assert_eq!(notified.load(Ordering::SeqCst), 1);
+
+        // Not due again until another interval has passed
+        assert_eq!(scheduler.tick(start + 90 * 60_000, &noon()), 0);
+        scheduler.clear_failures();
+        assert!(scheduler.failures().is_empty());
+    }
+
+    #[test]
+    fn test_script_still_running_is_not_started_again() {
+        let mut runner = MockScriptRunner::new();
+        runner.expect_start().never();
+        let scheduler = ScriptScheduler::new(
+            Arc::new(config()),
+            Arc::new(runner),
+            Arc::new(MockRunLog::new()),
+            Arc::new(MockWindowService::new()),
+        );
+        let start = {
+            let mut state = scheduler.state.lock().unwrap();
+            state.active.insert("sync".to_string());
+            state.started_at
+        };
+
+        assert_eq!(scheduler.tick(start + 120 * 60_000, &noon()), 0);
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_script_reading_stdin_finishes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ask.sh");
+        std::fs::write(&path, "read -r answer\necho \"answer: $answer\"\n").unwrap();
+        let script = ScriptConfig {
+            path: path.display().to_string(),
+            // Would get a terminal when run by hand
+            pty: true,
+            ..scheduled("ask", ScriptSchedule::IntervalMinutes(1))
+        };
+        let mut config = MockConfigService::new();
+        config.expect_load_config().returning(move || AppConfig {
+            scripts: vec![script.clone()],
+            ..Default::default()
+        });
+        let (saved_tx, saved_rx) = std::sync::mpsc::channel();
+        let mut run_log = MockRunLog::new();
+        run_log.expect_append().returning(move |record| {
+            saved_tx.send(record.clone()).unwrap();
+            Ok(())
+        });
+
+        let scheduler = ScriptScheduler::new(
+            Arc::new(config),
+            Arc::new(ProcessScriptRunner::new()),
+            Arc::new(run_log),
+            Arc::new(MockWindowService::new()),
+        );
+        let start = scheduler.state.lock().unwrap().started_at;
+        assert_eq!(scheduler.tick(start + 60_000, &noon()), 1);
+
+        let record = tokio::task::spawn_blocking(move || {
+            saved_rx.recv_timeout(std::time::Duration::from_secs(5))
+        })
+        .await
+        .unwrap()
+        .expect("the run should end at EOF");
+        assert_eq!(record.output, "> answer: \n");
+        assert!(scheduler.state.lock().unwrap().active.is_empty());
+        assert!(scheduler.failures().is_empty());
+    }
+}
@@ -1,8 +1,16 @@
+use crate::domain::frecency::HistorySnapshot;
+use crate::domain::search_session::SearchSessions;
 use crate::ports::ai_port::AiService;
 use crate::ports::app_port::AppRepository;
 use crate::ports::config_port::ConfigService;
+use crate::ports::file_index_port::FileIndex;
 use crate::ports::history::HistoryRepository;
 use crate::ports::icon_port::IconResolver;
+use crate::ports::mime_port::MimeResolver;
+use crate::ports::plugin_port::PluginService;
+use crate::ports::run_log_port::RunLog;
+use crate::ports::script_runner_port::ScriptRunner;
+use crate::ports::search_provider_port::SearchProvider;
 use crate::ports::translation_port::TranslationService;
 use crate::ports::window_port::WindowService;
 use std::sync::Arc;
@@ -12,7 +20,16 @@ pub struct AppState {
     pub window_service: Arc<dyn WindowService>,
     pub config_service: Arc<dyn ConfigService>,
     pub icon_resolver: Arc<dyn IconResolver>,
+    pub mime_resolver: Arc<dyn MimeResolver>,
     pub ai_service: Arc<dyn AiService>,
     pub history_repository: Arc<dyn HistoryRepository>,
+    /// What `history_repository` holds, refreshed whenever an action is recorded.
+    pub history_snapshot: Arc<HistorySnapshot>,
     pub translation_service: Arc<dyn TranslationService>,
+    pub file_index: Arc<dyn FileIndex>,
+    pub plugin_service: Arc<dyn PluginService>,
+    pub script_runner: Arc<dyn ScriptRunner>,
+    pub run_log: Arc<dyn RunLog>,
+    pub search_sessions: Arc<SearchSessions>,
+    pub search_providers: Vec<Arc<dyn SearchProvider>>,
 }
@@ -1,8 +1,11 @@
+use crate::scheduler::ScriptScheduler;
 use crate::utils::toggle_main_window;
+use std::sync::Arc;
 
 // Reuse PaletteTray as is.
 pub struct PaletteTray {
     pub handle: tauri::AppHandle,
+    pub scheduler: Arc<ScriptScheduler>,
 }
 
 impl ksni::Tray for PaletteTray {
@@ -69,15 +72,49 @@ impl ksni::Tray for PaletteTray {
 
     fn menu(&self) -> Vec<ksni::MenuItem<Self>> {
         use ksni::menu::*;
-        vec![
-            StandardItem {
-                label: "Show".into(),
-                activate: Box::new(|this: &mut Self| {
-                    toggle_main_window(&this.handle);
-                }),
-                ..Default::default()
-            }
-            .into(),
+        let mut items: Vec<ksni::MenuItem<Self>> = vec![StandardItem {
+            label: "Show".into(),
+            activate: Box::new(|this: &mut Self| {
+                toggle_main_window(&this.handle);
+            }),
+            ..Default::default()
+        }
+        .into()];
+
+        let failures = self.scheduler.failures();
+        if !failures.is_empty() {
+            let mut submenu: Vec<ksni::MenuItem<Self>> = failures
+                .iter()
+                .map(|failure| {
+                    StandardItem {
+                        label: format!("{}: {}", failure.script, failure.message),
+                        enabled: false,
+                        ..Default::default()
+                    }
+                    .into()
+                })
+                .collect();
+            submenu.push(MenuItem::Separator);
+            submenu.push(
+                StandardItem {
+                    label: "Dismiss".into(),
+                    activate: Box::new(|this: &mut Self| this.scheduler.clear_failures()),
+                    ..Default::default()
+                }
+                .into(),
+            );
+            items.push(
+                SubMenu {
+                    label: format!("Failed Scheduled Runs ({})", failures.len()),
+                    icon_name: "dialog-warning".into(),
+                    submenu,
+                    ..Default::default()
+                }
+                .into(),
+            );
+        }
+
+        items.push(
             StandardItem {
                 label: "Quit".into(),
                 activate: Box::new(|this: &mut Self| {
@@ -86,6 +123,7 @@ impl ksni::Tray for PaletteTray {
                 ..Default::default()
             }
             .into(),
-        ]
+        );
+        items
     }
 }
@@ -13,6 +13,16 @@ export const backend = {
         return await invoke('launch_app', { execCmd })
     },
 
+    // Opens files or URLs in a specific app; appId is its desktop-file id
+    async launchAppWith(appId, paths) {
+        return await invoke('launch_app_with', { appId, paths })
+    },
+
+    // { mime_type, default, others } for an "Open with…" list
+    async getOpenWithApps(path) {
+        return await invoke('get_open_with_apps', { path })
+    },
+
     async listApps() {
         return await invoke('list_apps')
     },
@@ -37,8 +47,16 @@ export const backend = {
         return await invoke('list_ollama_models')
     },
 
-    async searchFiles(query, path) {
-        return await invoke('search_files', { query, path })
+    async searchFiles(query, limit) {
+        return await invoke('search_files', { query, limit })
+    },
+
+    async startFileSearch(query, limit) {
+        return await invoke('start_file_search', { query, limit })
+    },
+
+    async cancelFileSearch(searchId) {
+        return await invoke('cancel_file_search', { searchId })
     },
 
     async openEntity(path) {
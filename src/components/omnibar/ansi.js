@@ -0,0 +1,138 @@
+// Renders raw terminal output (from PTY script runs) as HTML: SGR colors and styles
+// become spans, carriage returns overwrite the current line like progress bars
+// expect, and every other escape sequence is dropped.
+
+const PALETTE = [
+  '#1e1e1e', '#ef4444', '#22c55e', '#eab308', '#3b82f6', '#a855f7', '#06b6d4', '#e2e8f0',
+  '#64748b', '#f87171', '#4ade80', '#facc15', '#60a5fa', '#c084fc', '#22d3ee', '#ffffff'
+]
+
+// Index into the xterm 256-color palette
+function color256(n) {
+  if (n < 16) return PALETTE[n]
+  if (n < 232) {
+    const steps = [0, 95, 135, 175, 215, 255]
+    const i = n - 16
+    return `rgb(${steps[Math.floor(i / 36)]}, ${steps[Math.floor(i / 6) % 6]}, ${steps[i % 6]})`
+  }
+  const gray = 8 + (n - 232) * 10
+  return `rgb(${gray}, ${gray}, ${gray})`
+}
+
+function escapeHtml(text) {
+  return text.replace(/&/g, '&amp;').replace(/</g, '&lt;').replace(/>/g, '&gt;')
+}
+
+// Applies one SGR sequence's parameters to the current style
+function applySgr(style, params) {
+  const codes = params.length ? params.split(';').map(Number) : [0]
+  for (let i = 0; i < codes.length; i++) {
+    const code = codes[i]
+    if (code === 0) {
+      style = {}
+    } else if (code === 1) {
+      style = { ...style, bold: true }
+    } else if (code === 3) {
+      style = { ...style, italic: true }
+    } else if (code === 4) {
+      style = { ...style, underline: true }
+    } else if (code === 22) {
+      style = { ...style, bold: false }
+    } else if (code === 23) {
+      style = { ...style, italic: false }
+    } else if (code === 24) {
+      style = { ...style, underline: false }
+    } else if (code >= 30 && code <= 37) {
+      style = { ...style, fg: PALETTE[code - 30] }
+    } else if (code >= 90 && code <= 97) {
+      style = { ...style, fg: PALETTE[code - 90 + 8] }
+    } else if (code >= 40 && code <= 47) {
+      style = { ...style, bg: PALETTE[code - 40] }
+    } else if (code >= 100 && code <= 107) {
+      style = { ...style, bg: PALETTE[code - 100 + 8] }
+    } else if (code === 39) {
+      style = { ...style, fg: null }
+    } else if (code === 49) {
+      style = { ...style, bg: null }
+    } else if (code === 38 || code === 48) {
+      // 38;5;n or 38;2;r;g;b
+      let color = null
+      if (codes[i + 1] === 5) {
+        color = color256(codes[i + 2])
+        i += 2
+      } else if (codes[i + 1] === 2) {
+        color = `rgb(${codes[i + 2]}, ${codes[i + 3]}, ${codes[i + 4]})`
+        i += 4
+      }
+      style = { ...style, [code === 38 ? 'fg' : 'bg']: color }
+    }
+  }
+  return style
+}
+
+function styleCss(style) {
+  const css = []
+  if (style.fg) css.push(`color: ${style.fg}`)
+  if (style.bg) css.push(`background: ${style.bg}`)
+  if (style.bold) css.push('font-weight: bold')
+  if (style.italic) css.push('font-style: italic')
+  if (style.underline) css.push('text-decoration: underline')
+  return css.join('; ')
+}
+
+// Splits output into lines of styled cells, honoring \r and backspace
+function toLines(text) {
+  const lines = [[]]
+  let line = lines[0]
+  let col = 0
+  let style = {}
+  // CSI sequences, OSC sequences (ended by BEL or ST) and lone two-character escapes
+  const pattern = /\x1b\[([0-9;?]*)([A-Za-z])|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b[@-Z\\-_]|([\r\n\b])|([^\x1b\r\n\b]+)/g
+  let match
+  while ((match = pattern.exec(text)) !== null) {
+    const [, params, command, control, chunk] = match
+    if (chunk !== undefined) {
+      for (const char of chunk) {
+        line[col++] = { char, style }
+      }
+    } else if (control === '\n') {
+      line = []
+      lines.push(line)
+      col = 0
+    } else if (control === '\r') {
+      col = 0
+    } else if (control === '\b') {
+      col = Math.max(0, col - 1)
+    } else if (command === 'm') {
+      style = applySgr(style, params)
+    } else if (command === 'K') {
+      // Erase to end of line, as progress bars redraw with \r\x1b[K
+      line.length = col
+    }
+  }
+  return lines
+}
+
+export function ansiToHtml(text) {
+  return toLines(text).map(cells => {
+    let html = ''
+    let run = ''
+    let runStyle = null
+    const flush = () => {
+      if (!run) return
+      const css = styleCss(runStyle || {})
+      html += css ? `<span style="${css}">${escapeHtml(run)}</span>` : escapeHtml(run)
+      run = ''
+    }
+    for (const cell of cells) {
+      const { char, style } = cell || { char: ' ', style: {} }
+      if (style !== runStyle) {
+        flush()
+        runStyle = style
+      }
+      run += char
+    }
+    flush()
+    return html
+  }).join('\n')
+}
@@ -1,8 +1,8 @@
 import { ref, computed, watch, nextTick, shallowRef } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 import { getCurrentWindow, currentMonitor } from '@tauri-apps/api/window'
 import { LogicalSize } from '@tauri-apps/api/dpi'
-import * as path from '@tauri-apps/api/path'
 import { applyTheme } from '../theme'
 import SkillManager from '../skills'
 import { useTheme } from 'vuetify'
@@ -11,6 +11,7 @@ const COLLAPSED_HEIGHT = 100
 const EXPANDED_HEIGHT = 500
 const CHAT_HEIGHT = 600
 const BASE_WIDTH = 600
+const FILE_RESULT_LIMIT = 50
 
 // Singleton state to ensure consistency if shared (though mostly used in App.vue)
 // For now, we'll keep it as a standard composable function, but usually these are singletons in this type of app.
@@ -21,14 +22,58 @@ const uiState = ref('idle') // 'idle', 'searching', 'chatting', 'executing'
 const query = ref('')
 const config = ref(null)
 const apps = shallowRef([]) // use shallowRef for large lists for performance
-const windows = shallowRef([])
 const files = shallowRef([])
+const fileSearchError = ref(null) // Structured query error from search_files ({ code, message, token, position })
+const contentMatches = shallowRef([]) // Lines inside files, from search_content
 const scripts = shallowRef([])
 const recentActions = shallowRef([])
+const searchResults = shallowRef([]) // Ranked apps, windows, scripts and files from the unified `search` command
 const selectedIndex = ref(0)
 const showSettings = ref(false)
 const searchInput = ref(null) // Template ref
 
+// Streaming file search: ids grow with every start_file_search call
+let activeSearchId = 0 // Newest search we started (0 while its id is still unknown)
+let shownSearchId = 0 // Search whose results are currently in `files`
+let fileSearchRunning = false
+let searchListener = null
+
+function ensureSearchListener() {
+    if (searchListener) return
+    searchListener = listen('search-results', (event) => {
+        const { search_id, results, done } = event.payload
+        if (!fileSearchRunning) return // Cancelled, late events may still trickle in
+        if (search_id < activeSearchId || search_id < shownSearchId) return // Stale search
+        if (search_id > shownSearchId) {
+            shownSearchId = search_id
+            files.value = []
+        }
+        if (done) {
+            files.value = results // Final, fully ranked list
+        } else {
+            files.value = [...files.value, ...results]
+                .sort((a, b) => b.score - a.score)
+                .slice(0, FILE_RESULT_LIMIT)
+        }
+    })
+}
+
+// "grep:TODO ext:rs" or "/TODO ext:rs" searches inside files; returns the query part
+export function contentQueryOf(value) {
+    if (!value) return null
+    const trimmed = value.trimStart()
+    if (trimmed.toLowerCase().startsWith('grep:')) return trimmed.substring(5).trim()
+    if (trimmed.startsWith('/')) return trimmed.substring(1).trim()
+    return null
+}
+
+function cancelFileSearch() {
+    if (!fileSearchRunning) return
+    fileSearchRunning = false
+    activeSearchId = 0
+    invoke('cancel_file_search', { searchId: null }).catch(e => console.error(e))
+}
+
 export function useOmnibar() {
     const appWindow = getCurrentWindow()
     const vTheme = useTheme()
@@ -71,8 +116,8 @@ export function useOmnibar() {
                 width = Math.max(1000, Math.floor(width * 1.4)) // Wider for split pane
             } else if (uiState.value === 'searching') {
                 height = EXPANDED_HEIGHT
-                // Check if file search mode
-                if (query.value && query.value.trim().toLowerCase().startsWith('ff ')) {
+                // Check if file or content search mode
+                if (query.value && (query.value.trim().toLowerCase().startsWith('ff ') || contentQueryOf(query.value) !== null)) {
                     // Dual pane width
                     width = Math.max(1000, Math.floor(width * 1.4))
                 }
@@ -112,6 +157,7 @@ export function useOmnibar() {
     async function focusWindow(win) {
         try {
             await invoke('focus_window', { address: win.address })
+            recordAction(win)
             query.value = ''
             await hideWindow()
         } catch (e) {
@@ -131,9 +177,8 @@ export function useOmnibar() {
             }
         }
 
-        if (config.value.scripts) {
-            scripts.value = config.value.scripts
-        }
+        // Configured scripts plus the ones discovered in scripts_dir
+        scripts.value = await invoke('list_scripts').catch(() => config.value.scripts || [])
 
         // Trigger resize in case scale changed
         updateWindowSize()
@@ -156,7 +201,9 @@ export function useOmnibar() {
 
     async function loadRecentActions() {
         try {
-            recentActions.value = await invoke('get_recent_actions', { limit: 20 })
+            // Window switches only feed ranking, they aren't worth a spot in the recent list
+            const actions = await invoke('get_recent_actions', { limit: 40 })
+            recentActions.value = actions.filter(a => a.kind !== 'window').slice(0, 20)
         } catch (e) {
             console.error('Failed to load recent actions', e)
         }
@@ -165,6 +212,8 @@ export function useOmnibar() {
     async function recordAction(item) {
         try {
             if (!item) return;
+            // Read before callers clear the input; the backend learns which result was picked for it
+            const typed = query.value.replace(/^ff /i, '').trim()
 
             let action = {
                 id: '',
@@ -176,7 +225,8 @@ export function useOmnibar() {
             };
 
             if (item.exec) { // App
-                action.id = 'app:' + item.exec;
+                // Keyed by desktop-file id, so history survives the exec line changing
+                action.id = 'app:' + (item.id || item.exec);
                 action.kind = 'app';
                 action.content = item.exec;
                 action.name = item.name;
@@ -187,11 +237,12 @@ export function useOmnibar() {
                 action.content = item.path;
                 action.name = item.alias;
             } else if (item.address) { // Window
-                // We might not want to record window switching as persistent "Action" 
-                // effectively, but user asked for "recent selected actions".
-                // Window switching is ephemeral.
-                // Let's exclude window switching for now unless requested.
-                return;
+                // Keyed by class so history survives the window being closed and reopened
+                action.id = 'window:' + item.class;
+                action.kind = 'window';
+                action.content = item.class;
+                action.name = item.title;
+                action.icon = item.icon;
             } else if (typeof item === 'string') { // File path
                 action.id = 'file:' + item;
                 action.kind = 'file';
@@ -206,7 +257,7 @@ export function useOmnibar() {
                 return; // Unknown
             }
 
-            await invoke('record_action', { action })
+            await invoke('record_action', { action, query: typed || null })
             // Refresh
             loadRecentActions()
         } catch (e) {
@@ -214,6 +265,33 @@ export function useOmnibar() {
         }
     }
 
+    // Apps, windows, scripts and files come back as one ranked list; the sections below split it by kind
+    async function runSearch(q) {
+        // `ff ` and content queries run their own file searches
+        if (!q.trim() || q.toLowerCase().startsWith('ff ') || contentQueryOf(q)) {
+            searchResults.value = []
+            return
+        }
+        try {
+            const results = await invoke('search', { query: q, limit: 40 })
+            if (query.value !== q) return // Superseded while we waited
+            searchResults.value = results
+            if (!matchedTool.value) {
+                const hasMatches = results.some(r => ['window', 'app', 'script', 'plugin', 'script_item'].includes(r.kind))
+                selectedIndex.value = hasMatches ? 1 : 0
+            }
+            files.value = results
+                .filter(r => r.kind === 'file' || r.kind === 'folder')
+                .map(r => {
+                    // Highlights are relative to the file name; shift them onto the full path
+                    const offset = [...r.target].length - [...r.title].length
+                    return { path: r.target, is_dir: r.kind === 'folder', score: r.score, positions: r.positions.map(p => p + offset) }
+                })
+        } catch (e) {
+            console.error('Search failed', e)
+        }
+    }
+
     // --- Computed Props ---
 
     const matchedTool = computed(() => {
@@ -225,8 +303,9 @@ export function useOmnibar() {
         if (shortcuts[q]) {
             const targetId = shortcuts[q]
             if (targetId.startsWith('app:')) {
-                const exec = targetId.substring(4)
-                const app = apps.value.find(a => a.exec === exec)
+                // Desktop-file id; shortcuts saved before apps had ids name the exec line
+                const key = targetId.substring(4)
+                const app = apps.value.find(a => a.id === key || a.exec === key)
                 if (app) {
                     return {
                         type: 'app',
@@ -284,101 +363,42 @@ export function useOmnibar() {
         return null
     })
 
+    const resultsOfKind = (kind) => searchResults.value.filter(r => r.kind === kind)
+
     const filteredWindows = computed(() => {
         if (!query.value) return []
-        return windows.value.filter(w =>
-            w.title.toLowerCase().includes(query.value.toLowerCase()) ||
-            w.class.toLowerCase().includes(query.value.toLowerCase())
-        ).slice(0, 5)
+        return resultsOfKind('window')
+            .map(r => ({ title: r.title, class: r.subtitle, address: r.target, icon: r.icon }))
+            .slice(0, 5)
     })
 
     const filteredApps = computed(() => {
         if (!query.value) return []
-        const q = query.value.toLowerCase()
-
-        // 1. Filter
-        let matches = apps.value.filter(app =>
-            app.name.toLowerCase().includes(q) ||
-            app.exec.toLowerCase().includes(q)
-        )
-
-        // 2. Rank using history
-        const recentMap = new Map()
-        recentActions.value.forEach((action, index) => {
-            if (action.kind === 'app') {
-                recentMap.set(action.content, 10000 - index)
-            }
-        })
-
-        matches.sort((a, b) => {
-            const scoreA = recentMap.get(a.exec) || 0
-            const scoreB = recentMap.get(b.exec) || 0
-            if (scoreA !== scoreB) return scoreB - scoreA // Descending score
-
-            // Secondary sort: Starts with query?
-            const aStarts = a.name.toLowerCase().startsWith(q)
-            const bStarts = b.name.toLowerCase().startsWith(q)
-            if (aStarts && !bStarts) return -1
-            if (!aStarts && bStarts) return 1
-
-            return a.name.localeCompare(b.name)
-        })
-
-        return matches.slice(0, 5)
+        return resultsOfKind('app')
+            // Result ids are `app:` plus the desktop-file id
+            .map(r => ({ id: r.id.slice(4), name: r.title, exec: r.target, icon: r.icon }))
+            .slice(0, 5)
     })
 
     const filteredScripts = computed(() => {
         if (!query.value) return scripts.value
-        const q = query.value.toLowerCase()
-
-        let matches = scripts.value.filter(s => s.alias.toLowerCase().includes(q))
-
-        const recentMap = new Map()
-        recentActions.value.forEach((action, index) => {
-            if (action.kind === 'script') {
-                // script actions store path in content, or unique alias?
-                // logic in recordAction used item.path for content.
-                // Assuming reliable mapping.
-                recentMap.set(action.content, 10000 - index)
-            }
-        })
-
-        matches.sort((a, b) => {
-            const scoreA = recentMap.get(a.path) || 0
-            const scoreB = recentMap.get(b.path) || 0
-            if (scoreA !== scoreB) return scoreB - scoreA
-            return a.alias.localeCompare(b.alias)
-        })
+        // Prefer the configured entry, it carries the script's arguments
+        return resultsOfKind('script')
+            .map(r => scripts.value.find(s => s.path === r.target) || { alias: r.title, path: r.target })
+    })
 
-        return matches
+    const filteredItems = computed(() => {
+        if (!query.value) return []
+        return searchResults.value.filter(r => r.kind === 'plugin' || r.kind === 'script_item').slice(0, 8)
     })
 
     // Watchers
     watch(query, (newVal) => {
-        // Smart selection
-        if (matchedTool.value) {
-            selectedIndex.value = 0
-        } else {
-            const hasWindows = windows.value.some(w =>
-                w.title.toLowerCase().includes(newVal.toLowerCase()) ||
-                w.class.toLowerCase().includes(newVal.toLowerCase())
-            );
-            const hasApps = apps.value.some(app =>
-                app.name.toLowerCase().includes(newVal.toLowerCase()) ||
-                app.exec.toLowerCase().includes(newVal.toLowerCase())
-            );
-            const hasScripts = scripts.value.some(s => s.alias.toLowerCase().includes(newVal.toLowerCase()));
-
-            if (hasWindows || hasApps || hasScripts) {
-                selectedIndex.value = 1
-            } else {
-                selectedIndex.value = 0
-            }
-        }
+        // Smart selection; runSearch moves it onto the first match once results arrive
+        selectedIndex.value = 0
 
         if (newVal && uiState.value === 'idle') {
             uiState.value = 'searching'
-            invoke('list_windows').then(w => windows.value = w).catch(e => console.error(e))
             updateWindowSize()
         } else if (!newVal && (uiState.value === 'searching' || uiState.value === 'translating')) {
             uiState.value = 'idle'
@@ -397,29 +417,67 @@ export function useOmnibar() {
             updateWindowSize()
         }
 
+        runSearch(newVal)
+
+        // Content search
+        const contentQuery = contentQueryOf(newVal)
+        clearTimeout(window.contentSearchTimeout)
+        if (!contentQuery) {
+            contentMatches.value = []
+        } else {
+            window.contentSearchTimeout = setTimeout(async () => {
+                try {
+                    contentMatches.value = await invoke('search_content', { query: contentQuery, limit: FILE_RESULT_LIMIT })
+                    fileSearchError.value = null
+                } catch (e) {
+                    contentMatches.value = []
+                    if (e && e.code) {
+                        fileSearchError.value = e
+                    } else {
+                        console.error(e)
+                    }
+                }
+            }, 250)
+            cancelFileSearch()
+            files.value = []
+            return
+        }
+
         // File search
         if (!newVal || !newVal.toLowerCase().startsWith('ff ')) {
+            clearTimeout(window.searchTimeout)
+            cancelFileSearch()
             files.value = []
+            fileSearchError.value = null
         } else {
             const fileQuery = newVal.substring(3).trim()
             if (!fileQuery) {
+                clearTimeout(window.searchTimeout)
+                cancelFileSearch()
                 files.value = []
+                fileSearchError.value = null
                 return
             }
             clearTimeout(window.searchTimeout)
             window.searchTimeout = setTimeout(async () => {
+                ensureSearchListener()
                 try {
-                    const home = await path.homeDir()
-                    const includeHidden = config.value?.file_search?.include_hidden || false
-                    files.value = await invoke('search_files', {
-                        query: fileQuery,
-                        path: home,
-                        includeHidden
-                    })
+                    // Results stream in through `search-results`; starting a search cancels the previous one
+                    fileSearchRunning = true
+                    activeSearchId = 0
+                    activeSearchId = await invoke('start_file_search', { query: fileQuery, limit: FILE_RESULT_LIMIT })
+                    fileSearchError.value = null
                 } catch (e) {
-                    console.error(e)
+                    if (e && e.code) {
+                        // Invalid filter syntax, shown inline in the results list
+                        cancelFileSearch()
+                        files.value = []
+                        fileSearchError.value = e
+                    } else {
+                        console.error(e)
+                    }
                 }
-            }, 300)
+            }, 150)
         }
     })
 
@@ -438,8 +496,9 @@ export function useOmnibar() {
         query,
         config,
         apps,
-        windows,
         files,
+        fileSearchError,
+        contentMatches,
         scripts,
         selectedIndex,
         showSettings,
@@ -450,6 +509,7 @@ export function useOmnibar() {
         filteredWindows,
         filteredApps,
         filteredScripts,
+        filteredItems,
 
         // Actions
         updateWindowSize,
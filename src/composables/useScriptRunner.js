@@ -3,11 +3,28 @@ import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
 import { useOmnibar } from './useOmnibar'
 
+// Cell size of the terminal view's 13px monospace font
+const CHAR_WIDTH = 7.8
+const LINE_HEIGHT = 19.5
+const TERMINAL_PADDING = 32
+
+// Rows and columns that fit in an element (or the window, before the view is mounted)
+export function terminalSize(el) {
+    const width = (el ? el.clientWidth : window.innerWidth) - TERMINAL_PADDING
+    const height = (el ? el.clientHeight : window.innerHeight) - TERMINAL_PADDING
+    return {
+        cols: Math.max(20, Math.floor(width / CHAR_WIDTH)),
+        rows: Math.max(5, Math.floor(height / LINE_HEIGHT))
+    }
+}
+
 // Singleton State
 const scriptOutput = ref('')
 const scriptRunning = ref(false)
 const scriptError = ref(null)
 const currentScript = ref(null)
+const currentRunId = ref(null) // Events from other runs (e.g. one still going in the background) are ignored
+const currentCommand = ref(null) // What the backend actually executed: interpreter, script and arguments
 
 export function useScriptRunner() {
     const { uiState, updateWindowSize } = useOmnibar()
@@ -17,14 +34,17 @@ export function useScriptRunner() {
     let unlisteners = []
 
     async function setupScriptListeners() {
-        unlisteners.push(await listen('script-start', () => {
+        unlisteners.push(await listen('script-start', (event) => {
+            currentRunId.value = event.payload.run_id
+            currentCommand.value = event.payload
             scriptRunning.value = true
             scriptOutput.value = ''
             scriptError.value = null
         }))
 
         unlisteners.push(await listen('script-output', (event) => {
-            scriptOutput.value += event.payload
+            if (event.payload.run_id !== currentRunId.value) return
+            scriptOutput.value += event.payload.text
             nextTick(() => {
                 if (terminalOutputRef.value) {
                     terminalOutputRef.value.scrollTop = terminalOutputRef.value.scrollHeight
@@ -32,7 +52,8 @@ export function useScriptRunner() {
             })
         }))
 
-        unlisteners.push(await listen('script-done', () => {
+        unlisteners.push(await listen('script-done', (event) => {
+            if (event.payload.run_id !== currentRunId.value) return
             scriptRunning.value = false
         }))
     }
@@ -42,16 +63,32 @@ export function useScriptRunner() {
         unlisteners = []
     }
 
-    async function executeScript(script) {
+    // `mode` comes from the script's metadata header: silent scripts run without the
+    // terminal view, compact and inline ones only show their last line of output.
+    // Scripts with an `output_mode` other than terminal hand their result to the
+    // backend (clipboard, opener, notification, paste) and skip the view as well
+    async function executeScript(script, values = []) {
+        const { recordAction, hideWindow } = useOmnibar()
+        const background = script.mode === 'silent' || (script.output_mode || 'terminal') !== 'terminal'
         try {
             currentScript.value = script
-            uiState.value = 'executing'
+            currentCommand.value = null
             scriptError.value = null
-            updateWindowSize()
+            if (!background) {
+                uiState.value = 'executing'
+                updateWindowSize()
+            }
 
-            await invoke('execute_script', { path: script.path, args: script.args })
-            const { recordAction } = useOmnibar()
+            currentRunId.value = await invoke('execute_script', {
+                path: script.path,
+                args: script.args,
+                arguments: values,
+                size: script.pty ? terminalSize() : null
+            })
             recordAction(script)
+            if (background) {
+                await hideWindow()
+            }
 
         } catch (e) {
             console.error(e)
@@ -60,6 +97,61 @@ export function useScriptRunner() {
         }
     }
 
+    // Stops the current run and everything it started
+    async function killScript() {
+        if (!scriptRunning.value || !currentRunId.value) return
+        try {
+            await invoke('kill_script', { runId: currentRunId.value })
+        } catch (e) {
+            console.error(e)
+        }
+    }
+
+    // Runs the finished invocation shown in the terminal again with the same arguments
+    async function rerunScript() {
+        if (scriptRunning.value || !currentRunId.value) return
+        try {
+            scriptError.value = null
+            currentRunId.value = await invoke('rerun_script', {
+                runId: currentRunId.value,
+                size: currentScript.value?.pty ? terminalSize() : null
+            })
+        } catch (e) {
+            console.error(e)
+            scriptError.value = e
+        }
+    }
+
+    // Text typed into the terminal view, e.g. the answer to a [y/N] prompt
+    async function sendInput(text) {
+        if (!scriptRunning.value || !currentRunId.value) return
+        try {
+            await invoke('write_script_stdin', { runId: currentRunId.value, text })
+        } catch (e) {
+            console.error(e)
+        }
+    }
+
+    // Ctrl+D: scripts reading their input until EOF can finish
+    async function closeInput() {
+        if (!scriptRunning.value || !currentRunId.value) return
+        try {
+            await invoke('close_script_stdin', { runId: currentRunId.value })
+        } catch (e) {
+            console.error(e)
+        }
+    }
+
+    // PTY runs are told when the terminal view changes size
+    async function resizeTerminal(size) {
+        if (!scriptRunning.value || !currentRunId.value || !currentScript.value?.pty) return
+        try {
+            await invoke('resize_script', { runId: currentRunId.value, size })
+        } catch (e) {
+            console.error(e)
+        }
+    }
+
     function closeTerminal() {
         uiState.value = 'idle'
         updateWindowSize()
@@ -70,10 +162,16 @@ export function useScriptRunner() {
         scriptRunning,
         scriptError,
         currentScript,
+        currentCommand,
         terminalOutputRef, // expose for template ref
         setupScriptListeners,
         cleanupScriptListeners,
         executeScript,
+        killScript,
+        rerunScript,
+        sendInput,
+        closeInput,
+        resizeTerminal,
         closeTerminal
     }
 }